@@ -2,21 +2,30 @@ use anyhow::Result;
 use clap::App;
 use clap::AppSettings;
 use delphinus_zkwasm::circuits::config::MIN_K;
+use delphinus_zkwasm::loader::chain::ChainStepArg;
+use delphinus_zkwasm::runtime::host::record::HostCallMode;
+use delphinus_zkwasm::runtime::host::record::HostCallRecord;
 use log::info;
 use log::warn;
+use serde::Deserialize;
+use std::collections::VecDeque;
 use std::fs;
 use std::io::Write;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 
+use crate::args::parse_args;
 use crate::exec::exec_dry_run;
 
 use super::command::CommandBuilder;
 use super::exec::exec_aggregate_create_proof;
+use super::exec::exec_create_chain_proof;
 use super::exec::exec_create_proof;
+use super::exec::exec_debug;
 use super::exec::exec_dry_run_service;
 use super::exec::exec_image_checksum;
+use super::exec::exec_profile;
 use super::exec::exec_setup;
 use super::exec::exec_solidity_aggregate_proof;
 use super::exec::exec_verify_aggregate_proof;
@@ -52,6 +61,53 @@ pub fn write_context_output(
     Ok(())
 }
 
+#[derive(Deserialize)]
+struct ChainStepInputs {
+    #[serde(default)]
+    public: Vec<String>,
+    #[serde(default)]
+    private: Vec<String>,
+}
+
+pub fn load_chain_steps(path: &PathBuf) -> Result<Vec<ChainStepArg>> {
+    let steps: Vec<ChainStepInputs> = serde_json::from_slice(&fs::read(path)?)?;
+
+    Ok(steps
+        .into_iter()
+        .map(|step| ChainStepArg {
+            public_inputs: parse_args(step.public.iter().map(|v| v.as_str()).collect()),
+            private_inputs: parse_args(step.private.iter().map(|v| v.as_str()).collect()),
+        })
+        .collect())
+}
+
+pub fn load_host_call_mode(
+    record_path: Option<&PathBuf>,
+    replay_path: Option<&PathBuf>,
+) -> Result<HostCallMode> {
+    if let Some(path) = replay_path {
+        let records: VecDeque<HostCallRecord> = serde_json::from_slice(&fs::read(path)?)?;
+
+        Ok(HostCallMode::Replay(records))
+    } else if record_path.is_some() {
+        Ok(HostCallMode::Record(Arc::new(Mutex::new(vec![]))))
+    } else {
+        Ok(HostCallMode::Native)
+    }
+}
+
+pub fn write_host_call_records(
+    host_call_mode: &HostCallMode,
+    record_path: Option<PathBuf>,
+) -> Result<()> {
+    if let (HostCallMode::Record(records), Some(path)) = (host_call_mode, record_path) {
+        let fd = fs::File::create(path.as_path())?;
+        serde_json::to_writer_pretty(fd, &*records.lock().unwrap())?;
+    }
+
+    Ok(())
+}
+
 pub trait AppBuilder: CommandBuilder {
     const NAME: &'static str;
     const VERSION: &'static str;
@@ -67,13 +123,22 @@ pub trait AppBuilder: CommandBuilder {
             .arg(Self::output_path_arg())
             .arg(Self::function_name_arg())
             .arg(Self::phantom_functions_arg())
+            .arg(Self::dump_dir_arg())
+            .arg(Self::dump_tables_arg())
+            .arg(Self::dump_format_arg())
+            .arg(Self::step_limit_arg())
+            .arg(Self::public_input_digest_arg())
+            .arg(Self::opcode_set_from_image_arg())
             .arg(Self::zkwasm_file_arg());
 
         let app = Self::append_setup_subcommand(app);
         let app = Self::append_dry_run_subcommand(app);
+        let app = Self::append_profile_subcommand(app);
+        let app = Self::append_debug_subcommand(app);
         let app = Self::append_create_single_proof_subcommand(app);
         let app = Self::append_verify_single_proof_subcommand(app);
         let app = Self::append_create_aggregate_proof_subcommand(app);
+        let app = Self::append_create_chain_proof_subcommand(app);
         let app = Self::append_verify_aggregate_verify_subcommand(app);
         let app = Self::append_generate_solidity_verifier(app);
         let app = Self::append_image_checksum_subcommand(app);
@@ -96,6 +161,11 @@ pub trait AppBuilder: CommandBuilder {
 
         let md5 = format!("{:X}", md5::compute(&wasm_binary));
         let phantom_functions = Self::parse_phantom_functions(&top_matches);
+        let table_dump = Self::parse_table_dump_args(&top_matches);
+        let step_limit = Self::parse_step_limit_arg(&top_matches);
+
+        let public_input_digest = Self::parse_public_input_digest_arg(&top_matches);
+        let opcode_set_from_image = Self::parse_opcode_set_from_image_arg(&top_matches);
 
         let output_dir =
             load_or_generate_output_path(&md5, top_matches.get_one::<PathBuf>("output"));
@@ -108,6 +178,8 @@ pub trait AppBuilder: CommandBuilder {
                 Self::NAME,
                 wasm_binary,
                 phantom_functions,
+                public_input_digest,
+                opcode_set_from_image,
                 &output_dir,
             ),
             Some(("checksum", _)) => {
@@ -119,6 +191,10 @@ pub trait AppBuilder: CommandBuilder {
                 let context_in: Vec<u64> = Self::parse_context_in_arg(&sub_matches);
                 let context_out_path: Option<PathBuf> =
                     Self::parse_context_out_path_arg(&sub_matches);
+                let record_path: Option<PathBuf> =
+                    Self::parse_record_host_calls_path_arg(&sub_matches);
+                let replay_path: Option<PathBuf> =
+                    Self::parse_replay_host_calls_path_arg(&sub_matches);
                 let service_mode = Self::parse_dry_run_service_arg(&sub_matches);
 
                 if let Some(listen) = service_mode {
@@ -135,6 +211,8 @@ pub trait AppBuilder: CommandBuilder {
                     assert!(public_inputs.len() <= Self::MAX_PUBLIC_INPUT_SIZE);
 
                     let context_output = Arc::new(Mutex::new(vec![]));
+                    let host_call_mode =
+                        load_host_call_mode(record_path.as_ref(), replay_path.as_ref())?;
 
                     exec_dry_run(
                         zkwasm_k,
@@ -144,13 +222,71 @@ pub trait AppBuilder: CommandBuilder {
                         private_inputs,
                         context_in,
                         context_output.clone(),
+                        host_call_mode.clone(),
                     )?;
 
                     write_context_output(&context_output.lock().unwrap(), context_out_path)?;
+                    write_host_call_records(&host_call_mode, record_path)?;
 
                     Ok(())
                 }
             }
+            Some(("profile", sub_matches)) => {
+                let public_inputs: Vec<u64> = Self::parse_single_public_arg(&sub_matches);
+                let private_inputs: Vec<u64> = Self::parse_single_private_arg(&sub_matches);
+                let context_in: Vec<u64> = Self::parse_context_in_arg(&sub_matches);
+                let replay_path: Option<PathBuf> =
+                    Self::parse_replay_host_calls_path_arg(&sub_matches);
+
+                let host_call_mode = load_host_call_mode(None, replay_path.as_ref())?;
+
+                assert!(public_inputs.len() <= Self::MAX_PUBLIC_INPUT_SIZE);
+
+                exec_profile(
+                    Self::NAME,
+                    zkwasm_k,
+                    wasm_binary,
+                    phantom_functions,
+                    table_dump,
+                    step_limit,
+                    &output_dir,
+                    public_inputs,
+                    private_inputs,
+                    context_in,
+                    Arc::new(Mutex::new(vec![])),
+                    host_call_mode,
+                )
+            }
+            Some(("debug", sub_matches)) => {
+                let public_inputs: Vec<u64> = Self::parse_single_public_arg(&sub_matches);
+                let private_inputs: Vec<u64> = Self::parse_single_private_arg(&sub_matches);
+                let context_in: Vec<u64> = Self::parse_context_in_arg(&sub_matches);
+                let replay_path: Option<PathBuf> =
+                    Self::parse_replay_host_calls_path_arg(&sub_matches);
+                let load_trace_path: Option<PathBuf> =
+                    Self::parse_load_trace_path_arg(&sub_matches);
+                let save_trace_path: Option<PathBuf> =
+                    Self::parse_save_trace_path_arg(&sub_matches);
+
+                let host_call_mode = load_host_call_mode(None, replay_path.as_ref())?;
+
+                assert!(public_inputs.len() <= Self::MAX_PUBLIC_INPUT_SIZE);
+
+                exec_debug(
+                    zkwasm_k,
+                    wasm_binary,
+                    phantom_functions,
+                    table_dump,
+                    step_limit,
+                    public_inputs,
+                    private_inputs,
+                    context_in,
+                    Arc::new(Mutex::new(vec![])),
+                    host_call_mode,
+                    load_trace_path,
+                    save_trace_path,
+                )
+            }
             Some(("single-prove", sub_matches)) => {
                 let public_inputs: Vec<u64> = Self::parse_single_public_arg(&sub_matches);
                 let private_inputs: Vec<u64> = Self::parse_single_private_arg(&sub_matches);
@@ -158,7 +294,16 @@ pub trait AppBuilder: CommandBuilder {
                 let context_out_path: Option<PathBuf> =
                     Self::parse_context_out_path_arg(&sub_matches);
 
+                let record_path: Option<PathBuf> =
+                    Self::parse_record_host_calls_path_arg(&sub_matches);
+                let replay_path: Option<PathBuf> =
+                    Self::parse_replay_host_calls_path_arg(&sub_matches);
+                let time_profile_path: Option<PathBuf> =
+                    Self::parse_time_profile_path_arg(&sub_matches);
+
                 let context_out = Arc::new(Mutex::new(vec![]));
+                let host_call_mode =
+                    load_host_call_mode(record_path.as_ref(), replay_path.as_ref())?;
 
                 assert!(public_inputs.len() <= Self::MAX_PUBLIC_INPUT_SIZE);
 
@@ -167,14 +312,21 @@ pub trait AppBuilder: CommandBuilder {
                     zkwasm_k,
                     wasm_binary,
                     phantom_functions,
+                    public_input_digest,
+                    opcode_set_from_image,
+                    table_dump,
+                    step_limit,
                     &output_dir,
                     public_inputs,
                     private_inputs,
                     context_in,
                     context_out.clone(),
+                    host_call_mode.clone(),
+                    time_profile_path,
                 )?;
 
                 write_context_output(&context_out.lock().unwrap(), context_out_path)?;
+                write_host_call_records(&host_call_mode, record_path)?;
 
                 Ok(())
             }
@@ -187,6 +339,8 @@ pub trait AppBuilder: CommandBuilder {
                     zkwasm_k,
                     wasm_binary,
                     phantom_functions,
+                    public_input_digest,
+                    opcode_set_from_image,
                     &output_dir,
                     &proof_path,
                     &instance_path,
@@ -215,6 +369,10 @@ pub trait AppBuilder: CommandBuilder {
                     Self::NAME,
                     wasm_binary,
                     phantom_functions,
+                    public_input_digest,
+                    opcode_set_from_image,
+                    table_dump,
+                    step_limit,
                     &output_dir,
                     public_inputs,
                     private_inputs,
@@ -223,6 +381,41 @@ pub trait AppBuilder: CommandBuilder {
                 )
             }
 
+            Some(("chain", sub_matches)) => {
+                let steps_path: PathBuf = Self::parse_chain_steps_path_arg(&sub_matches);
+                let context_in: Vec<u64> = Self::parse_context_in_arg(&sub_matches);
+                let context_out_path: Option<PathBuf> =
+                    Self::parse_context_out_path_arg(&sub_matches);
+                let mock = Self::parse_chain_mock_arg(&sub_matches);
+
+                let steps = load_chain_steps(&steps_path)?;
+                let context_out = Arc::new(Mutex::new(vec![]));
+
+                for step in &steps {
+                    assert!(step.public_inputs.len() <= Self::MAX_PUBLIC_INPUT_SIZE);
+                }
+
+                exec_create_chain_proof(
+                    zkwasm_k,
+                    Self::NAME,
+                    wasm_binary,
+                    phantom_functions,
+                    public_input_digest,
+                    opcode_set_from_image,
+                    table_dump,
+                    step_limit,
+                    &output_dir,
+                    steps,
+                    context_in,
+                    context_out.clone(),
+                    mock,
+                )?;
+
+                write_context_output(&context_out.lock().unwrap(), context_out_path)?;
+
+                Ok(())
+            }
+
             Some(("aggregate-verify", sub_matches)) => {
                 let proof_path: PathBuf = Self::parse_proof_path_arg(&sub_matches);
                 let instances_path: PathBuf = Self::parse_aggregate_instance(&sub_matches);
@@ -5,6 +5,9 @@ use clap::value_parser;
 use clap::Arg;
 use clap::ArgAction;
 use clap::ArgMatches;
+use specs::dump::TableDumpFormat;
+use specs::dump::TableDumpOption;
+use specs::dump::TableKind;
 
 pub fn parse_args(values: Vec<&str>) -> Vec<u64> {
     values
@@ -99,7 +102,7 @@ pub trait ArgBuilder {
             .long("phantom")
             .value_parser(value_parser!(String))
             .action(ArgAction::Append)
-            .help("Specify phantom functions.")
+            .help("Specify phantom functions by name, glob pattern (* and ?) or regex prefixed by re:.")
             .min_values(0)
     }
     fn parse_phantom_functions(matches: &ArgMatches) -> Vec<String> {
@@ -117,6 +120,76 @@ pub trait ArgBuilder {
         ).value_parser(value_parser!(PathBuf))
     }
 
+    fn dump_dir_arg<'a>() -> Arg<'a> {
+        arg!(
+            --dump_dir [DUMP_DIR] "Directory to dump tables after execution, nothing is dumped if not supplied."
+        )
+        .value_parser(value_parser!(PathBuf))
+    }
+
+    fn dump_tables_arg<'a>() -> Arg<'a> {
+        Arg::new("dump_tables")
+            .long("dump_tables")
+            .value_parser(|s: &str| s.parse::<TableKind>())
+            .use_value_delimiter(true)
+            .action(ArgAction::Append)
+            .requires("dump_dir")
+            .help("Tables to dump, one of itable|imtable|etable|mtable|jtable|external_host_table. All tables are dumped if not supplied.")
+    }
+
+    fn dump_format_arg<'a>() -> Arg<'a> {
+        arg!(
+            --dump_format [DUMP_FORMAT] "Format of dumped tables, one of json|jsonl|binary. json is the default."
+        )
+        .value_parser(|s: &str| s.parse::<TableDumpFormat>())
+        .requires("dump_dir")
+    }
+
+    fn parse_table_dump_args(matches: &ArgMatches) -> Option<TableDumpOption> {
+        matches.get_one::<PathBuf>("dump_dir").map(|dir| {
+            let mut option = TableDumpOption::new(dir.clone());
+
+            if let Some(tables) = matches.get_many::<TableKind>("dump_tables") {
+                option.tables = tables.cloned().collect();
+            }
+            if let Some(format) = matches.get_one::<TableDumpFormat>("dump_format") {
+                option.format = *format;
+            }
+
+            option
+        })
+    }
+
+    fn step_limit_arg<'a>() -> Arg<'a> {
+        arg!(
+            --step_limit [STEP_LIMIT] "Maximal number of instructions to trace, derived from K if not supplied."
+        )
+        .value_parser(value_parser!(usize))
+    }
+    fn parse_step_limit_arg(matches: &ArgMatches) -> Option<usize> {
+        matches.get_one::<usize>("step_limit").cloned()
+    }
+
+    fn public_input_digest_arg<'a>() -> Arg<'a> {
+        arg!(
+            --public_input_digest "Expose the digest of the public inputs and outputs as the only public instance."
+        )
+        .takes_value(false)
+    }
+    fn parse_public_input_digest_arg(matches: &ArgMatches) -> bool {
+        matches.contains_id("public_input_digest")
+    }
+
+    fn opcode_set_from_image_arg<'a>() -> Arg<'a> {
+        arg!(
+            --opcode_set_from_image "Configure only the opcode classes used by the image for a smaller circuit."
+        )
+        .takes_value(false)
+    }
+    fn parse_opcode_set_from_image_arg(matches: &ArgMatches) -> bool {
+        matches.contains_id("opcode_set_from_image")
+    }
+
     fn proof_path_arg<'a>() -> Arg<'a> {
         arg!(
             -p --proof <PROOF_PATH> "Path of proof."
@@ -204,6 +277,81 @@ pub trait ArgBuilder {
         matches.get_one::<PathBuf>("ctxout").cloned()
     }
 
+    fn chain_steps_path_arg<'a>() -> Arg<'a> {
+        arg!(
+            --steps <STEPS_PATH> "Path of a JSON array of steps, each step is an object with optional public and private arguments of format value:type."
+        )
+        .value_parser(value_parser!(PathBuf))
+    }
+    fn parse_chain_steps_path_arg(matches: &ArgMatches) -> PathBuf {
+        matches
+            .get_one::<PathBuf>("steps")
+            .expect("steps is required.")
+            .clone()
+    }
+
+    fn chain_mock_arg<'a>() -> Arg<'a> {
+        arg!(
+            --mock "Mock test each step before proving it."
+        )
+        .takes_value(false)
+    }
+    fn parse_chain_mock_arg(matches: &ArgMatches) -> bool {
+        matches.contains_id("mock")
+    }
+
+    fn record_host_calls_path_arg<'a>() -> Arg<'a> {
+        arg!(
+            --record_host_calls [RECORD_PATH] "Path to record invocations of external host functions."
+        )
+        .value_parser(value_parser!(PathBuf))
+        .conflicts_with("replay_host_calls")
+    }
+    fn parse_record_host_calls_path_arg(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("record_host_calls").cloned()
+    }
+
+    fn replay_host_calls_path_arg<'a>() -> Arg<'a> {
+        arg!(
+            --replay_host_calls [REPLAY_PATH] "Path of recorded invocations of external host functions to replay."
+        )
+        .value_parser(value_parser!(PathBuf))
+    }
+    fn parse_replay_host_calls_path_arg(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("replay_host_calls").cloned()
+    }
+
+    fn load_trace_path_arg<'a>() -> Arg<'a> {
+        arg!(
+            --load_trace [TRACE_PATH] "Path of a saved execution trace to debug instead of executing the image."
+        )
+        .value_parser(value_parser!(PathBuf))
+        .conflicts_with("save_trace")
+    }
+    fn parse_load_trace_path_arg(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("load_trace").cloned()
+    }
+
+    fn save_trace_path_arg<'a>() -> Arg<'a> {
+        arg!(
+            --save_trace [TRACE_PATH] "Path to save the execution trace for later debugging."
+        )
+        .value_parser(value_parser!(PathBuf))
+    }
+    fn parse_save_trace_path_arg(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("save_trace").cloned()
+    }
+
+    fn time_profile_path_arg<'a>() -> Arg<'a> {
+        arg!(
+            --time_profile [TIME_PROFILE_PATH] "Path to write the time profile of execution and table assignment as JSON."
+        )
+        .value_parser(value_parser!(PathBuf))
+    }
+    fn parse_time_profile_path_arg(matches: &ArgMatches) -> Option<PathBuf> {
+        matches.get_one::<PathBuf>("time_profile").cloned()
+    }
+
     fn instances_path_arg<'a>() -> Arg<'a> {
         arg!(
             -i --instances <AGGREGATE_INSTANCE_PATH> "Path of aggregate instances."
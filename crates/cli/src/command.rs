@@ -22,7 +22,31 @@ pub trait CommandBuilder: ArgBuilder {
             .arg(Self::single_private_arg())
             .arg(Self::dry_run_service_arg())
             .arg(Self::context_in_arg())
-            .arg(Self::context_out_path_arg());
+            .arg(Self::context_out_path_arg())
+            .arg(Self::record_host_calls_path_arg())
+            .arg(Self::replay_host_calls_path_arg());
+
+        app.subcommand(command)
+    }
+
+    fn append_profile_subcommand(app: App) -> App {
+        let command = Command::new("profile")
+            .arg(Self::single_public_arg())
+            .arg(Self::single_private_arg())
+            .arg(Self::context_in_arg())
+            .arg(Self::replay_host_calls_path_arg());
+
+        app.subcommand(command)
+    }
+
+    fn append_debug_subcommand(app: App) -> App {
+        let command = Command::new("debug")
+            .arg(Self::single_public_arg())
+            .arg(Self::single_private_arg())
+            .arg(Self::context_in_arg())
+            .arg(Self::replay_host_calls_path_arg())
+            .arg(Self::load_trace_path_arg())
+            .arg(Self::save_trace_path_arg());
 
         app.subcommand(command)
     }
@@ -32,7 +56,10 @@ pub trait CommandBuilder: ArgBuilder {
             .arg(Self::single_public_arg())
             .arg(Self::single_private_arg())
             .arg(Self::context_in_arg())
-            .arg(Self::context_out_path_arg());
+            .arg(Self::context_out_path_arg())
+            .arg(Self::record_host_calls_path_arg())
+            .arg(Self::replay_host_calls_path_arg())
+            .arg(Self::time_profile_path_arg());
 
         app.subcommand(command)
     }
@@ -53,6 +80,16 @@ pub trait CommandBuilder: ArgBuilder {
         app.subcommand(command)
     }
 
+    fn append_create_chain_proof_subcommand(app: App) -> App {
+        let command = Command::new("chain")
+            .arg(Self::chain_steps_path_arg())
+            .arg(Self::context_in_arg())
+            .arg(Self::context_out_path_arg())
+            .arg(Self::chain_mock_arg());
+
+        app.subcommand(command)
+    }
+
     fn append_verify_aggregate_verify_subcommand(app: App) -> App {
         let command = Command::new("aggregate-verify")
             .arg(Self::proof_path_arg())
@@ -1,7 +1,17 @@
+use anyhow::bail;
 use anyhow::Result;
+use delphinus_zkwasm::circuits::config::with_circuit_params;
+use delphinus_zkwasm::circuits::diagnose::DiagnosticReport;
 use delphinus_zkwasm::circuits::TestCircuit;
+use delphinus_zkwasm::foreign::context::circuits::CONTEXT_INSTANCE_SIZE;
+use delphinus_zkwasm::loader::chain::check_context_chain;
+use delphinus_zkwasm::loader::chain::ChainStepArg;
 use delphinus_zkwasm::loader::ExecutionArg;
 use delphinus_zkwasm::loader::ZkWasmLoader;
+use delphinus_zkwasm::profile::function_profile::FunctionProfile;
+use delphinus_zkwasm::runtime::debugger::DebugCommand;
+use delphinus_zkwasm::runtime::debugger::TraceDebugger;
+use delphinus_zkwasm::runtime::host::record::HostCallMode;
 use halo2_proofs::arithmetic::BaseExt;
 use halo2_proofs::pairing::bn256::Bn256;
 use halo2_proofs::pairing::bn256::Fr;
@@ -28,7 +38,10 @@ use notify::RecursiveMode;
 use notify::Watcher;
 use serde::Deserialize;
 use serde::Serialize;
+use specs::dump::TableDumpOption;
+use specs::Tables;
 use std::fs;
+use std::io::BufRead;
 use std::io::Write;
 use std::path::Path;
 use std::path::PathBuf;
@@ -47,6 +60,8 @@ pub fn exec_setup(
     prefix: &'static str,
     wasm_binary: Vec<u8>,
     phantom_functions: Vec<String>,
+    public_input_digest: bool,
+    opcode_set_from_image: bool,
     output_dir: &PathBuf,
 ) -> Result<()> {
     info!("Setup Params and VerifyingKey");
@@ -76,7 +91,9 @@ pub fn exec_setup(
             info!("Found Verifying at {:?}", vk_path);
         } else {
             info!("Create Verifying to {:?}", vk_path);
-            let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?;
+            let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?
+                .with_public_input_digest(public_input_digest)
+                .with_opcode_set_from_image(opcode_set_from_image)?;
 
             let vkey = loader.create_vkey(&params)?;
 
@@ -102,10 +119,10 @@ pub fn exec_image_checksum(
     );
 
     let checksum = loader.checksum(&params)?;
-    assert_eq!(checksum.len(), 1);
-    let checksum = checksum[0];
+    assert_eq!(checksum.len(), 2);
 
-    println!("image checksum: {:?}", checksum);
+    println!("image checksum: {:?}", checksum[0]);
+    println!("global init checksum: {:?}", checksum[1]);
 
     let mut fd =
         std::fs::File::create(&output_dir.join(format!("checksum.data",)).as_path()).unwrap();
@@ -185,6 +202,7 @@ pub fn exec_dry_run_service(
                                     private_inputs,
                                     context_inputs,
                                     context_outputs: context_outputs.clone(),
+                                    host_call_mode: HostCallMode::Native,
                                 })
                                 .unwrap();
                             println!("return value: {:?}", r);
@@ -231,6 +249,7 @@ pub fn exec_dry_run(
     private_inputs: Vec<u64>,
     context_inputs: Vec<u64>,
     context_outputs: Arc<Mutex<Vec<u64>>>,
+    host_call_mode: HostCallMode,
 ) -> Result<()> {
     let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?;
 
@@ -239,51 +258,200 @@ pub fn exec_dry_run(
         private_inputs,
         context_inputs,
         context_outputs,
+        host_call_mode,
     })?;
 
     Ok(())
 }
 
+pub fn exec_profile(
+    prefix: &'static str,
+    zkwasm_k: u32,
+    wasm_binary: Vec<u8>,
+    phantom_functions: Vec<String>,
+    table_dump: Option<TableDumpOption>,
+    step_limit: Option<usize>,
+    output_dir: &PathBuf,
+    public_inputs: Vec<u64>,
+    private_inputs: Vec<u64>,
+    context_inputs: Vec<u64>,
+    context_outputs: Arc<Mutex<Vec<u64>>>,
+    host_call_mode: HostCallMode,
+) -> Result<()> {
+    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?
+        .with_table_dump(table_dump)
+        .with_step_limit(step_limit);
+
+    let execution_result = loader.run(ExecutionArg {
+        public_inputs,
+        private_inputs,
+        context_inputs,
+        context_outputs,
+        host_call_mode,
+    })?;
+
+    let profile = FunctionProfile::new(&execution_result.tables, loader.debug_info());
+
+    {
+        let folded_path = output_dir.join(format!("{}.folded", prefix));
+        println!("write folded stacks to {:?}", folded_path);
+        fs::write(&folded_path, profile.folded_stacks())?;
+
+        let summary_path = output_dir.join(format!("{}.profile.json", prefix));
+        println!("write profile summary to {:?}", summary_path);
+        fs::write(&summary_path, profile.to_json())?;
+
+        let time_profile_path = output_dir.join(format!("{}.time.json", prefix));
+        println!("write time profile to {:?}", time_profile_path);
+        fs::write(&time_profile_path, execution_result.time_profile.to_json())?;
+    }
+
+    Ok(())
+}
+
+pub fn exec_debug(
+    zkwasm_k: u32,
+    wasm_binary: Vec<u8>,
+    phantom_functions: Vec<String>,
+    table_dump: Option<TableDumpOption>,
+    step_limit: Option<usize>,
+    public_inputs: Vec<u64>,
+    private_inputs: Vec<u64>,
+    context_inputs: Vec<u64>,
+    context_outputs: Arc<Mutex<Vec<u64>>>,
+    host_call_mode: HostCallMode,
+    load_trace_path: Option<PathBuf>,
+    save_trace_path: Option<PathBuf>,
+) -> Result<()> {
+    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?
+        .with_table_dump(table_dump)
+        .with_step_limit(step_limit);
+
+    let tables: Tables = match load_trace_path {
+        Some(path) => serde_json::from_slice(&fs::read(&path)?)?,
+        None => {
+            let execution_result = loader.run(ExecutionArg {
+                public_inputs,
+                private_inputs,
+                context_inputs,
+                context_outputs,
+                host_call_mode,
+            })?;
+
+            execution_result.tables
+        }
+    };
+
+    if let Some(path) = save_trace_path {
+        println!("write execution trace to {:?}", path);
+        fs::write(&path, serde_json::to_string(&tables)?)?;
+    }
+
+    let mut debugger = TraceDebugger::new(&tables, loader.debug_info());
+    println!(
+        "{}",
+        debugger.execute(DebugCommand::Info).unwrap_or_default()
+    );
+
+    let stdin = std::io::stdin();
+    let mut last_command = DebugCommand::Step(1);
+    loop {
+        print!("(zkwasm) ");
+        std::io::stdout().flush()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line)? == 0 {
+            break;
+        }
+
+        // An empty line repeats the last command.
+        let command = if line.trim().is_empty() {
+            Ok(last_command.clone())
+        } else {
+            DebugCommand::parse(&line)
+        };
+
+        match command {
+            Ok(command) => {
+                last_command = command.clone();
+
+                match debugger.execute(command) {
+                    Some(output) => println!("{}", output),
+                    None => break,
+                }
+            }
+            Err(err) => println!("{}, type `help` for commands", err),
+        }
+    }
+
+    Ok(())
+}
+
 pub fn exec_create_proof(
     prefix: &'static str,
     zkwasm_k: u32,
     wasm_binary: Vec<u8>,
     phantom_functions: Vec<String>,
+    public_input_digest: bool,
+    opcode_set_from_image: bool,
+    table_dump: Option<TableDumpOption>,
+    step_limit: Option<usize>,
     output_dir: &PathBuf,
     public_inputs: Vec<u64>,
     private_inputs: Vec<u64>,
     context_inputs: Vec<u64>,
     context_outputs: Arc<Mutex<Vec<u64>>>,
+    host_call_mode: HostCallMode,
+    time_profile_path: Option<PathBuf>,
 ) -> Result<()> {
-    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?;
+    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?
+        .with_public_input_digest(public_input_digest)
+        .with_opcode_set_from_image(opcode_set_from_image)?
+        .with_table_dump(table_dump)
+        .with_step_limit(step_limit);
 
     let params = load_or_build_unsafe_params::<Bn256>(
         zkwasm_k,
         Some(&output_dir.join(format!("K{}.params", zkwasm_k))),
     );
 
-    let vkey = load_vkey::<Bn256, TestCircuit<_>>(
-        &params,
-        &output_dir.join(format!("{}.{}.vkey.data", prefix, 0)),
-    );
+    let vkey = with_circuit_params(loader.circuit_params(), || {
+        load_vkey::<Bn256, TestCircuit<_>>(
+            &params,
+            &output_dir.join(format!("{}.{}.vkey.data", prefix, 0)),
+        )
+    });
 
-    let (circuit, instances) = loader.circuit_with_witness(ExecutionArg {
+    let execution_result = loader.run(ExecutionArg {
         public_inputs,
         private_inputs,
         context_inputs,
         context_outputs,
+        host_call_mode,
     })?;
+    let time_profile = execution_result.time_profile.clone();
+
+    let (circuit, instances) = loader.circuit_with_execution_result(execution_result);
 
     {
+        // The context digests are stored after the public inputs.
         store_instance(
-            &vec![instances.clone()],
+            &instances,
             &output_dir.join(format!("{}.{}.instance.data", prefix, 0)),
         );
     }
 
     if true {
         info!("Mock test...");
-        loader.mock_test(&circuit, &instances)?;
+        if let Err(err) = loader.mock_test(&circuit, &instances) {
+            if let Some(report) = err.downcast_ref::<DiagnosticReport>() {
+                let report_path = output_dir.join(format!("{}.{}.mock.json", prefix, 0));
+                println!("write mock test report to {:?}", report_path);
+                std::fs::write(&report_path, report.to_json())?;
+            }
+
+            return Err(err);
+        }
         info!("Mock test passed");
     }
 
@@ -298,6 +466,11 @@ pub fn exec_create_proof(
 
     info!("Proof has been created.");
 
+    if let Some(path) = time_profile_path {
+        println!("write time profile to {:?}", path);
+        fs::write(&path, time_profile.with_assignment_timings().to_json())?;
+    }
+
     Ok(())
 }
 
@@ -306,6 +479,8 @@ pub fn exec_verify_proof(
     zkwasm_k: u32,
     wasm_binary: Vec<u8>,
     phantom_functions: Vec<String>,
+    public_input_digest: bool,
+    opcode_set_from_image: bool,
     output_dir: &PathBuf,
     proof_path: &PathBuf,
     instance_path: &PathBuf,
@@ -317,19 +492,32 @@ pub fn exec_verify_proof(
             instance.push(f);
         }
 
-        instance
+        if instance.len() < CONTEXT_INSTANCE_SIZE {
+            bail!(
+                "Instance file {:?} lacks the context digests",
+                instance_path
+            );
+        }
+
+        let context_instance = instance.split_off(instance.len() - CONTEXT_INSTANCE_SIZE);
+
+        vec![instance, context_instance]
     };
     let params = load_or_build_unsafe_params::<Bn256>(
         zkwasm_k,
         Some(&output_dir.join(format!("K{}.params", zkwasm_k))),
     );
 
-    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?;
+    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?
+        .with_public_input_digest(public_input_digest)
+        .with_opcode_set_from_image(opcode_set_from_image)?;
 
-    let vkey = load_vkey::<Bn256, TestCircuit<_>>(
-        &params,
-        &output_dir.join(format!("{}.{}.vkey.data", prefix, 0)),
-    );
+    let vkey = with_circuit_params(loader.circuit_params(), || {
+        load_vkey::<Bn256, TestCircuit<_>>(
+            &params,
+            &output_dir.join(format!("{}.{}.vkey.data", prefix, 0)),
+        )
+    });
 
     let proof = load_proof(proof_path);
 
@@ -346,6 +534,10 @@ pub fn exec_aggregate_create_proof(
     prefix: &'static str,
     wasm_binary: Vec<u8>,
     phantom_functions: Vec<String>,
+    public_input_digest: bool,
+    opcode_set_from_image: bool,
+    table_dump: Option<TableDumpOption>,
+    step_limit: Option<usize>,
     output_dir: &PathBuf,
     public_inputs: Vec<Vec<u64>>,
     private_inputs: Vec<Vec<u64>>,
@@ -354,7 +546,11 @@ pub fn exec_aggregate_create_proof(
 ) -> Result<()> {
     assert_eq!(public_inputs.len(), private_inputs.len());
 
-    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?;
+    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?
+        .with_public_input_digest(public_input_digest)
+        .with_opcode_set_from_image(opcode_set_from_image)?
+        .with_table_dump(table_dump)
+        .with_step_limit(step_limit);
 
     let (circuits, instances) = public_inputs
         .into_iter()
@@ -370,29 +566,33 @@ pub fn exec_aggregate_create_proof(
                         private_inputs,
                         context_inputs,
                         context_outputs,
+                        host_call_mode: HostCallMode::Native,
                     })?;
 
                     circuits.push(circuit);
-                    instances.push(vec![instance]);
+                    instances.push(instance);
 
                     Ok((circuits, instances))
                 })
             },
         )?;
 
-    let (aggregate_circuit, aggregate_instances) = run_circuit_unsafe_full_pass::<Bn256, _>(
-        &output_dir.as_path(),
-        prefix,
-        zkwasm_k,
-        circuits,
-        instances,
-        TranscriptHash::Poseidon,
-        vec![],
-        vec![],
-        vec![],
-        false,
-    )
-    .unwrap();
+    let (aggregate_circuit, aggregate_instances) =
+        with_circuit_params(loader.circuit_params(), || {
+            run_circuit_unsafe_full_pass::<Bn256, _>(
+                &output_dir.as_path(),
+                prefix,
+                zkwasm_k,
+                circuits,
+                instances,
+                TranscriptHash::Poseidon,
+                vec![],
+                vec![],
+                vec![],
+                false,
+            )
+        })
+        .unwrap();
 
     run_circuit_unsafe_full_pass::<Bn256, _>(
         &output_dir.as_path(),
@@ -410,6 +610,82 @@ pub fn exec_aggregate_create_proof(
     Ok(())
 }
 
+/// Prove the steps in order, each step reads the context written by the previous one. The
+/// proofs don't bind consecutive steps, a verifier checks the chain on the instances of the
+/// steps with `check_context_chain`.
+pub fn exec_create_chain_proof(
+    zkwasm_k: u32,
+    prefix: &'static str,
+    wasm_binary: Vec<u8>,
+    phantom_functions: Vec<String>,
+    public_input_digest: bool,
+    opcode_set_from_image: bool,
+    table_dump: Option<TableDumpOption>,
+    step_limit: Option<usize>,
+    output_dir: &PathBuf,
+    steps: Vec<ChainStepArg>,
+    context_inputs: Vec<u64>,
+    context_outputs: Arc<Mutex<Vec<u64>>>,
+    mock: bool,
+) -> Result<()> {
+    let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k, wasm_binary, phantom_functions)?
+        .with_public_input_digest(public_input_digest)
+        .with_opcode_set_from_image(opcode_set_from_image)?
+        .with_table_dump(table_dump)
+        .with_step_limit(step_limit);
+
+    let chain = loader.circuits_with_context_chain(steps, context_inputs)?;
+
+    if let Some(last) = chain.last() {
+        *context_outputs.lock().unwrap() = last.context_outputs.clone();
+    }
+
+    let instances = chain
+        .iter()
+        .map(|step| step.instances.clone())
+        .collect::<Vec<_>>();
+
+    // A step reading only a part of the context written by the previous one breaks the chain.
+    check_context_chain(&instances)?;
+
+    let params = load_or_build_unsafe_params::<Bn256>(
+        zkwasm_k,
+        Some(&output_dir.join(format!("K{}.params", zkwasm_k))),
+    );
+
+    let vkey = with_circuit_params(loader.circuit_params(), || {
+        load_vkey::<Bn256, TestCircuit<_>>(
+            &params,
+            &output_dir.join(format!("{}.{}.vkey.data", prefix, 0)),
+        )
+    });
+
+    for (index, step) in chain.into_iter().enumerate() {
+        if mock {
+            info!("Mock test step {}...", index);
+            loader.mock_test(&step.circuit, &step.instances)?;
+        }
+
+        info!("Prove step {}...", index);
+
+        store_instance(
+            &step.instances,
+            &output_dir.join(format!("{}.chain.{}.instance.data", prefix, index)),
+        );
+
+        let proof = loader.create_proof(&params, vkey.clone(), step.circuit, &step.instances)?;
+
+        let proof_path = output_dir.join(format!("{}.chain.{}.transcript.data", prefix, index));
+        println!("write transcript to {:?}", proof_path);
+        let mut fd = std::fs::File::create(&proof_path)?;
+        fd.write_all(&proof)?;
+    }
+
+    info!("Proofs of the chain have been created.");
+
+    Ok(())
+}
+
 pub fn exec_verify_aggregate_proof(
     aggregate_k: u32,
     output_dir: &PathBuf,
@@ -4,6 +4,7 @@ use std::sync::Mutex;
 use anyhow::Result;
 use delphinus_zkwasm::loader::ExecutionArg;
 use delphinus_zkwasm::loader::ZkWasmLoader;
+use delphinus_zkwasm::runtime::host::record::HostCallMode;
 use pairing_bn256::bn256::Bn256;
 
 fn main() -> Result<()> {
@@ -18,6 +19,7 @@ fn main() -> Result<()> {
         private_inputs: vec![],
         context_inputs: context_in,
         context_outputs: context_outputs.clone(),
+        host_call_mode: HostCallMode::Native,
     };
 
     let (circuit, instances) = loader.circuit_with_witness(arg)?;
@@ -28,6 +30,7 @@ fn main() -> Result<()> {
         private_inputs: vec![],
         context_inputs: context_outputs.lock().unwrap().to_vec(),
         context_outputs: Arc::new(Mutex::new(vec![])),
+        host_call_mode: HostCallMode::Native,
     };
 
     let (circuit, instances) = loader.circuit_with_witness(arg)?;
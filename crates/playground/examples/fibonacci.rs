@@ -4,6 +4,7 @@ use std::sync::Mutex;
 use anyhow::Result;
 use delphinus_zkwasm::loader::ExecutionArg;
 use delphinus_zkwasm::loader::ZkWasmLoader;
+use delphinus_zkwasm::runtime::host::record::HostCallMode;
 use pairing_bn256::bn256::Bn256;
 
 fn main() -> Result<()> {
@@ -16,6 +17,7 @@ fn main() -> Result<()> {
         private_inputs: vec![],
         context_inputs: vec![],
         context_outputs: Arc::new(Mutex::new(vec![])),
+        host_call_mode: HostCallMode::Native,
     })?;
     loader.mock_test(&circuit, &instances)
 }
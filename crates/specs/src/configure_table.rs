@@ -1,3 +1,4 @@
+use serde::Deserialize;
 use serde::Serialize;
 
 // A wasm page size is 64KB
@@ -5,7 +6,7 @@ pub const WASM_BYTES_PER_PAGE: u64 = 64 * 1024 as u64;
 
 const WASM_32_MAXIMAL_PAGES_DEFAULT: u32 = 65536;
 
-#[derive(Serialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct ConfigureTable {
     pub init_memory_pages: u32,
     pub maximal_memory_pages: u32,
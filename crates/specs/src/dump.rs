@@ -0,0 +1,179 @@
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use serde::Serialize;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::Tables;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, EnumIter)]
+pub enum TableKind {
+    Itable,
+    Imtable,
+    Etable,
+    Mtable,
+    Jtable,
+    ExternalHostCallTable,
+}
+
+impl TableKind {
+    pub fn all() -> Vec<TableKind> {
+        TableKind::iter().collect()
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            TableKind::Itable => "itable",
+            TableKind::Imtable => "imtable",
+            TableKind::Etable => "etable",
+            TableKind::Mtable => "mtable",
+            TableKind::Jtable => "jtable",
+            TableKind::ExternalHostCallTable => "external_host_table",
+        }
+    }
+}
+
+impl FromStr for TableKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        TableKind::iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                format!(
+                    "unknown table {}, expected one of {}",
+                    s,
+                    TableKind::iter()
+                        .map(|kind| kind.name())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TableDumpFormat {
+    /// One pretty-printed JSON document per table.
+    Json,
+    /// One compact JSON document per table entry and line.
+    JsonLines,
+    /// bincode encoding of the whole table.
+    Binary,
+}
+
+impl TableDumpFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            TableDumpFormat::Json => "json",
+            TableDumpFormat::JsonLines => "jsonl",
+            TableDumpFormat::Binary => "bin",
+        }
+    }
+}
+
+impl FromStr for TableDumpFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "json" => Ok(TableDumpFormat::Json),
+            "jsonl" => Ok(TableDumpFormat::JsonLines),
+            "binary" => Ok(TableDumpFormat::Binary),
+            _ => Err(format!(
+                "unknown dump format {}, expected one of json, jsonl, binary",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TableDumpOption {
+    pub dir: PathBuf,
+    pub tables: Vec<TableKind>,
+    pub format: TableDumpFormat,
+}
+
+impl TableDumpOption {
+    /// Dump all tables as pretty JSON.
+    pub fn new(dir: PathBuf) -> Self {
+        TableDumpOption {
+            dir,
+            tables: TableKind::all(),
+            format: TableDumpFormat::Json,
+        }
+    }
+}
+
+fn write_table<T: Serialize, E: Serialize>(
+    path: PathBuf,
+    format: TableDumpFormat,
+    table: &T,
+    entries: &[E],
+) -> io::Result<()> {
+    let mut fd = BufWriter::new(File::create(path)?);
+
+    match format {
+        TableDumpFormat::Json => serde_json::to_writer_pretty(&mut fd, table)?,
+        TableDumpFormat::JsonLines => {
+            for entry in entries {
+                serde_json::to_writer(&mut fd, entry)?;
+                fd.write_all(b"\n")?;
+            }
+        }
+        TableDumpFormat::Binary => bincode::serialize_into(&mut fd, table)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?,
+    }
+
+    fd.flush()
+}
+
+impl Tables {
+    pub fn dump(&self, option: &TableDumpOption) -> io::Result<()> {
+        std::fs::create_dir_all(&option.dir)?;
+
+        for kind in &option.tables {
+            let path = option
+                .dir
+                .join(format!("{}.{}", kind.name(), option.format.extension()));
+
+            match kind {
+                TableKind::Itable => {
+                    let table = &self.compilation_tables.itable;
+                    write_table(path, option.format, table, table.entries())?
+                }
+                TableKind::Imtable => {
+                    let table = &self.compilation_tables.imtable;
+                    write_table(path, option.format, table, table.entries())?
+                }
+                TableKind::Etable => {
+                    let table = &self.execution_tables.etable;
+                    write_table(path, option.format, table, table.entries())?
+                }
+                TableKind::Mtable => {
+                    let table = &self.execution_tables.mtable;
+                    write_table(path, option.format, table, table.entries())?
+                }
+                TableKind::Jtable => {
+                    let table = &self.execution_tables.jtable;
+                    write_table(path, option.format, table, table.entries())?
+                }
+                TableKind::ExternalHostCallTable => {
+                    let table = self
+                        .execution_tables
+                        .etable
+                        .filter_external_host_call_table();
+                    write_table(path, option.format, &table, table.entries())?
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
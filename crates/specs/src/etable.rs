@@ -1,10 +1,14 @@
+use std::ops::Deref;
+use std::ops::DerefMut;
+
+use serde::Deserialize;
 use serde::Serialize;
 
 use super::itable::InstructionTableEntry;
 use crate::host_function::HostPlugin;
 use crate::step::StepInfo;
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct EventTableEntry {
     pub eid: u32,
     pub sp: u32,
@@ -38,24 +42,76 @@ impl Iterator for RestJops {
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize)]
-pub struct EventTable(Vec<EventTableEntry>);
+/// Payload of the unwinding started by `EventTableEntries::push` beyond the limit.
+#[derive(Clone, Copy, Debug)]
+pub struct EventTableLimitExceeded {
+    pub limit: usize,
+}
+
+/// Entries of an `EventTable`, the tracer pushes an entry for each step it executes.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct EventTableEntries {
+    entries: Vec<EventTableEntry>,
+    #[serde(skip)]
+    limit: Option<usize>,
+}
+
+impl EventTableEntries {
+    /// Pushing an entry beyond the limit unwinds with an `EventTableLimitExceeded` payload,
+    /// which aborts the interpreter within the step. The panic hook is not invoked.
+    pub fn push(&mut self, entry: EventTableEntry) {
+        if let Some(limit) = self.limit {
+            if self.entries.len() >= limit {
+                std::panic::resume_unwind(Box::new(EventTableLimitExceeded { limit }));
+            }
+        }
+
+        self.entries.push(entry);
+    }
+}
+
+impl Deref for EventTableEntries {
+    type Target = Vec<EventTableEntry>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entries
+    }
+}
+
+impl DerefMut for EventTableEntries {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.entries
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EventTable(EventTableEntries);
 
 impl EventTable {
     pub fn new(entries: Vec<EventTableEntry>) -> Self {
-        Self(entries)
+        Self(EventTableEntries {
+            entries,
+            limit: None,
+        })
+    }
+
+    /// Bounds the number of entries, see `EventTableEntries::push`.
+    pub fn set_limit(&mut self, limit: Option<usize>) {
+        self.0.limit = limit;
     }
 
     pub fn entries(&self) -> &Vec<EventTableEntry> {
-        &self.0
+        &self.0.entries
     }
 
-    pub fn entries_mut(&mut self) -> &mut Vec<EventTableEntry> {
+    pub fn entries_mut(&mut self) -> &mut EventTableEntries {
         &mut self.0
     }
 
     pub fn filter_foreign_entries(&self, foreign: HostPlugin) -> Vec<EventTableEntry> {
         self.0
+            .entries
             .clone()
             .into_iter()
             .filter(|entry| match entry.step_info {
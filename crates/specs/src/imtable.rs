@@ -1,10 +1,11 @@
-use std::cmp::Ordering;
+use std::collections::BTreeMap;
 
 use crate::mtable::LocationType;
 use crate::mtable::VarType;
+use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Serialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct InitMemoryTableEntry {
     pub ltype: LocationType,
     pub is_mutable: bool,
@@ -15,81 +16,88 @@ pub struct InitMemoryTableEntry {
     pub value: u64,
 }
 
-#[derive(Serialize, Default, Debug, Clone)]
+#[derive(Serialize, Deserialize, Default, Debug, Clone)]
 pub struct InitMemoryTable {
     entries: Vec<InitMemoryTableEntry>,
     sorted_global_init_entries: Vec<InitMemoryTableEntry>,
-    sorted_heap_init_entries: Vec<InitMemoryTableEntry>,
+    /// The non-zero heap words by offset, the words missing are zero.
+    heap_words: BTreeMap<u32, u64>,
 }
 
 impl InitMemoryTable {
+    /// Heap words which are not covered by `entries` are zero, so only the non-zero words of
+    /// the data segments need to be given. Zero heap entries are dropped, the heap is kept
+    /// as its non-zero words whatever the size of the memory.
     pub fn new(entries: Vec<InitMemoryTableEntry>, k: u32) -> Self {
-        let mut imtable = Self {
-            entries: entries
-                .into_iter()
-                .map(|entry| InitMemoryTableEntry {
-                    ltype: entry.ltype,
-                    is_mutable: entry.is_mutable,
-                    start_offset: entry.start_offset,
-                    end_offset: if entry.end_offset == u32::MAX {
-                        (1u32 << (k - 1)) - 1
-                    } else {
-                        entry.end_offset
-                    },
-                    vtype: entry.vtype,
-                    value: entry.value,
-                })
-                .collect(),
-            sorted_global_init_entries: vec![],
-            sorted_heap_init_entries: vec![],
-        };
-        imtable.sort();
-        imtable.merge();
+        let entries = entries.into_iter().map(|entry| InitMemoryTableEntry {
+            end_offset: if entry.end_offset == u32::MAX {
+                (1u32 << (k - 1)) - 1
+            } else {
+                entry.end_offset
+            },
+            ..entry
+        });
+
+        let mut sorted_global_init_entries = vec![];
+        let mut heap_words = BTreeMap::new();
+
+        for entry in entries {
+            match entry.ltype {
+                LocationType::Heap => {
+                    if entry.value != 0 {
+                        for offset in entry.start_offset..=entry.end_offset {
+                            heap_words.insert(offset, entry.value);
+                        }
+                    }
+                }
+                LocationType::Global => sorted_global_init_entries.push(entry),
+                LocationType::Stack => unreachable!(),
+            }
+        }
 
-        imtable.sorted_heap_init_entries = imtable
-            .entries
-            .iter()
-            .filter(|entry| entry.ltype == LocationType::Heap)
-            .map(|entry| entry.clone())
-            .collect();
-        imtable.sorted_global_init_entries = imtable
-            .entries
+        sorted_global_init_entries.sort_by_key(|entry| entry.start_offset);
+
+        let entries = heap_words
             .iter()
-            .filter(|entry| entry.ltype == LocationType::Global)
-            .map(|entry| entry.clone())
+            .map(|(offset, value)| InitMemoryTableEntry {
+                ltype: LocationType::Heap,
+                is_mutable: true,
+                start_offset: *offset,
+                end_offset: *offset,
+                vtype: VarType::I64,
+                value: *value,
+            })
+            .chain(sorted_global_init_entries.iter().cloned())
             .collect();
 
-        imtable
+        Self {
+            entries,
+            sorted_global_init_entries,
+            heap_words,
+        }
     }
 
     pub fn entries(&self) -> &Vec<InitMemoryTableEntry> {
         &self.entries
     }
 
+    /// The non-zero heap words by offset.
+    pub fn heap_words(&self) -> &BTreeMap<u32, u64> {
+        &self.heap_words
+    }
+
     pub fn to_string(&self) -> String {
         serde_json::to_string(&self.entries).unwrap()
     }
 
+    /// A heap word is found at any offset, its range is the word itself.
     pub fn try_find(&self, ltype: LocationType, offset: u32) -> Option<(u32, u32, u64)> {
         match ltype {
             LocationType::Heap => {
-                let idx = self
-                    .sorted_heap_init_entries
-                    .binary_search_by(|entry| {
-                        if offset >= entry.start_offset && offset <= entry.end_offset {
-                            Ordering::Equal
-                        } else if offset < entry.start_offset {
-                            Ordering::Greater
-                        } else {
-                            Ordering::Less
-                        }
-                    })
-                    .unwrap();
-
                 return Some((
-                    self.sorted_heap_init_entries[idx].start_offset,
-                    self.sorted_heap_init_entries[idx].end_offset,
-                    self.sorted_heap_init_entries[idx].value,
+                    offset,
+                    offset,
+                    self.heap_words.get(&offset).cloned().unwrap_or(0),
                 ));
             }
             LocationType::Global => {
@@ -105,58 +113,6 @@ impl InitMemoryTable {
         None
     }
 
-    fn sort(&mut self) {
-        self.entries
-            .sort_by_key(|item| (item.ltype, item.start_offset))
-    }
-
-    fn merge(&mut self) {
-        let mut merged_entries: Vec<_> = self
-            .entries()
-            .iter()
-            .filter(|entry| entry.ltype == LocationType::Global)
-            .map(|entry| entry.clone())
-            .collect();
-
-        let heap_initial: Vec<_> = self
-            .entries()
-            .iter()
-            .filter(|entry| entry.ltype == LocationType::Heap)
-            .collect();
-
-        if !heap_initial.is_empty() {
-            let mut scan = 0;
-            let mut cursor = scan + 1;
-            while scan < heap_initial.len() && cursor < heap_initial.len() {
-                if heap_initial[scan].value == heap_initial[cursor].value {
-                    cursor += 1;
-                } else {
-                    merged_entries.push(InitMemoryTableEntry {
-                        ltype: LocationType::Heap,
-                        is_mutable: true,
-                        start_offset: heap_initial[scan].start_offset,
-                        end_offset: heap_initial[cursor - 1].end_offset,
-                        vtype: VarType::I64,
-                        value: heap_initial[scan].value,
-                    });
-
-                    scan = cursor;
-                    cursor = scan + 1;
-                }
-            }
-            merged_entries.push(InitMemoryTableEntry {
-                ltype: LocationType::Heap,
-                is_mutable: true,
-                start_offset: heap_initial[scan].start_offset,
-                end_offset: heap_initial[cursor - 1].end_offset,
-                vtype: VarType::I64,
-                value: heap_initial[scan].value,
-            });
-        }
-
-        self.entries = merged_entries;
-    }
-
     pub fn filter(&self, ltype: LocationType) -> Vec<&InitMemoryTableEntry> {
         self.entries.iter().filter(|e| e.ltype == ltype).collect()
     }
@@ -16,6 +16,7 @@ use crate::mtable::MemoryReadSize;
 use crate::mtable::MemoryStoreSize;
 use crate::types::ValueType;
 use num_bigint::BigUint;
+use serde::Deserialize;
 use serde::Serialize;
 use std::collections::HashSet;
 use strum_macros::EnumIter;
@@ -50,6 +51,8 @@ pub enum OpcodeClass {
     MemorySize,
     MemoryGrow,
     Conversion,
+    BinConst,
+    BinLocal,
     ForeignPluginStart,
 }
 
@@ -83,6 +86,8 @@ impl OpcodeClass {
             OpcodeClass::MemorySize => 1,
             OpcodeClass::MemoryGrow => 2,
             OpcodeClass::Conversion => 2,
+            OpcodeClass::BinConst => 2, // pop lhs, push result
+            OpcodeClass::BinLocal => 3, // read local, pop lhs, push result
             OpcodeClass::ForeignPluginStart => 0,
         }
     }
@@ -106,14 +111,14 @@ impl OpcodeClassPlain {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum UnaryOp {
     Ctz,
     Clz,
     Popcnt,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BinOp {
     Add,
     Sub,
@@ -124,7 +129,7 @@ pub enum BinOp {
     SignedRem,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ShiftOp {
     Shl,
     UnsignedShr,
@@ -133,7 +138,7 @@ pub enum ShiftOp {
     Rotr,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
 pub enum BitOp {
     And = 0,
     Or = 1,
@@ -150,7 +155,7 @@ impl BitOp {
     }
 }
 
-#[derive(Copy, Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum RelOp {
     Eq,
     Ne,
@@ -164,12 +169,12 @@ pub enum RelOp {
     UnsignedLe,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, EnumIter, PartialEq, Eq, PartialOrd, Ord)]
 pub enum TestOp {
     Eqz,
 }
 
-#[derive(Copy, Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ConversionOp {
     I32WrapI64,
     I64ExtendI32s,
@@ -181,14 +186,14 @@ pub enum ConversionOp {
     I64Extend32S,
 }
 
-#[derive(Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub struct BrTarget {
     pub drop: u32,
     pub keep: Vec<ValueType>,
     pub dst_pc: u32,
 }
 
-#[derive(Clone, Debug, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
 pub enum Opcode {
     LocalGet {
         vtype: VarType,
@@ -292,6 +297,18 @@ pub enum Opcode {
     Conversion {
         class: ConversionOp,
     },
+    /// `Const` followed by `Bin`, `value` is the right operand.
+    BinConst {
+        class: BinOp,
+        vtype: VarType,
+        value: u64,
+    },
+    /// `LocalGet` followed by `Bin`, the right operand is the local at `offset`.
+    BinLocal {
+        class: BinOp,
+        vtype: VarType,
+        offset: u64,
+    },
 }
 
 impl Opcode {
@@ -310,6 +327,8 @@ impl Opcode {
             Opcode::Const { vtype, .. } => Some(*vtype),
             Opcode::Bin { vtype, .. } => Some(*vtype),
             Opcode::BinBit { vtype, .. } => Some(*vtype),
+            Opcode::BinConst { vtype, .. } => Some(*vtype),
+            Opcode::BinLocal { vtype, .. } => Some(*vtype),
             _ => None,
         }
     }
@@ -359,6 +378,26 @@ impl Into<BigUint> for Opcode {
                     + (BigUint::from(class as u64) << OPCODE_ARG0_SHIFT)
                     + (BigUint::from(vtype as u64) << OPCODE_ARG1_SHIFT)
             }
+            Opcode::BinConst {
+                class,
+                vtype,
+                value,
+            } => {
+                (BigUint::from(OpcodeClass::BinConst as u64) << OPCODE_CLASS_SHIFT)
+                    + (BigUint::from(class as u64) << OPCODE_ARG0_SHIFT)
+                    + (BigUint::from(vtype as u64) << OPCODE_ARG1_SHIFT)
+                    + value
+            }
+            Opcode::BinLocal {
+                class,
+                vtype,
+                offset,
+            } => {
+                (BigUint::from(OpcodeClass::BinLocal as u64) << OPCODE_CLASS_SHIFT)
+                    + (BigUint::from(class as u64) << OPCODE_ARG0_SHIFT)
+                    + (BigUint::from(vtype as u64) << OPCODE_ARG1_SHIFT)
+                    + offset
+            }
             Opcode::BinShift { class, vtype } => {
                 (BigUint::from(OpcodeClass::BinShift as u64) << OPCODE_CLASS_SHIFT)
                     + (BigUint::from(class as u64) << OPCODE_ARG0_SHIFT)
@@ -569,6 +608,8 @@ impl Into<OpcodeClass> for Opcode {
             Opcode::MemorySize => OpcodeClass::MemorySize,
             Opcode::MemoryGrow => OpcodeClass::MemoryGrow,
             Opcode::Conversion { .. } => OpcodeClass::Conversion,
+            Opcode::BinConst { .. } => OpcodeClass::BinConst,
+            Opcode::BinLocal { .. } => OpcodeClass::BinLocal,
         }
     }
 }
@@ -585,7 +626,7 @@ impl Into<OpcodeClassPlain> for Opcode {
     }
 }
 
-#[derive(Clone, Debug, Serialize)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct InstructionTableEntry {
     pub fid: u32,
     pub iid: u32,
@@ -605,7 +646,7 @@ impl InstructionTableEntry {
         bn
     }
 }
-#[derive(Default, Serialize, Debug, Clone)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct InstructionTable(Vec<InstructionTableEntry>);
 
 impl InstructionTable {
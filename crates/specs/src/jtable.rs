@@ -1,7 +1,8 @@
 use super::itable::InstructionTableEntry;
+use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Default, Serialize, Debug, Clone)]
+#[derive(Default, Serialize, Deserialize, Debug, Clone)]
 pub struct StaticFrameEntry {
     pub enable: bool,
     pub frame_id: u32,
@@ -11,7 +12,7 @@ pub struct StaticFrameEntry {
     pub iid: u32,
 }
 
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JumpTableEntry {
     // caller eid (unique)
     pub eid: u32,
@@ -26,7 +27,7 @@ impl JumpTableEntry {
     }
 }
 
-#[derive(Clone, Debug, Default, Serialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct JumpTable(Vec<JumpTableEntry>);
 
 impl JumpTable {
@@ -34,6 +35,10 @@ impl JumpTable {
         &self.0
     }
 
+    pub fn entries_mut(&mut self) -> &mut Vec<JumpTableEntry> {
+        &mut self.0
+    }
+
     pub fn push(&mut self, entry: JumpTableEntry) {
         self.0.push(entry)
     }
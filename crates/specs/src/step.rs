@@ -10,9 +10,10 @@ use crate::mtable::MemoryReadSize;
 use crate::mtable::MemoryStoreSize;
 use crate::mtable::VarType;
 use crate::types::ValueType;
+use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum StepInfo {
     Br {
         dst_pc: u32,
@@ -237,4 +238,21 @@ pub enum StepInfo {
         value: i64,
         result: i64,
     },
+
+    // Fused instructions, operands are stored as their u64 representation.
+    BinConst {
+        class: BinOp,
+        vtype: VarType,
+        left: u64,
+        right: u64,
+        value: u64,
+    },
+    BinLocal {
+        class: BinOp,
+        vtype: VarType,
+        depth: u32,
+        left: u64,
+        right: u64,
+        value: u64,
+    },
 }
@@ -16,9 +16,14 @@ pub(crate) struct CompilationTableWithParams<'a, 'b, C: CurveAffine> {
 
 impl<'a, 'b, C: CurveAffine> ImageCheckSum<Vec<C>> for CompilationTableWithParams<'a, 'b, C> {
     fn checksum(&self) -> Vec<C> {
-        let cells = self.table.encode_compilation_table_values().plain();
+        let layouter = self.table.encode_compilation_table_values();
 
-        let c = best_multiexp_gpu_cond(&cells[..], &self.params.get_g_lagrange()[0..cells.len()]);
-        vec![c.into()]
+        [layouter.plain(), layouter.init_memory_entries.unwrap()]
+            .iter()
+            .map(|cells| {
+                best_multiexp_gpu_cond(&cells[..], &self.params.get_g_lagrange()[0..cells.len()])
+                    .into()
+            })
+            .collect()
     }
 }
@@ -0,0 +1,151 @@
+//! Number of entries each table can hold for the current K.
+//!
+//! Checking a trace against its capacity before assignment turns an assertion failure deep in
+//! the synthesis into a `TraceCapacityError`.
+
+use std::fmt::Display;
+
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::Circuit;
+use halo2_proofs::plonk::ConstraintSystem;
+use serde::Serialize;
+use specs::ExecutionTable;
+
+use super::config::zkwasm_k;
+use super::etable::EVENT_TABLE_ENTRY_ROWS;
+use super::jtable::JtableOffset;
+use super::memory_tree::heap_init_offsets;
+use super::memory_tree::memory_tree_capacity;
+use super::memory_tree::memory_tree_nodes;
+use super::mtable::MEMORY_TABLE_ENTRY_ROWS;
+use super::utils::table_entry::MemoryWritingTable;
+use super::TestCircuit;
+use crate::foreign::bigint_helper::circuits::u256_capacity;
+use crate::foreign::context::circuits::assign::ExtractContextFromTrace;
+use crate::foreign::context::circuits::context_capacity;
+use crate::foreign::ForeignInst;
+
+/// The jtable always starts with two static entries and ends with a disabled one.
+const RESERVED_JTABLE_ENTRIES: usize = 3;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum CapacityTable {
+    Etable,
+    Mtable,
+    Jtable,
+    /// Context reads or writes, absorbed by the context digests.
+    Context,
+    /// `u256_op` calls, each owns a block of the u256 helper table.
+    U256,
+    /// Nodes of the heap memory tree on the paths to the initial heap words read by the trace.
+    MemoryTree,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TraceCapacityError {
+    pub k: u32,
+    pub table: CapacityTable,
+    pub entries: usize,
+    pub capacity: usize,
+}
+
+impl Display for TraceCapacityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "trace exceeds capacity for K = {}: {:?} has {} entries but holds at most {}",
+            self.k, self.table, self.entries, self.capacity
+        )
+    }
+}
+
+impl std::error::Error for TraceCapacityError {}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TraceCapacity {
+    pub k: u32,
+    pub etable: usize,
+    pub mtable: usize,
+    pub jtable: usize,
+    pub context: usize,
+    pub u256: usize,
+    pub memory_tree: usize,
+}
+
+impl TraceCapacity {
+    pub fn new() -> Self {
+        let mut meta = ConstraintSystem::<Fr>::default();
+        let max_available_rows = TestCircuit::<Fr>::configure(&mut meta).max_available_rows;
+
+        TraceCapacity {
+            k: zkwasm_k(),
+            etable: max_available_rows / EVENT_TABLE_ENTRY_ROWS as usize,
+            // The memory table requires a row behind the last entry.
+            mtable: (max_available_rows - 1) / MEMORY_TABLE_ENTRY_ROWS as usize,
+            jtable: max_available_rows / JtableOffset::JtableOffsetMax as usize
+                - RESERVED_JTABLE_ENTRIES,
+            context: context_capacity(max_available_rows),
+            u256: u256_capacity(max_available_rows),
+            memory_tree: memory_tree_capacity(max_available_rows),
+        }
+    }
+
+    /// The number of instructions the tracer may execute before the etable must overflow,
+    /// fusion merges at most two instructions into one entry.
+    pub fn step_budget(&self) -> usize {
+        self.etable * 2
+    }
+
+    pub fn error(&self, table: CapacityTable, entries: usize) -> TraceCapacityError {
+        TraceCapacityError {
+            k: self.k,
+            table,
+            entries,
+            capacity: match table {
+                CapacityTable::Etable => self.etable,
+                CapacityTable::Mtable => self.mtable,
+                CapacityTable::Jtable => self.jtable,
+                CapacityTable::Context => self.context,
+                CapacityTable::U256 => self.u256,
+                CapacityTable::MemoryTree => self.memory_tree,
+            },
+        }
+    }
+
+    /// Among the overflowed tables, the most utilized one is reported.
+    pub fn check(&self, tables: &ExecutionTable) -> Result<(), TraceCapacityError> {
+        let memory_writing_table: MemoryWritingTable = tables.mtable.clone().into();
+
+        [
+            (CapacityTable::Etable, tables.etable.entries().len()),
+            (CapacityTable::Mtable, memory_writing_table.0.len()),
+            (CapacityTable::Jtable, tables.jtable.entries().len()),
+            (
+                CapacityTable::Context,
+                usize::max(
+                    tables.etable.get_context_inputs().len(),
+                    tables.etable.get_context_outputs().len(),
+                ),
+            ),
+            (
+                CapacityTable::U256,
+                tables
+                    .etable
+                    .filter_external_host_call_table()
+                    .entries()
+                    .iter()
+                    .filter(|entry| entry.op == ForeignInst::U256Op as usize)
+                    .count(),
+            ),
+            (
+                CapacityTable::MemoryTree,
+                memory_tree_nodes(heap_init_offsets(&tables.mtable)),
+            ),
+        ]
+        .into_iter()
+        .map(|(table, entries)| self.error(table, entries))
+        .filter(|error| error.entries > error.capacity)
+        .max_by(|a, b| (a.entries * b.capacity).cmp(&(b.entries * a.capacity)))
+        .map_or(Ok(()), Err)
+    }
+}
@@ -1,6 +1,10 @@
+use std::cell::RefCell;
+use std::collections::BTreeSet;
 use std::env;
 use std::sync::Mutex;
 
+use specs::itable::OpcodeClassPlain;
+
 pub const POW_TABLE_POWER_START: u64 = 128;
 
 pub const MIN_K: u32 = 18;
@@ -21,10 +25,67 @@ pub fn zkwasm_k() -> u32 {
     *ZKWASM_K.lock().unwrap()
 }
 
+/// Parameters of a `TestCircuit`, carried by the circuit since they change its
+/// configuration.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CircuitParams {
+    /// Expose the digest of the public inputs and outputs as the only public instance
+    /// instead of one instance per word.
+    pub public_input_digest: bool,
+    /// The opcode classes to configure, all of them if `None`. A circuit configured with
+    /// the classes of its image only supports images using these classes.
+    pub opcode_set: Option<BTreeSet<OpcodeClassPlain>>,
+}
+
+thread_local! {
+    static CIRCUIT_PARAMS: RefCell<CircuitParams> = RefCell::new(CircuitParams::default());
+}
+
+/// Restores the parameters replaced by `with_circuit_params`, even on panic.
+struct CircuitParamsGuard(CircuitParams);
+
+impl Drop for CircuitParamsGuard {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.0);
+        CIRCUIT_PARAMS.with(|params| *params.borrow_mut() = previous);
+    }
+}
+
+/// `Circuit::configure` has no access to the circuit, so it reads the parameters set here:
+/// every halo2 call configuring a `TestCircuit` (`keygen_vk`, `keygen_pk`, `create_proof`,
+/// `MockProver::run`, reading a verifying key) must run in `f` with the parameters of the
+/// circuit. Outside of it the default parameters are configured. The calls synthesizing the
+/// circuit panic on a mismatch, reading a verifying key cannot tell.
+pub fn with_circuit_params<T>(params: &CircuitParams, f: impl FnOnce() -> T) -> T {
+    let _guard = CircuitParamsGuard(CIRCUIT_PARAMS.with(|current| current.replace(params.clone())));
+
+    f()
+}
+
+/// The parameters in scope, see `with_circuit_params`.
+pub(crate) fn circuit_params() -> CircuitParams {
+    CIRCUIT_PARAMS.with(|params| params.borrow().clone())
+}
+
+pub(crate) fn public_input_digest() -> bool {
+    CIRCUIT_PARAMS.with(|params| params.borrow().public_input_digest)
+}
+
+pub(crate) fn opcode_set() -> Option<BTreeSet<OpcodeClassPlain>> {
+    CIRCUIT_PARAMS.with(|params| params.borrow().opcode_set.clone())
+}
+
 pub fn init_zkwasm_runtime(k: u32) {
     set_zkwasm_k(k);
 }
 
-pub(crate) fn max_image_table_rows() -> u32 {
-    8192
+/// Rows of the image table for the instruction, br and elem tables, doubled with each
+/// increment of K from 8192 rows at `MIN_K`.
+pub fn max_image_table_rows() -> u32 {
+    8192 << (zkwasm_k() - MIN_K)
+}
+
+/// Rows of the init memory column, half of the rows of the circuit.
+pub fn max_init_memory_table_rows() -> u32 {
+    1 << (zkwasm_k() - 1)
 }
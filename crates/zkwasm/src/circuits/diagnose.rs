@@ -0,0 +1,437 @@
+//! Translate `MockProver` failures back to the trace.
+//!
+//! The etable, mtable and jtable share the region "jtable mtable etable" which starts at row 0,
+//! so a failing row identifies an entry of each table directly:
+//!
+//! * etable entry `i` occupies rows `[i * EVENT_TABLE_ENTRY_ROWS, (i + 1) * EVENT_TABLE_ENTRY_ROWS)`,
+//! * mtable entry `i` occupies rows `[i * MEMORY_TABLE_ENTRY_ROWS, (i + 1) * MEMORY_TABLE_ENTRY_ROWS)`,
+//! * jtable starts with two static entries followed by the dynamic ones,
+//!   each of them occupies `JtableOffsetMax` rows.
+//!
+//! The table a failing gate or lookup belongs to is recovered from its index, each table
+//! records the gates and lookups it configures in a `CircuitLayout`.
+
+use std::fmt::Display;
+use std::ops::Range;
+
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::dev::VerifyFailure;
+use halo2_proofs::plonk::ConstraintSystem;
+use serde::Serialize;
+use specs::Tables;
+
+use super::etable::EVENT_TABLE_ENTRY_ROWS;
+use super::jtable::JtableOffset;
+use super::mtable::MEMORY_TABLE_ENTRY_ROWS;
+use super::utils::table_entry::MemoryWritingTable;
+use crate::circuits::TestCircuit;
+use crate::loader::debug_info::DebugInfo;
+
+const STATIC_FRAME_ENTRIES: usize = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum FailureKind {
+    Constraint,
+    Lookup,
+    Permutation,
+    CellNotAssigned,
+    Other,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub enum FailureTable {
+    Etable,
+    Mtable,
+    Jtable,
+    BitTable,
+    RangeTable,
+    ImageTable,
+    MemoryTree,
+    ExternalHostCallTable,
+    WasmInputHelperTable,
+    ContextHelperTable,
+    U256HelperTable,
+    /// The failure cannot be attributed to a single table, e.g. a lookup or a permutation.
+    /// All entries located at the failing row are reported.
+    Unknown,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EtableLocation {
+    pub index: usize,
+    pub eid: u32,
+    pub fid: u32,
+    pub iid: u32,
+    pub opcode: String,
+    pub function_name: Option<String>,
+    pub source: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MtableLocation {
+    pub index: usize,
+    pub eid: u32,
+    pub end_eid: u32,
+    pub emid: u32,
+    pub ltype: String,
+    pub offset: u32,
+    pub value: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JtableLocation {
+    pub index: usize,
+    pub is_static: bool,
+    pub callee_fid: u32,
+    pub fid: u32,
+    pub iid: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FailureDiagnosis {
+    pub kind: FailureKind,
+    /// The failure as reported by `MockProver`.
+    pub description: String,
+    pub gate: Option<String>,
+    pub row: Option<usize>,
+    pub table: FailureTable,
+    pub etable_entry: Option<EtableLocation>,
+    pub mtable_entry: Option<MtableLocation>,
+    pub jtable_entry: Option<JtableLocation>,
+    pub cell_values: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DiagnosticReport {
+    pub failures: Vec<FailureDiagnosis>,
+}
+
+/// Extract the gate name from the `Display` of a gate or a constraint, i.e. the last `('...')`.
+fn gate_name(desc: &str) -> Option<String> {
+    let start = desc.rfind("('")?;
+    let end = desc.rfind("')")?;
+
+    (start + 2 <= end).then(|| desc[start + 2..end].to_owned())
+}
+
+/// Extract the gate index from the `Display` of a gate or a constraint, i.e. the number
+/// preceding the gate name.
+fn gate_index(desc: &str) -> Option<usize> {
+    let end = desc.rfind(" ('")?;
+    let start = desc[..end].rfind(' ')? + 1;
+
+    desc[start..end].parse().ok()
+}
+
+/// Indices of the gates and lookups configured by each table.
+#[derive(Clone, Debug, Default)]
+pub struct CircuitLayout {
+    gates: Vec<(Range<usize>, FailureTable)>,
+    lookups: Vec<(Range<usize>, FailureTable)>,
+}
+
+impl CircuitLayout {
+    /// Attributes the gates and lookups created by `configure` to `table`.
+    pub(crate) fn configure<F: FieldExt, C>(
+        &mut self,
+        meta: &mut ConstraintSystem<F>,
+        table: FailureTable,
+        configure: impl FnOnce(&mut ConstraintSystem<F>) -> C,
+    ) -> C {
+        let gates = meta.gates.len();
+        let lookups = meta.lookups.len();
+
+        let config = configure(meta);
+
+        self.gates.push((gates..meta.gates.len(), table));
+        self.lookups.push((lookups..meta.lookups.len(), table));
+
+        config
+    }
+
+    fn table_of(ranges: &[(Range<usize>, FailureTable)], index: usize) -> FailureTable {
+        ranges
+            .iter()
+            .find(|(range, _)| range.contains(&index))
+            .map_or(FailureTable::Unknown, |(_, table)| *table)
+    }
+
+    fn table_of_gate(&self, index: usize) -> FailureTable {
+        Self::table_of(&self.gates, index)
+    }
+
+    fn table_of_lookup(&self, index: usize) -> FailureTable {
+        Self::table_of(&self.lookups, index)
+    }
+}
+
+struct RowLocator<'a> {
+    tables: &'a Tables,
+    mtable: MemoryWritingTable,
+    debug_info: &'a DebugInfo,
+}
+
+impl<'a> RowLocator<'a> {
+    fn etable(&self, row: usize) -> Option<EtableLocation> {
+        let index = row / EVENT_TABLE_ENTRY_ROWS as usize;
+
+        self.tables
+            .execution_tables
+            .etable
+            .entries()
+            .get(index)
+            .map(|entry| EtableLocation {
+                index,
+                eid: entry.eid,
+                fid: entry.inst.fid,
+                iid: entry.inst.iid,
+                opcode: format!("{:?}", entry.inst.opcode),
+                function_name: self
+                    .debug_info
+                    .function_name(entry.inst.fid)
+                    .map(|name| name.to_owned()),
+                source: self
+                    .debug_info
+                    .location(entry.inst.fid, entry.inst.iid)
+                    .map(|location| location.to_string()),
+            })
+    }
+
+    fn mtable(&self, row: usize) -> Option<MtableLocation> {
+        let index = row / MEMORY_TABLE_ENTRY_ROWS as usize;
+
+        self.mtable.0.get(index).map(|entry| MtableLocation {
+            index,
+            eid: entry.entry.eid,
+            end_eid: entry.end_eid,
+            emid: entry.entry.emid,
+            ltype: format!("{:?}", entry.entry.ltype),
+            offset: entry.entry.offset,
+            value: entry.entry.value,
+        })
+    }
+
+    fn jtable(&self, row: usize) -> Option<JtableLocation> {
+        let index = row / JtableOffset::JtableOffsetMax as usize;
+
+        if index < STATIC_FRAME_ENTRIES {
+            self.tables
+                .compilation_tables
+                .static_jtable
+                .get(index)
+                .map(|entry| JtableLocation {
+                    index,
+                    is_static: true,
+                    callee_fid: entry.callee_fid,
+                    fid: entry.fid,
+                    iid: entry.iid,
+                })
+        } else {
+            let index = index - STATIC_FRAME_ENTRIES;
+
+            self.tables
+                .execution_tables
+                .jtable
+                .entries()
+                .get(index)
+                .map(|entry| JtableLocation {
+                    index,
+                    is_static: false,
+                    callee_fid: entry.callee_fid,
+                    fid: entry.inst.fid,
+                    iid: entry.inst.iid,
+                })
+        }
+    }
+
+    fn locate(
+        &self,
+        kind: FailureKind,
+        description: String,
+        (gate, table): (Option<String>, FailureTable),
+        row: Option<usize>,
+        cell_values: Vec<(String, String)>,
+    ) -> FailureDiagnosis {
+        let wants = |t: FailureTable| table == t || table == FailureTable::Unknown;
+
+        FailureDiagnosis {
+            kind,
+            description,
+            gate,
+            row,
+            table,
+            etable_entry: row
+                .filter(|_| wants(FailureTable::Etable))
+                .and_then(|row| self.etable(row)),
+            mtable_entry: row
+                .filter(|_| wants(FailureTable::Mtable))
+                .and_then(|row| self.mtable(row)),
+            jtable_entry: row
+                .filter(|_| wants(FailureTable::Jtable))
+                .and_then(|row| self.jtable(row)),
+            cell_values,
+        }
+    }
+}
+
+impl DiagnosticReport {
+    pub fn new<F: FieldExt>(
+        circuit: &TestCircuit<F>,
+        debug_info: &DebugInfo,
+        failures: Vec<VerifyFailure>,
+    ) -> Self {
+        let tables = &circuit.tables;
+        let layout = circuit.layout();
+        let locator = RowLocator {
+            tables,
+            mtable: tables.execution_tables.mtable.clone().into(),
+            debug_info,
+        };
+
+        let gate_of = |desc: String| {
+            (
+                gate_name(&desc),
+                gate_index(&desc)
+                    .map_or(FailureTable::Unknown, |index| layout.table_of_gate(index)),
+            )
+        };
+
+        let failures = failures
+            .into_iter()
+            .map(|failure| {
+                let description = format!("{}", failure);
+
+                match failure {
+                    VerifyFailure::ConstraintNotSatisfied {
+                        constraint,
+                        row,
+                        cell_values,
+                        ..
+                    } => locator.locate(
+                        FailureKind::Constraint,
+                        description,
+                        gate_of(format!("{}", constraint)),
+                        Some(row),
+                        cell_values
+                            .iter()
+                            .map(|(cell, value)| (format!("{:?}", cell), value.clone()))
+                            .collect(),
+                    ),
+                    VerifyFailure::CellNotAssigned {
+                        gate,
+                        region,
+                        offset,
+                        ..
+                    } => {
+                        // Offsets are relative to the region, only the shared region is mapped.
+                        let row = format!("{}", region)
+                            .contains("jtable mtable etable")
+                            .then_some(offset)
+                            .and_then(|offset| usize::try_from(offset).ok());
+
+                        locator.locate(
+                            FailureKind::CellNotAssigned,
+                            description,
+                            gate_of(format!("{}", gate)),
+                            row,
+                            vec![],
+                        )
+                    }
+                    VerifyFailure::Lookup {
+                        lookup_index, row, ..
+                    } => locator.locate(
+                        FailureKind::Lookup,
+                        description,
+                        (None, layout.table_of_lookup(lookup_index)),
+                        Some(row),
+                        vec![],
+                    ),
+                    VerifyFailure::Permutation { row, .. } => locator.locate(
+                        FailureKind::Permutation,
+                        description,
+                        (None, FailureTable::Unknown),
+                        Some(row),
+                        vec![],
+                    ),
+                    _ => locator.locate(
+                        FailureKind::Other,
+                        description,
+                        (None, FailureTable::Unknown),
+                        None,
+                        vec![],
+                    ),
+                }
+            })
+            .collect();
+
+        DiagnosticReport { failures }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
+
+impl Display for DiagnosticReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "mock prover failed with {} failure(s):",
+            self.failures.len()
+        )?;
+
+        for failure in &self.failures {
+            writeln!(f, "  {}", failure.description)?;
+
+            if let Some(entry) = &failure.etable_entry {
+                writeln!(
+                    f,
+                    "    etable[{}]: eid {} fid {}{} iid {} {}{}",
+                    entry.index,
+                    entry.eid,
+                    entry.fid,
+                    entry
+                        .function_name
+                        .as_ref()
+                        .map_or(String::new(), |name| format!(" ({})", name)),
+                    entry.iid,
+                    entry.opcode,
+                    entry
+                        .source
+                        .as_ref()
+                        .map_or(String::new(), |source| format!(" at {}", source))
+                )?;
+            }
+
+            if let Some(entry) = &failure.mtable_entry {
+                writeln!(
+                    f,
+                    "    mtable[{}]: eid {}..{} emid {} {} offset {} value {}",
+                    entry.index,
+                    entry.eid,
+                    entry.end_eid,
+                    entry.emid,
+                    entry.ltype,
+                    entry.offset,
+                    entry.value
+                )?;
+            }
+
+            if let Some(entry) = &failure.jtable_entry {
+                writeln!(
+                    f,
+                    "    jtable[{}{}]: callee fid {} from fid {} iid {}",
+                    if entry.is_static { "static " } else { "" },
+                    entry.index,
+                    entry.callee_fid,
+                    entry.fid,
+                    entry.iid
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for DiagnosticReport {}
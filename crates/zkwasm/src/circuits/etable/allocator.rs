@@ -154,14 +154,187 @@ pub(crate) enum EventTableCellType {
     JTableLookup,
 }
 
-const BIT_COLUMNS: usize = 12;
-const U8_COLUMNS: usize = 1;
-const U64_CELLS: usize = 5;
-const U16_COLUMNS: usize = U64_CELLS;
-const COMMON_RANGE_COLUMNS: usize = 7;
-const UNLIMITED_COLUMNS: usize = 7;
-const MEMORY_TABLE_LOOKUP_COLUMNS: usize = 2;
-const JUMP_TABLE_LOOKUP_COLUMNS: usize = 1;
+impl EventTableCellType {
+    const ALL: [EventTableCellType; 7] = [
+        EventTableCellType::Bit,
+        EventTableCellType::U8,
+        EventTableCellType::U16,
+        EventTableCellType::CommonRange,
+        EventTableCellType::Unlimited,
+        EventTableCellType::MTableLookup,
+        EventTableCellType::JTableLookup,
+    ];
+
+    /// Advice columns backing one column of cells.
+    fn width(&self) -> usize {
+        match self {
+            EventTableCellType::MTableLookup => 4,
+            _ => 1,
+        }
+    }
+}
+
+/// Cells taken from an allocator. The u16 and unlimited cells backing u64 cells are counted
+/// by `u64_cells` only.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct EventTableCellUsage {
+    cells: BTreeMap<EventTableCellType, usize>,
+    u64_cells: usize,
+}
+
+impl EventTableCellUsage {
+    fn cells(&self, t: &EventTableCellType) -> usize {
+        self.cells.get(t).cloned().unwrap_or(0)
+    }
+}
+
+/// Columns of each cell type and u64 cells to create.
+#[derive(Debug, Clone)]
+pub(crate) struct EventTableCellLayout {
+    columns: BTreeMap<EventTableCellType, usize>,
+    u64_cells: usize,
+}
+
+impl EventTableCellLayout {
+    /// Cells of type `t` taken by preparing the u64 cells.
+    fn u64_part_cells(&self, t: &EventTableCellType) -> usize {
+        match t {
+            EventTableCellType::U16 => self.u64_cells * 4,
+            EventTableCellType::Unlimited => self.u64_cells,
+            _ => 0,
+        }
+    }
+}
+
+/// Collects the cell usage of the common config and of each op config.
+///
+/// All op configs are configured from a clone of the allocator holding the common cells,
+/// so the columns must fit the peak usage over them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EventTableCellProfiler {
+    common: EventTableCellUsage,
+    ops: Vec<(&'static str, EventTableCellUsage)>,
+}
+
+impl EventTableCellProfiler {
+    pub(crate) fn new<F: FieldExt>(common: &EventTableCellAllocator<F>) -> Self {
+        Self {
+            common: common.usage(),
+            ops: vec![],
+        }
+    }
+
+    pub(crate) fn record<F: FieldExt>(
+        &mut self,
+        op: &'static str,
+        allocator: &EventTableCellAllocator<F>,
+    ) {
+        self.ops.push((op, allocator.usage()));
+    }
+
+    /// The op config taking most cells of type `t`, with its usage.
+    fn peak(&self, t: &EventTableCellType) -> (Option<&'static str>, usize) {
+        self.ops.iter().fold(
+            (None, self.common.cells(t)),
+            |(peak_op, peak), (op, usage)| {
+                if usage.cells(t) > peak {
+                    (Some(*op), usage.cells(t))
+                } else {
+                    (peak_op, peak)
+                }
+            },
+        )
+    }
+
+    /// The smallest layout fitting all recorded usages.
+    pub(crate) fn layout(&self) -> EventTableCellLayout {
+        let mut layout = EventTableCellLayout {
+            columns: BTreeMap::new(),
+            u64_cells: self
+                .ops
+                .iter()
+                .map(|(_, usage)| usage.u64_cells)
+                .fold(self.common.u64_cells, usize::max),
+        };
+
+        for t in EventTableCellType::ALL {
+            let cells = layout.u64_part_cells(&t) + self.peak(&t).1;
+
+            layout.columns.insert(
+                t,
+                (cells + EVENT_TABLE_ENTRY_ROWS as usize - 1) / EVENT_TABLE_ENTRY_ROWS as usize,
+            );
+        }
+
+        layout
+    }
+}
+
+/// How much of the columns of a cell type is used by the op config taking most cells.
+#[derive(Debug, Clone)]
+pub(crate) struct EventTableCellUtilization {
+    pub(crate) cell_type: EventTableCellType,
+    pub(crate) columns: usize,
+    pub(crate) cells: usize,
+    /// Cells backing the u64 cells.
+    pub(crate) u64_part: usize,
+    pub(crate) peak: usize,
+    /// `None` if no op config takes more cells than the common config.
+    pub(crate) peak_op: Option<&'static str>,
+}
+
+impl EventTableCellProfiler {
+    pub(crate) fn utilization(&self) -> Vec<EventTableCellUtilization> {
+        let layout = self.layout();
+
+        EventTableCellType::ALL
+            .iter()
+            .map(|t| {
+                let (peak_op, peak) = self.peak(t);
+
+                EventTableCellUtilization {
+                    cell_type: *t,
+                    columns: layout.columns[t] * t.width(),
+                    cells: layout.columns[t] * EVENT_TABLE_ENTRY_ROWS as usize,
+                    u64_part: layout.u64_part_cells(t),
+                    peak,
+                    peak_op,
+                }
+            })
+            .collect()
+    }
+}
+
+impl std::fmt::Display for EventTableCellProfiler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<14}{:>9}{:>7}{:>6}{:>7}{:>8}  {}",
+            "cell type", "columns", "cells", "u64", "peak", "usage", "peak op"
+        )?;
+
+        for utilization in self.utilization() {
+            writeln!(
+                f,
+                "{:<14}{:>9}{:>7}{:>6}{:>7}{:>7.1}%  {}",
+                format!("{:?}", utilization.cell_type),
+                utilization.columns,
+                utilization.cells,
+                utilization.u64_part,
+                utilization.peak,
+                if utilization.cells == 0 {
+                    100.0
+                } else {
+                    (utilization.u64_part + utilization.peak) as f64 * 100.0
+                        / utilization.cells as f64
+                },
+                utilization.peak_op.unwrap_or("common"),
+            )?;
+        }
+
+        write!(f, "u64 cells: {}", self.layout().u64_cells)
+    }
+}
 
 #[derive(Clone, Copy)]
 pub(crate) struct AllocatedBitTableLookupCells<F: FieldExt> {
@@ -194,6 +367,11 @@ pub(crate) struct EventTableCellAllocator<F: FieldExt> {
     pub(crate) free_cells: BTreeMap<EventTableCellType, (usize, u32)>,
     all_cols: BTreeMap<EventTableCellType, Vec<Vec<Column<Advice>>>>,
     free_u64_cells: Vec<AllocatedU64Cell<F>>,
+    prepared_u64_cells: usize,
+    taken_u64_cells: usize,
+    /// Set when profiling: cells beyond the columns reuse the last column, and u64 cells are
+    /// taken from this placeholder, so that any usage can be recorded.
+    placeholder_u64_cell: Option<AllocatedU64Cell<F>>,
     _mark: PhantomData<F>,
 }
 
@@ -240,13 +418,36 @@ impl<F: FieldExt> EventTableCellAllocator<F> {
         rtable: &RangeTableConfig<F>,
         mtable: &impl ConfigureLookupTable<F>,
         jtable: &JumpTableConfig<F>,
-        cols: &mut impl Iterator<Item = Column<Advice>>,
+        layout: &EventTableCellLayout,
     ) -> Self {
-        let mut allocator = Self::_new(meta, sel, rtable, mtable, jtable, cols);
-        for _ in 0..U64_CELLS {
+        let mut allocator = Self::_new(meta, sel, rtable, mtable, jtable, &layout.columns);
+        for _ in 0..layout.u64_cells {
             let cell = allocator.prepare_alloc_u64_cell(meta, |meta| fixed_curr!(meta, sel));
             allocator.free_u64_cells.push(cell);
         }
+        allocator.prepared_u64_cells = layout.u64_cells;
+        allocator
+    }
+
+    /// An allocator with a single column of each type which never runs out of cells, to
+    /// collect the usage of the configs with an `EventTableCellProfiler`.
+    pub(super) fn new_for_profiling(
+        meta: &mut ConstraintSystem<F>,
+        sel: Column<Fixed>,
+        rtable: &RangeTableConfig<F>,
+        mtable: &impl ConfigureLookupTable<F>,
+        jtable: &JumpTableConfig<F>,
+    ) -> Self {
+        let columns = EventTableCellType::ALL.iter().map(|t| (*t, 1)).collect();
+
+        let mut allocator = Self::_new(meta, sel, rtable, mtable, jtable, &columns);
+        allocator.placeholder_u64_cell = Some(AllocatedU64Cell {
+            u16_cells_le: [0; 4]
+                .map(|_| AllocatedU16Cell(allocator.placeholder_cell(&EventTableCellType::U16))),
+            u64_cell: AllocatedUnlimitedCell(
+                allocator.placeholder_cell(&EventTableCellType::Unlimited),
+            ),
+        });
         allocator
     }
 
@@ -256,69 +457,85 @@ impl<F: FieldExt> EventTableCellAllocator<F> {
         rtable: &RangeTableConfig<F>,
         mtable: &impl ConfigureLookupTable<F>,
         jtable: &JumpTableConfig<F>,
-        cols: &mut impl Iterator<Item = Column<Advice>>,
+        columns: &BTreeMap<EventTableCellType, usize>,
     ) -> Self {
+        let columns = |t: EventTableCellType| columns.get(&t).cloned().unwrap_or(0);
+
         let mut all_cols = BTreeMap::new();
         all_cols.insert(
             EventTableCellType::Bit,
-            [0; BIT_COLUMNS]
-                .map(|_| vec![BitColumn::configure(meta, cols, |meta| fixed_curr!(meta, sel)).col])
-                .into_iter()
+            (0..columns(EventTableCellType::Bit))
+                .map(|_| {
+                    let col = meta.advice_column();
+                    BitColumn::configure(meta, &mut [col].into_iter(), |meta| {
+                        fixed_curr!(meta, sel)
+                    });
+                    vec![col]
+                })
                 .collect(),
         );
         all_cols.insert(
             EventTableCellType::U8,
-            [0; U8_COLUMNS]
-                .map(|_| vec![U8Column::configure(meta, cols, rtable, |_| constant_from!(1)).col])
-                .into_iter()
+            (0..columns(EventTableCellType::U8))
+                .map(|_| {
+                    let col = meta.advice_column();
+                    U8Column::configure(meta, &mut [col].into_iter(), rtable, |_| {
+                        constant_from!(1)
+                    });
+                    vec![col]
+                })
                 .collect(),
         );
         all_cols.insert(
             EventTableCellType::U16,
-            [0; U16_COLUMNS]
-                .map(|_| vec![U16Column::configure(meta, cols, rtable, |_| constant_from!(1)).col])
-                .into_iter()
+            (0..columns(EventTableCellType::U16))
+                .map(|_| {
+                    let col = meta.advice_column();
+                    U16Column::configure(meta, &mut [col].into_iter(), rtable, |_| {
+                        constant_from!(1)
+                    });
+                    vec![col]
+                })
                 .collect(),
         );
         all_cols.insert(
             EventTableCellType::CommonRange,
-            [0; COMMON_RANGE_COLUMNS]
+            (0..columns(EventTableCellType::CommonRange))
                 .map(|_| {
-                    vec![
-                        CommonRangeColumn::configure(meta, cols, rtable, |_| constant_from!(1)).col,
-                    ]
+                    let col = meta.advice_column();
+                    CommonRangeColumn::configure(meta, &mut [col].into_iter(), rtable, |_| {
+                        constant_from!(1)
+                    });
+                    vec![col]
                 })
-                .into_iter()
                 .collect(),
         );
         all_cols.insert(
             EventTableCellType::Unlimited,
-            [0; UNLIMITED_COLUMNS]
-                .map(|_| vec![cols.next().unwrap()])
-                .into_iter()
+            (0..columns(EventTableCellType::Unlimited))
+                .map(|_| vec![meta.advice_column()])
                 .collect(),
         );
         all_cols.insert(
             EventTableCellType::JTableLookup,
-            [0; JUMP_TABLE_LOOKUP_COLUMNS]
+            (0..columns(EventTableCellType::JTableLookup))
                 .map(|_| {
-                    let col = cols.next().unwrap();
+                    let col = meta.advice_column();
                     jtable.configure_in_table(meta, "c8c. jtable_lookup in jtable", |meta| {
                         curr!(meta, col)
                     });
                     vec![col]
                 })
-                .into_iter()
                 .collect(),
         );
         all_cols.insert(
             EventTableCellType::MTableLookup,
-            [0; MEMORY_TABLE_LOOKUP_COLUMNS]
+            (0..columns(EventTableCellType::MTableLookup))
                 .map(|_| {
-                    let start_eid_col = cols.next().unwrap();
-                    let end_eid_col = cols.next().unwrap();
-                    let encode_col = cols.next().unwrap();
-                    let value_col = cols.next().unwrap();
+                    let start_eid_col = meta.advice_column();
+                    let end_eid_col = meta.advice_column();
+                    let encode_col = meta.advice_column();
+                    let value_col = meta.advice_column();
                     mtable.configure_in_table(meta, "c8e. mtable_lookup in mtable", |meta| {
                         vec![
                             curr!(meta, start_eid_col),
@@ -329,54 +546,70 @@ impl<F: FieldExt> EventTableCellAllocator<F> {
                     });
                     vec![start_eid_col, end_eid_col, encode_col, value_col]
                 })
-                .into_iter()
                 .collect(),
         );
 
         Self {
             all_cols,
-            free_cells: BTreeMap::from_iter(
-                vec![
-                    (EventTableCellType::Bit, (0, 0)),
-                    (EventTableCellType::U8, (0, 0)),
-                    (EventTableCellType::U16, (0, 0)),
-                    (EventTableCellType::CommonRange, (0, 0)),
-                    (EventTableCellType::Unlimited, (0, 0)),
-                    (EventTableCellType::MTableLookup, (0, 0)),
-                    (EventTableCellType::JTableLookup, (0, 0)),
-                ]
-                .into_iter(),
-            ),
+            free_cells: EventTableCellType::ALL
+                .iter()
+                .map(|t| (*t, (0, 0)))
+                .collect(),
             free_u64_cells: vec![],
+            prepared_u64_cells: 0,
+            taken_u64_cells: 0,
+            placeholder_u64_cell: None,
             _mark: PhantomData,
         }
     }
 
-    fn alloc(&mut self, t: &EventTableCellType) -> AllocatedCell<F> {
-        let v = self.free_cells.get_mut(t).unwrap();
+    pub(super) fn usage(&self) -> EventTableCellUsage {
+        let mut cells: BTreeMap<_, _> = self
+            .free_cells
+            .iter()
+            .map(|(t, (col, rot))| (*t, col * EVENT_TABLE_ENTRY_ROWS as usize + *rot as usize))
+            .collect();
 
-        let res = AllocatedCell {
-            col: self.all_cols.get(t).unwrap()[v.0][0],
-            rot: v.1 as i32,
-            _mark: PhantomData,
-        };
+        // Exclude the cells backing the prepared u64 cells.
+        *cells.get_mut(&EventTableCellType::U16).unwrap() -= self.prepared_u64_cells * 4;
+        *cells.get_mut(&EventTableCellType::Unlimited).unwrap() -= self.prepared_u64_cells;
 
-        v.1 += 1;
-        if v.1 == EVENT_TABLE_ENTRY_ROWS as u32 {
-            v.0 += 1;
-            v.1 = 0;
+        EventTableCellUsage {
+            cells,
+            u64_cells: self.taken_u64_cells,
         }
+    }
 
-        res
+    fn placeholder_cell(&self, t: &EventTableCellType) -> AllocatedCell<F> {
+        AllocatedCell {
+            col: self.all_cols.get(t).unwrap()[0][0],
+            rot: 0,
+            _mark: PhantomData,
+        }
+    }
+
+    /// The columns backing the next free cell of type `t`.
+    fn cols(&self, t: &EventTableCellType) -> &Vec<Column<Advice>> {
+        let cols = self.all_cols.get(t).unwrap();
+        let index = self.free_cells.get(t).unwrap().0;
+
+        cols.get(index)
+            .or_else(|| cols.last().filter(|_| self.placeholder_u64_cell.is_some()))
+            .unwrap_or_else(|| panic!("no more free {:?} cells", t))
+    }
+
+    fn alloc(&mut self, t: &EventTableCellType) -> AllocatedCell<F> {
+        self.alloc_group(t)[0]
     }
 
     fn alloc_group(&mut self, t: &EventTableCellType) -> Vec<AllocatedCell<F>> {
+        let cols = self.cols(t).clone();
         let v = self.free_cells.get_mut(t).unwrap();
 
-        let res = self.all_cols.get(t).unwrap()[v.0]
-            .iter()
+        let res = cols
+            .into_iter()
             .map(|col| AllocatedCell {
-                col: *col,
+                col,
                 rot: v.1 as i32,
                 _mark: PhantomData,
             })
@@ -391,6 +624,15 @@ impl<F: FieldExt> EventTableCellAllocator<F> {
         res
     }
 
+    fn take_u64_cell(&mut self) -> AllocatedU64Cell<F> {
+        self.taken_u64_cells += 1;
+
+        self.free_u64_cells
+            .pop()
+            .or(self.placeholder_u64_cell)
+            .expect("no more free u64 cells")
+    }
+
     pub(crate) fn alloc_bit_cell(&mut self) -> AllocatedBitCell<F> {
         AllocatedBitCell(self.alloc(&EventTableCellType::Bit))
     }
@@ -594,7 +836,7 @@ impl<F: FieldExt> EventTableCellAllocator<F> {
     }
 
     pub(crate) fn alloc_u64_cell(&mut self) -> AllocatedU64Cell<F> {
-        self.free_u64_cells.pop().expect("no more free u64 cells")
+        self.take_u64_cell()
     }
 
     pub(crate) fn alloc_u64_with_flag_bit_cell_dyn(
@@ -602,7 +844,7 @@ impl<F: FieldExt> EventTableCellAllocator<F> {
         constraint_builder: &mut ConstraintBuilder<F>,
         is_i32: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F> + 'static,
     ) -> AllocatedU64CellWithFlagBitDyn<F> {
-        let value = self.free_u64_cells.pop().expect("no more free u64 cells");
+        let value = self.take_u64_cell();
         let flag_bit_cell = self.alloc_bit_cell();
         let flag_u16_rem_cell = self.alloc_common_range_cell();
         let flag_u16_rem_diff_cell = self.alloc_common_range_cell();
@@ -638,7 +880,7 @@ impl<F: FieldExt> EventTableCellAllocator<F> {
         is_i32: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F> + 'static,
         is_sign: impl Fn(&mut VirtualCells<'_, F>) -> Expression<F> + 'static,
     ) -> AllocatedU64CellWithFlagBitDynSign<F> {
-        let value = self.free_u64_cells.pop().expect("no more free u64 cells");
+        let value = self.take_u64_cell();
         let flag_bit_cell = self.alloc_bit_cell();
         let flag_u16_rem_cell = self.alloc_common_range_cell();
         let flag_u16_rem_diff_cell = self.alloc_common_range_cell();
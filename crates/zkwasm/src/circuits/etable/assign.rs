@@ -25,6 +25,22 @@ pub(in crate::circuits) struct EventTablePermutationCells {
     pub(in crate::circuits) fid_of_entry: Cell,
     pub(in crate::circuits) initial_memory_pages: Cell,
     pub(in crate::circuits) maximal_memory_pages: Cell,
+    /// Number of public inputs and outputs plus one, at the end of the table.
+    pub(in crate::circuits) input_index: Cell,
+    /// Number of context reads plus one, at the end of the table.
+    pub(in crate::circuits) context_input_index: Cell,
+    /// Number of context writes plus one, at the end of the table.
+    pub(in crate::circuits) context_output_index: Cell,
+}
+
+/// Cells returned by `assign_entries`.
+struct EventTableEntriesCells {
+    fid_of_entry: Cell,
+    initial_memory_pages: Cell,
+    maximal_memory_pages: Cell,
+    input_index: Cell,
+    context_input_index: Cell,
+    context_output_index: Cell,
 }
 
 impl<F: FieldExt> EventTableChip<F> {
@@ -111,6 +127,47 @@ impl<F: FieldExt> EventTableChip<F> {
         Ok((rest_mops_cell.cell(), rest_mops_jell.cell()))
     }
 
+    /// Keep the public input and context indexes from the current step to the end of the
+    /// table, where their cells are at a fixed position whatever the length of the trace.
+    fn assign_indexes_until_end(
+        &self,
+        ctx: &mut Context<'_, F>,
+        host_public_inputs: u32,
+        context_in_index: u32,
+        context_out_index: u32,
+    ) -> Result<(Cell, Cell, Cell), Error> {
+        let end = self.max_available_rows / EVENT_TABLE_ENTRY_ROWS as usize
+            * EVENT_TABLE_ENTRY_ROWS as usize;
+
+        loop {
+            let public_input_index_cell = self
+                .config
+                .common_config
+                .input_index_cell
+                .assign(ctx, F::from(host_public_inputs as u64))?;
+            let input_index_cell = self
+                .config
+                .common_config
+                .context_input_index_cell
+                .assign(ctx, F::from(context_in_index as u64))?;
+            let output_index_cell = self
+                .config
+                .common_config
+                .context_output_index_cell
+                .assign(ctx, F::from(context_out_index as u64))?;
+
+            if ctx.offset >= end {
+                return Ok((
+                    public_input_index_cell.cell(),
+                    input_index_cell.cell(),
+                    output_index_cell.cell(),
+                ));
+            }
+
+            ctx.step(EVENT_TABLE_ENTRY_ROWS as usize);
+        }
+    }
+
     fn assign_entries(
         &self,
         ctx: &mut Context<'_, F>,
@@ -119,7 +176,7 @@ impl<F: FieldExt> EventTableChip<F> {
         configure_table: &ConfigureTable,
         fid_of_entry: u32,
         rest_ops: Vec<(u32, u32)>,
-    ) -> Result<(Cell, Cell, Cell), Error> {
+    ) -> Result<EventTableEntriesCells, Error> {
         macro_rules! assign_advice {
             ($cell:ident, $value:expr) => {
                 self.config.common_config.$cell.assign(ctx, $value)?
@@ -175,11 +232,23 @@ impl<F: FieldExt> EventTableChip<F> {
         {
             let assigned_cell = assign_advice!(enabled_cell, F::zero());
             if assigned_cell.value().is_none() {
-                return Ok((
-                    fid_of_entry_cell.cell(),
-                    initial_memory_pages_cell.cell(),
-                    maximal_memory_pages_cell.cell(),
-                ));
+                ctx.step(EVENT_TABLE_ENTRY_ROWS as usize);
+                let (input_index, context_input_index, context_output_index) = self
+                    .assign_indexes_until_end(
+                        ctx,
+                        host_public_inputs,
+                        context_in_index,
+                        context_out_index,
+                    )?;
+
+                return Ok(EventTableEntriesCells {
+                    fid_of_entry: fid_of_entry_cell.cell(),
+                    initial_memory_pages: initial_memory_pages_cell.cell(),
+                    maximal_memory_pages: maximal_memory_pages_cell.cell(),
+                    input_index,
+                    context_input_index,
+                    context_output_index,
+                });
             }
         }
 
@@ -187,11 +256,23 @@ impl<F: FieldExt> EventTableChip<F> {
          * The length of event_table equals 0: without_witness
          */
         if event_table.0.len() == 0 {
-            return Ok((
-                fid_of_entry_cell.cell(),
-                initial_memory_pages_cell.cell(),
-                maximal_memory_pages_cell.cell(),
-            ));
+            ctx.step(EVENT_TABLE_ENTRY_ROWS as usize);
+            let (input_index, context_input_index, context_output_index) = self
+                .assign_indexes_until_end(
+                    ctx,
+                    host_public_inputs,
+                    context_in_index,
+                    context_out_index,
+                )?;
+
+            return Ok(EventTableEntriesCells {
+                fid_of_entry: fid_of_entry_cell.cell(),
+                initial_memory_pages: initial_memory_pages_cell.cell(),
+                maximal_memory_pages: maximal_memory_pages_cell.cell(),
+                input_index,
+                context_input_index,
+                context_output_index,
+            });
         }
 
         let status = {
@@ -245,7 +326,12 @@ impl<F: FieldExt> EventTableChip<F> {
             {
                 let class: OpcodeClassPlain = entry.eentry.inst.opcode.clone().into();
 
-                let op = self.config.common_config.ops[class.index()];
+                let op = *self
+                    .config
+                    .common_config
+                    .ops
+                    .get(&class)
+                    .unwrap_or_else(|| panic!("opcode class {:?} is not configured", class));
                 assign_advice_cell!(op, F::one());
             }
 
@@ -312,19 +398,27 @@ impl<F: FieldExt> EventTableChip<F> {
             maximal_memory_pages_cell,
             F::from(configure_table.maximal_memory_pages as u64)
         );
-        assign_advice!(input_index_cell, F::from(host_public_inputs as u64));
-        assign_advice!(context_input_index_cell, F::from(context_in_index as u64));
-        assign_advice!(context_output_index_cell, F::from(context_out_index as u64));
         assign_advice!(
             external_host_call_index_cell,
             F::from(external_host_call_call_index as u64)
         );
 
-        Ok((
-            fid_of_entry_cell.cell(),
-            initial_memory_pages_cell.cell(),
-            maximal_memory_pages_cell.cell(),
-        ))
+        let (input_index, context_input_index, context_output_index) = self
+            .assign_indexes_until_end(
+                ctx,
+                host_public_inputs,
+                context_in_index,
+                context_out_index,
+            )?;
+
+        Ok(EventTableEntriesCells {
+            fid_of_entry: fid_of_entry_cell.cell(),
+            initial_memory_pages: initial_memory_pages_cell.cell(),
+            maximal_memory_pages: maximal_memory_pages_cell.cell(),
+            input_index,
+            context_input_index,
+            context_output_index,
+        })
     }
 
     pub(in crate::circuits) fn assign(
@@ -349,7 +443,7 @@ impl<F: FieldExt> EventTableChip<F> {
         )?;
         ctx.reset();
 
-        let (fid_of_entry, initial_memory_pages, maximal_memory_pages) = self.assign_entries(
+        let entries_cells = self.assign_entries(
             ctx,
             &self.config.op_configs,
             event_table,
@@ -362,9 +456,12 @@ impl<F: FieldExt> EventTableChip<F> {
         Ok(EventTablePermutationCells {
             rest_mops: Some(rest_mops_cell),
             rest_jops: Some(rest_jops_cell),
-            fid_of_entry,
-            initial_memory_pages,
-            maximal_memory_pages,
+            fid_of_entry: entries_cells.fid_of_entry,
+            initial_memory_pages: entries_cells.initial_memory_pages,
+            maximal_memory_pages: entries_cells.maximal_memory_pages,
+            input_index: entries_cells.input_index,
+            context_input_index: entries_cells.context_input_index,
+            context_output_index: entries_cells.context_output_index,
         })
     }
 }
@@ -11,8 +11,11 @@ use super::traits::ConfigureLookupTable;
 use super::utils::step_status::StepStatus;
 use super::utils::table_entry::EventTableEntryWithMemoryInfo;
 use super::utils::Context;
+use crate::circuits::config::opcode_set;
 use crate::circuits::etable::op_configure::op_bin::BinConfigBuilder;
 use crate::circuits::etable::op_configure::op_bin_bit::BinBitConfigBuilder;
+use crate::circuits::etable::op_configure::op_bin_const::BinConstConfigBuilder;
+use crate::circuits::etable::op_configure::op_bin_local::BinLocalConfigBuilder;
 use crate::circuits::etable::op_configure::op_bin_shift::BinShiftConfigBuilder;
 use crate::circuits::etable::op_configure::op_br::BrConfigBuilder;
 use crate::circuits::etable::op_configure::op_br_if::BrIfConfigBuilder;
@@ -47,13 +50,13 @@ use crate::foreign::EventTableForeignCallConfigBuilder;
 use crate::foreign::ForeignTableConfig;
 use crate::foreign::InternalHostPluginBuilder;
 use halo2_proofs::arithmetic::FieldExt;
-use halo2_proofs::plonk::Advice;
 use halo2_proofs::plonk::Column;
 use halo2_proofs::plonk::ConstraintSystem;
 use halo2_proofs::plonk::Error;
 use halo2_proofs::plonk::Expression;
 use halo2_proofs::plonk::Fixed;
 use halo2_proofs::plonk::VirtualCells;
+use log::debug;
 use specs::encode::instruction_table::encode_instruction_table_entry;
 use specs::etable::EventTableEntry;
 use specs::itable::OpcodeClass;
@@ -68,14 +71,14 @@ pub(crate) mod allocator;
 pub(crate) mod constraint_builder;
 
 pub(crate) const EVENT_TABLE_ENTRY_ROWS: i32 = 4;
-pub(crate) const OP_CAPABILITY: usize = 32;
+pub(crate) const OP_CAPABILITY: usize = 34;
 
 const FOREIGN_LOOKUP_CAPABILITY: usize = 6;
 
 #[derive(Clone)]
 pub struct EventTableCommonConfig<F: FieldExt> {
     enabled_cell: AllocatedBitCell<F>,
-    ops: [AllocatedBitCell<F>; OP_CAPABILITY],
+    ops: BTreeMap<OpcodeClassPlain, AllocatedBitCell<F>>,
 
     rest_mops_cell: AllocatedCommonRangeCell<F>,
     rest_jops_cell: AllocatedCommonRangeCell<F>,
@@ -98,6 +101,7 @@ pub struct EventTableCommonConfig<F: FieldExt> {
     pow_table_lookup_power_cell: AllocatedUnlimitedCell<F>,
     bit_table_lookup_cells: AllocatedBitTableLookupCells<F>,
     external_foreign_call_lookup_cell: AllocatedUnlimitedCell<F>,
+    u256_result_lookup_cell: AllocatedUnlimitedCell<F>,
 }
 
 pub(in crate::circuits::etable) trait EventTableOpcodeConfigBuilder<F: FieldExt> {
@@ -207,12 +211,58 @@ pub struct EventTableConfig<F: FieldExt> {
     pub step_sel: Column<Fixed>,
     pub common_config: EventTableCommonConfig<F>,
     op_configs: BTreeMap<OpcodeClassPlain, Rc<Box<dyn EventTableOpcodeConfig<F>>>>,
+    pub(crate) cell_profiler: EventTableCellProfiler,
 }
 
 impl<F: FieldExt> EventTableConfig<F> {
+    /// The op configs are first configured against a scratch constraint system to collect
+    /// their cell usage, then against `meta` with exactly the columns they need.
     pub(crate) fn configure(
         meta: &mut ConstraintSystem<F>,
-        cols: &mut (impl Iterator<Item = Column<Advice>> + Clone),
+        rtable: &RangeTableConfig<F>,
+        image_table: &ImageTableConfig<F>,
+        mtable: &MemoryTableConfig<F>,
+        jtable: &JumpTableConfig<F>,
+        bit_table: &BitTableConfig<F>,
+        external_host_call_table: &ExternalHostCallTableConfig<F>,
+        foreign_table_configs: &BTreeMap<&'static str, Box<dyn ForeignTableConfig<F>>>,
+    ) -> EventTableConfig<F> {
+        let profiler = Self::configure_with_layout(
+            &mut ConstraintSystem::default(),
+            None,
+            rtable,
+            image_table,
+            mtable,
+            jtable,
+            bit_table,
+            external_host_call_table,
+            foreign_table_configs,
+        )
+        .cell_profiler;
+
+        let config = Self::configure_with_layout(
+            meta,
+            Some(&profiler.layout()),
+            rtable,
+            image_table,
+            mtable,
+            jtable,
+            bit_table,
+            external_host_call_table,
+            foreign_table_configs,
+        );
+        assert_eq!(config.cell_profiler, profiler);
+
+        debug!("event table cell utilization:\n{}", profiler);
+
+        config
+    }
+
+    /// Configure with the columns of `layout`. If it is `None`, only the cells and the op
+    /// configs are configured, with profiling columns, to collect their usage.
+    fn configure_with_layout(
+        meta: &mut ConstraintSystem<F>,
+        layout: Option<&EventTableCellLayout>,
         rtable: &RangeTableConfig<F>,
         image_table: &ImageTableConfig<F>,
         mtable: &MemoryTableConfig<F>,
@@ -223,10 +273,24 @@ impl<F: FieldExt> EventTableConfig<F> {
     ) -> EventTableConfig<F> {
         let step_sel = meta.fixed_column();
 
-        let mut allocator =
-            EventTableCellAllocator::new(meta, step_sel, rtable, mtable, jtable, cols);
+        let mut allocator = match layout {
+            Some(layout) => {
+                EventTableCellAllocator::new(meta, step_sel, rtable, mtable, jtable, layout)
+            }
+            None => {
+                EventTableCellAllocator::new_for_profiling(meta, step_sel, rtable, mtable, jtable)
+            }
+        };
+
+        let opcode_set = opcode_set();
+        let is_configured =
+            |op: &OpcodeClassPlain| opcode_set.as_ref().map_or(true, |set| set.contains(op));
 
-        let ops = [0; OP_CAPABILITY].map(|_| allocator.alloc_bit_cell());
+        let ops: BTreeMap<_, _> = (0..OP_CAPABILITY)
+            .map(OpcodeClassPlain)
+            .filter(is_configured)
+            .map(|op| (op, allocator.alloc_bit_cell()))
+            .collect();
         let enabled_cell = allocator.alloc_bit_cell();
 
         let rest_mops_cell = allocator.alloc_common_range_cell();
@@ -261,6 +325,7 @@ impl<F: FieldExt> EventTableConfig<F> {
         let pow_table_lookup_modulus_cell = allocator.alloc_unlimited_cell();
         let pow_table_lookup_power_cell = allocator.alloc_unlimited_cell();
         let external_foreign_call_lookup_cell = allocator.alloc_unlimited_cell();
+        let u256_result_lookup_cell = allocator.alloc_unlimited_cell();
         let bit_table_lookup_cells = allocator.alloc_bit_table_lookup_cells();
 
         let mut foreign_table_reserved_lookup_cells = [(); FOREIGN_LOOKUP_CAPABILITY]
@@ -269,7 +334,7 @@ impl<F: FieldExt> EventTableConfig<F> {
 
         let common_config = EventTableCommonConfig {
             enabled_cell,
-            ops,
+            ops: ops.clone(),
             rest_mops_cell,
             rest_jops_cell,
             input_index_cell,
@@ -290,9 +355,11 @@ impl<F: FieldExt> EventTableConfig<F> {
             pow_table_lookup_power_cell,
             bit_table_lookup_cells,
             external_foreign_call_lookup_cell,
+            u256_result_lookup_cell,
         };
 
-        let mut op_bitmaps: BTreeMap<OpcodeClassPlain, usize> = BTreeMap::new();
+        let mut cell_profiler = EventTableCellProfiler::new(&allocator);
+
         let mut op_configs: BTreeMap<OpcodeClassPlain, Rc<Box<dyn EventTableOpcodeConfig<F>>>> =
             BTreeMap::new();
 
@@ -300,21 +367,20 @@ impl<F: FieldExt> EventTableConfig<F> {
             ($op:expr, $x:ident) => {
                 let op = OpcodeClassPlain($op as usize);
 
-                let foreign_table_configs = BTreeMap::new();
-                let mut constraint_builder = ConstraintBuilder::new(meta, &foreign_table_configs);
+                if is_configured(&op) {
+                    let mut constraint_builder =
+                        ConstraintBuilder::new(meta, &foreign_table_configs);
 
-                let config = $x::configure(
-                    &common_config,
-                    &mut allocator.clone(),
-                    &mut constraint_builder,
-                );
+                    let mut op_allocator = allocator.clone();
+                    let config =
+                        $x::configure(&common_config, &mut op_allocator, &mut constraint_builder);
+                    cell_profiler.record(stringify!($x), &op_allocator);
 
-                constraint_builder.finalize(|meta| {
-                    (fixed_curr!(meta, step_sel), ops[op.index()].curr_expr(meta))
-                });
+                    constraint_builder
+                        .finalize(|meta| (fixed_curr!(meta, step_sel), ops[&op].curr_expr(meta)));
 
-                op_bitmaps.insert(op, op.index());
-                op_configs.insert(op, Rc::new(config));
+                    op_configs.insert(op, Rc::new(config));
+                }
             };
         }
 
@@ -345,34 +411,50 @@ impl<F: FieldExt> EventTableConfig<F> {
         configure!(OpcodeClass::MemoryGrow, MemoryGrowConfigBuilder);
         configure!(OpcodeClass::BrTable, BrTableConfigBuilder);
         configure!(OpcodeClass::CallIndirect, CallIndirectConfigBuilder);
+        configure!(OpcodeClass::BinConst, BinConstConfigBuilder);
+        configure!(OpcodeClass::BinLocal, BinLocalConfigBuilder);
 
         macro_rules! configure_foreign {
             ($x:ident, $i:expr) => {
-                let builder = $x::new($i);
                 let op = OpcodeClass::ForeignPluginStart as usize + $i;
                 let op = OpcodeClassPlain(op);
 
-                let mut constraint_builder = ConstraintBuilder::new(meta, foreign_table_configs);
+                if is_configured(&op) {
+                    let builder = $x::new($i);
 
-                let config = builder.configure(
-                    &common_config,
-                    &mut allocator.clone(),
-                    &mut constraint_builder,
-                    &mut foreign_table_reserved_lookup_cells,
-                );
+                    let mut constraint_builder =
+                        ConstraintBuilder::new(meta, foreign_table_configs);
 
-                constraint_builder.finalize(|meta| {
-                    (fixed_curr!(meta, step_sel), ops[op.index()].curr_expr(meta))
-                });
+                    let mut op_allocator = allocator.clone();
+                    let config = builder.configure(
+                        &common_config,
+                        &mut op_allocator,
+                        &mut constraint_builder,
+                        &mut foreign_table_reserved_lookup_cells,
+                    );
+                    cell_profiler.record(stringify!($x), &op_allocator);
 
-                op_bitmaps.insert(op, op.index());
-                op_configs.insert(op, Rc::new(config));
+                    constraint_builder
+                        .finalize(|meta| (fixed_curr!(meta, step_sel), ops[&op].curr_expr(meta)));
+
+                    op_configs.insert(op, Rc::new(config));
+                }
             };
         }
         configure_foreign!(ETableWasmInputHelperTableConfigBuilder, 0);
         configure_foreign!(ETableContextHelperTableConfigBuilder, 1);
         configure_foreign!(ETableRequireHelperTableConfigBuilder, 2);
 
+        // The gates and lookups of the table allocate no cell.
+        if layout.is_none() {
+            return Self {
+                step_sel,
+                common_config,
+                op_configs,
+                cell_profiler,
+            };
+        }
+
         meta.create_gate("c1. enable seq", |meta| {
             vec![
                 enabled_cell.next_expr(meta)
@@ -383,10 +465,10 @@ impl<F: FieldExt> EventTableConfig<F> {
 
         meta.create_gate("c4. opcode_bit lvl sum equals to 1", |meta| {
             vec![
-                ops.map(|x| x.curr_expr(meta))
-                    .into_iter()
+                ops.values()
+                    .map(|x| x.curr_expr(meta))
                     .reduce(|acc, x| acc + x)
-                    .unwrap()
+                    .unwrap_or(constant_from!(0))
                     - enabled_cell.curr_expr(meta),
             ]
             .into_iter()
@@ -407,11 +489,10 @@ impl<F: FieldExt> EventTableConfig<F> {
                 &Rc<Box<dyn EventTableOpcodeConfig<F>>>,
             ) -> Option<Expression<F>>,
              enable: Option<&dyn Fn(&mut VirtualCells<'_, F>) -> Expression<F>>| {
-                let expr = op_bitmaps
+                let expr = op_configs
                     .iter()
-                    .filter_map(|(op, op_index)| {
-                        get_expr(meta, op_configs.get(op).unwrap())
-                            .map(|expr| expr * ops[*op_index].curr_expr(meta))
+                    .filter_map(|(op, config)| {
+                        get_expr(meta, config).map(|expr| expr * ops[op].curr_expr(meta))
                     })
                     .fold(init, |acc, x| acc + x)
                     * fixed_curr!(meta, step_sel);
@@ -424,14 +505,13 @@ impl<F: FieldExt> EventTableConfig<F> {
             &mut VirtualCells<'_, F>,
             &Rc<Box<dyn EventTableOpcodeConfig<F>>>,
         ) -> Option<Expression<F>>| {
-            op_bitmaps
+            op_configs
                 .iter()
-                .filter_map(|(op, op_index)| {
-                    get_expr(meta, op_configs.get(op).unwrap())
-                        .map(|expr| expr * ops[*op_index].curr_expr(meta))
+                .filter_map(|(op, config)| {
+                    get_expr(meta, config).map(|expr| expr * ops[op].curr_expr(meta))
                 })
                 .reduce(|acc, x| acc + x)
-                .unwrap()
+                .unwrap_or(constant_from!(0))
         };
 
         meta.create_gate("c5a. rest_mops change", |meta| {
@@ -452,6 +532,7 @@ impl<F: FieldExt> EventTableConfig<F> {
             )]
         });
 
+        // Kept after the termination like the context indexes, see c5g.
         meta.create_gate("c5c. input_index change", |meta| {
             vec![sum_ops_expr_with_init(
                 input_index_cell.curr_expr(meta) - input_index_cell.next_expr(meta),
@@ -459,7 +540,7 @@ impl<F: FieldExt> EventTableConfig<F> {
                 &|meta, config: &Rc<Box<dyn EventTableOpcodeConfig<F>>>| {
                     config.input_index_increase(meta, &common_config)
                 },
-                Some(&|meta| enabled_cell.curr_expr(meta)),
+                None,
             )]
         });
 
@@ -495,6 +576,10 @@ impl<F: FieldExt> EventTableConfig<F> {
             )]
         });
 
+        /*
+         * Context indexes are kept after the termination, so that the number of context
+         * reads and writes is found at the end of the table.
+         */
         meta.create_gate("c5g. context_input_index change", |meta| {
             vec![sum_ops_expr_with_init(
                 context_input_index_cell.curr_expr(meta) - context_input_index_cell.next_expr(meta),
@@ -502,7 +587,7 @@ impl<F: FieldExt> EventTableConfig<F> {
                 &|meta, config: &Rc<Box<dyn EventTableOpcodeConfig<F>>>| {
                     config.context_input_index_increase(meta, &common_config)
                 },
-                Some(&|meta| enabled_cell.curr_expr(meta)),
+                None,
             )]
         });
 
@@ -514,7 +599,7 @@ impl<F: FieldExt> EventTableConfig<F> {
                 &|meta, config: &Rc<Box<dyn EventTableOpcodeConfig<F>>>| {
                     config.context_output_index_increase(meta, &common_config)
                 },
-                Some(&|meta| enabled_cell.curr_expr(meta)),
+                None,
             )]
         });
 
@@ -593,12 +678,21 @@ impl<F: FieldExt> EventTableConfig<F> {
             |meta| fixed_curr!(meta, step_sel),
         );
 
+        // A call is bound to the row of the external host call table at its index.
+        let call_host = ops
+            .get(&OpcodeClassPlain(OpcodeClass::CallHost as usize))
+            .cloned();
         external_host_call_table.configure_in_table(
             meta,
             "c8g. external_foreign_call_lookup in foreign table",
             |meta| {
+                let index = call_host.map_or(constant_from!(0), |call_host| {
+                    call_host.curr_expr(meta) * external_host_call_index_cell.curr_expr(meta)
+                });
+
                 vec![
                     external_foreign_call_lookup_cell.curr_expr(meta) * fixed_curr!(meta, step_sel),
+                    index * fixed_curr!(meta, step_sel),
                 ]
             },
         );
@@ -626,6 +720,7 @@ impl<F: FieldExt> EventTableConfig<F> {
             step_sel,
             common_config,
             op_configs,
+            cell_profiler,
         }
     }
 }
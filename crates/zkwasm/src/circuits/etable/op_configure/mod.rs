@@ -1,5 +1,7 @@
 pub mod op_bin;
 pub mod op_bin_bit;
+pub mod op_bin_const;
+pub mod op_bin_local;
 pub mod op_bin_shift;
 pub mod op_br;
 pub mod op_br_if;
@@ -26,11 +26,13 @@ use specs::mtable::LocationType;
 use specs::mtable::VarType;
 use specs::step::StepInfo;
 
-pub struct BinConfig<F: FieldExt> {
-    lhs: AllocatedU64CellWithFlagBitDyn<F>,
-    rhs: AllocatedU64CellWithFlagBitDyn<F>,
+/// Arithmetic of `Bin`, shared with the fused `BinConst` and `BinLocal`.
+#[derive(Clone, Copy)]
+pub(super) struct BinArithConfig<F: FieldExt> {
+    pub(super) lhs: AllocatedU64CellWithFlagBitDyn<F>,
+    pub(super) rhs: AllocatedU64CellWithFlagBitDyn<F>,
 
-    is_i32: AllocatedBitCell<F>,
+    pub(super) is_i32: AllocatedBitCell<F>,
 
     d: AllocatedU64Cell<F>,
     d_flag_helper_diff: AllocatedCommonRangeCell<F>,
@@ -55,20 +57,13 @@ pub struct BinConfig<F: FieldExt> {
     d_leading_u16: AllocatedUnlimitedCell<F>,
     degree_helper1: AllocatedUnlimitedCell<F>,
     degree_helper2: AllocatedUnlimitedCell<F>,
-
-    memory_table_lookup_stack_read_lhs: AllocatedMemoryTableLookupReadCell<F>,
-    memory_table_lookup_stack_read_rhs: AllocatedMemoryTableLookupReadCell<F>,
-    memory_table_lookup_stack_write: AllocatedMemoryTableLookupWriteCell<F>,
 }
 
-pub struct BinConfigBuilder {}
-
-impl<F: FieldExt> EventTableOpcodeConfigBuilder<F> for BinConfigBuilder {
-    fn configure(
-        common_config: &EventTableCommonConfig<F>,
+impl<F: FieldExt> BinArithConfig<F> {
+    pub(super) fn configure(
         allocator: &mut EventTableCellAllocator<F>,
         constraint_builder: &mut ConstraintBuilder<F>,
-    ) -> Box<dyn EventTableOpcodeConfig<F>> {
+    ) -> Self {
         let is_i32 = allocator.alloc_bit_cell();
         let lhs = allocator
             .alloc_u64_with_flag_bit_cell_dyn(constraint_builder, move |meta| is_i32.expr(meta));
@@ -100,43 +95,64 @@ impl<F: FieldExt> EventTableOpcodeConfigBuilder<F> for BinConfigBuilder {
         let degree_helper1 = allocator.alloc_unlimited_cell();
         let degree_helper2 = allocator.alloc_unlimited_cell();
 
-        let eid = common_config.eid_cell;
-        let sp = common_config.sp_cell;
-
-        let memory_table_lookup_stack_read_rhs = allocator.alloc_memory_table_lookup_read_cell(
-            "op_bin stack read",
-            constraint_builder,
-            eid,
-            move |____| constant_from!(LocationType::Stack as u64),
-            move |meta| sp.expr(meta) + constant_from!(1),
-            move |meta| is_i32.expr(meta),
-            move |meta| rhs.u64_cell.expr(meta),
-            move |____| constant_from!(1),
-        );
-
-        let memory_table_lookup_stack_read_lhs = allocator.alloc_memory_table_lookup_read_cell(
-            "op_bin stack read",
-            constraint_builder,
-            eid,
-            move |____| constant_from!(LocationType::Stack as u64),
-            move |meta| sp.expr(meta) + constant_from!(2),
-            move |meta| is_i32.expr(meta),
-            move |meta| lhs.u64_cell.expr(meta),
-            move |____| constant_from!(1),
-        );
-
-        let memory_table_lookup_stack_write = allocator
-            .alloc_memory_table_lookup_write_cell_with_value(
-                "op_bin stack read",
-                constraint_builder,
-                eid,
-                move |____| constant_from!(LocationType::Stack as u64),
-                move |meta| sp.expr(meta) + constant_from!(2),
-                move |meta| is_i32.expr(meta),
-                move |____| constant_from!(1),
-            );
+        BinArithConfig {
+            lhs,
+            rhs,
+            is_i32,
+            d,
+            d_flag_helper_diff,
+            aux1,
+            aux2,
+            overflow,
+            is_add,
+            is_sub,
+            is_mul,
+            is_div_u,
+            is_rem_u,
+            is_div_s,
+            is_rem_s,
+            is_div_s_or_rem_s,
+            res_flag,
+            size_modulus,
+            normalized_lhs,
+            normalized_rhs,
+            d_leading_u16,
+            degree_helper1,
+            degree_helper2,
+        }
+    }
 
-        let res = memory_table_lookup_stack_write.value_cell;
+    /// Constrain `res` to be the result of the operation on `lhs` and `rhs`.
+    pub(super) fn constrain(
+        &self,
+        constraint_builder: &mut ConstraintBuilder<F>,
+        res: AllocatedUnlimitedCell<F>,
+    ) {
+        let BinArithConfig {
+            lhs,
+            rhs,
+            is_i32,
+            d,
+            d_flag_helper_diff,
+            aux1,
+            aux2,
+            overflow,
+            is_add,
+            is_sub,
+            is_mul,
+            is_div_u,
+            is_rem_u,
+            is_div_s,
+            is_rem_s,
+            is_div_s_or_rem_s,
+            res_flag,
+            size_modulus,
+            normalized_lhs,
+            normalized_rhs,
+            d_leading_u16,
+            degree_helper1,
+            degree_helper2,
+        } = *self;
 
         constraint_builder.push(
             "bin: selector",
@@ -309,43 +325,11 @@ impl<F: FieldExt> EventTableOpcodeConfigBuilder<F> for BinConfigBuilder {
                 ]
             }),
         );
-
-        Box::new(BinConfig {
-            lhs,
-            rhs,
-            is_i32,
-            d,
-            d_flag_helper_diff,
-            aux1,
-            aux2,
-            overflow,
-            is_add,
-            is_sub,
-            is_mul,
-            is_div_u,
-            is_rem_u,
-            is_div_s,
-            is_rem_s,
-            is_div_s_or_rem_s,
-            memory_table_lookup_stack_read_lhs,
-            memory_table_lookup_stack_read_rhs,
-            memory_table_lookup_stack_write,
-            size_modulus,
-            res_flag,
-            normalized_lhs,
-            normalized_rhs,
-            d_leading_u16,
-            degree_helper1,
-            degree_helper2,
-        })
     }
-}
 
-impl<F: FieldExt> EventTableOpcodeConfig<F> for BinConfig<F> {
-    fn opcode(&self, meta: &mut VirtualCells<'_, F>) -> Expression<F> {
-        constant!(bn_to_field(
-            &(BigUint::from(OpcodeClass::Bin as u64) << OPCODE_CLASS_SHIFT)
-        )) + self.is_add.expr(meta)
+    /// The class and the type of the operation encoded as opcode arguments.
+    pub(super) fn opcode_args(&self, meta: &mut VirtualCells<'_, F>) -> Expression<F> {
+        self.is_add.expr(meta)
             * constant!(bn_to_field(
                 &(BigUint::from(BinOp::Add as u64) << OPCODE_ARG0_SHIFT)
             ))
@@ -377,43 +361,16 @@ impl<F: FieldExt> EventTableOpcodeConfig<F> for BinConfig<F> {
                 * constant!(bn_to_field(&(BigUint::from(1u64) << OPCODE_ARG1_SHIFT)))
     }
 
-    fn assign(
+    pub(super) fn assign(
         &self,
         ctx: &mut Context<'_, F>,
-        step: &StepStatus,
-        entry: &EventTableEntryWithMemoryInfo,
+        class: &BinOp,
+        var_type: VarType,
+        left: u64,
+        right: u64,
+        value: u64,
     ) -> Result<(), Error> {
-        let (class, var_type, shift, left, right, value) = match &entry.eentry.step_info {
-            StepInfo::I32BinOp {
-                class,
-                left,
-                right,
-                value,
-            } => {
-                let var_type = VarType::I32;
-                let left = *left as u32 as u64;
-                let right = *right as u32 as u64;
-                let value = *value as u32 as u64;
-
-                (class, var_type, 32, left, right, value)
-            }
-
-            StepInfo::I64BinOp {
-                class,
-                left,
-                right,
-                value,
-            } => {
-                let var_type = VarType::I64;
-                let left = *left as u64;
-                let right = *right as u64;
-                let value = *value as u64;
-
-                (class, var_type, 64, left, right, value)
-            }
-
-            _ => unreachable!(),
-        };
+        let shift = if var_type == VarType::I32 { 32 } else { 64 };
 
         self.lhs
             .assign(ctx, left.into(), var_type == VarType::I32)?;
@@ -552,6 +509,130 @@ impl<F: FieldExt> EventTableOpcodeConfig<F> for BinConfig<F> {
             _ => {}
         };
 
+        Ok(())
+    }
+}
+
+pub struct BinConfig<F: FieldExt> {
+    arith: BinArithConfig<F>,
+
+    memory_table_lookup_stack_read_lhs: AllocatedMemoryTableLookupReadCell<F>,
+    memory_table_lookup_stack_read_rhs: AllocatedMemoryTableLookupReadCell<F>,
+    memory_table_lookup_stack_write: AllocatedMemoryTableLookupWriteCell<F>,
+}
+
+pub struct BinConfigBuilder {}
+
+impl<F: FieldExt> EventTableOpcodeConfigBuilder<F> for BinConfigBuilder {
+    fn configure(
+        common_config: &EventTableCommonConfig<F>,
+        allocator: &mut EventTableCellAllocator<F>,
+        constraint_builder: &mut ConstraintBuilder<F>,
+    ) -> Box<dyn EventTableOpcodeConfig<F>> {
+        let arith = BinArithConfig::configure(allocator, constraint_builder);
+        let is_i32 = arith.is_i32;
+        let lhs = arith.lhs;
+        let rhs = arith.rhs;
+
+        let eid = common_config.eid_cell;
+        let sp = common_config.sp_cell;
+
+        let memory_table_lookup_stack_read_rhs = allocator.alloc_memory_table_lookup_read_cell(
+            "op_bin stack read",
+            constraint_builder,
+            eid,
+            move |____| constant_from!(LocationType::Stack as u64),
+            move |meta| sp.expr(meta) + constant_from!(1),
+            move |meta| is_i32.expr(meta),
+            move |meta| rhs.u64_cell.expr(meta),
+            move |____| constant_from!(1),
+        );
+
+        let memory_table_lookup_stack_read_lhs = allocator.alloc_memory_table_lookup_read_cell(
+            "op_bin stack read",
+            constraint_builder,
+            eid,
+            move |____| constant_from!(LocationType::Stack as u64),
+            move |meta| sp.expr(meta) + constant_from!(2),
+            move |meta| is_i32.expr(meta),
+            move |meta| lhs.u64_cell.expr(meta),
+            move |____| constant_from!(1),
+        );
+
+        let memory_table_lookup_stack_write = allocator
+            .alloc_memory_table_lookup_write_cell_with_value(
+                "op_bin stack read",
+                constraint_builder,
+                eid,
+                move |____| constant_from!(LocationType::Stack as u64),
+                move |meta| sp.expr(meta) + constant_from!(2),
+                move |meta| is_i32.expr(meta),
+                move |____| constant_from!(1),
+            );
+
+        arith.constrain(
+            constraint_builder,
+            memory_table_lookup_stack_write.value_cell,
+        );
+
+        Box::new(BinConfig {
+            arith,
+            memory_table_lookup_stack_read_lhs,
+            memory_table_lookup_stack_read_rhs,
+            memory_table_lookup_stack_write,
+        })
+    }
+}
+
+/// Operands and result of a `Bin` step as `(class, type, left, right, value)`.
+pub(super) fn bin_operands(step_info: &StepInfo) -> (BinOp, VarType, u64, u64, u64) {
+    match step_info {
+        StepInfo::I32BinOp {
+            class,
+            left,
+            right,
+            value,
+        } => (
+            *class,
+            VarType::I32,
+            *left as u32 as u64,
+            *right as u32 as u64,
+            *value as u32 as u64,
+        ),
+        StepInfo::I64BinOp {
+            class,
+            left,
+            right,
+            value,
+        } => (
+            *class,
+            VarType::I64,
+            *left as u64,
+            *right as u64,
+            *value as u64,
+        ),
+        _ => unreachable!(),
+    }
+}
+
+impl<F: FieldExt> EventTableOpcodeConfig<F> for BinConfig<F> {
+    fn opcode(&self, meta: &mut VirtualCells<'_, F>) -> Expression<F> {
+        constant!(bn_to_field(
+            &(BigUint::from(OpcodeClass::Bin as u64) << OPCODE_CLASS_SHIFT)
+        )) + self.arith.opcode_args(meta)
+    }
+
+    fn assign(
+        &self,
+        ctx: &mut Context<'_, F>,
+        step: &StepStatus,
+        entry: &EventTableEntryWithMemoryInfo,
+    ) -> Result<(), Error> {
+        let (class, var_type, left, right, value) = bin_operands(&entry.eentry.step_info);
+
+        self.arith
+            .assign(ctx, &class, var_type, left, right, value)?;
+
         self.memory_table_lookup_stack_read_rhs.assign(
             ctx,
             entry.memory_rw_entires[0].start_eid,
@@ -0,0 +1,183 @@
+use crate::circuits::cell::*;
+use crate::circuits::etable::allocator::*;
+use crate::circuits::etable::op_configure::op_bin::BinArithConfig;
+use crate::circuits::etable::ConstraintBuilder;
+use crate::circuits::etable::EventTableCommonConfig;
+use crate::circuits::etable::EventTableOpcodeConfig;
+use crate::circuits::etable::EventTableOpcodeConfigBuilder;
+use crate::circuits::utils::bn_to_field;
+use crate::circuits::utils::step_status::StepStatus;
+use crate::circuits::utils::table_entry::EventTableEntryWithMemoryInfo;
+use crate::circuits::utils::Context;
+use crate::constant;
+use crate::constant_from;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::plonk::Error;
+use halo2_proofs::plonk::Expression;
+use halo2_proofs::plonk::VirtualCells;
+use num_bigint::BigUint;
+use specs::etable::EventTableEntry;
+use specs::itable::OpcodeClass;
+use specs::itable::OPCODE_CLASS_SHIFT;
+use specs::mtable::LocationType;
+use specs::mtable::VarType;
+use specs::step::StepInfo;
+
+/// `LocalGet` fused with the following `Bin`, the right operand is read from the local at `depth`.
+pub struct BinLocalConfig<F: FieldExt> {
+    arith: BinArithConfig<F>,
+    offset_cell: AllocatedCommonRangeCell<F>,
+
+    memory_table_lookup_stack_read_rhs: AllocatedMemoryTableLookupReadCell<F>,
+    memory_table_lookup_stack_read_lhs: AllocatedMemoryTableLookupReadCell<F>,
+    memory_table_lookup_stack_write: AllocatedMemoryTableLookupWriteCell<F>,
+}
+
+pub struct BinLocalConfigBuilder {}
+
+impl<F: FieldExt> EventTableOpcodeConfigBuilder<F> for BinLocalConfigBuilder {
+    fn configure(
+        common_config: &EventTableCommonConfig<F>,
+        allocator: &mut EventTableCellAllocator<F>,
+        constraint_builder: &mut ConstraintBuilder<F>,
+    ) -> Box<dyn EventTableOpcodeConfig<F>> {
+        let arith = BinArithConfig::configure(allocator, constraint_builder);
+        let is_i32 = arith.is_i32;
+        let lhs = arith.lhs;
+        let rhs = arith.rhs;
+        let offset_cell = allocator.alloc_common_range_cell();
+
+        let eid = common_config.eid_cell;
+        let sp = common_config.sp_cell;
+
+        let memory_table_lookup_stack_read_rhs = allocator.alloc_memory_table_lookup_read_cell(
+            "op_bin_local stack read",
+            constraint_builder,
+            eid,
+            move |____| constant_from!(LocationType::Stack as u64),
+            move |meta| sp.expr(meta) + offset_cell.expr(meta),
+            move |meta| is_i32.expr(meta),
+            move |meta| rhs.u64_cell.expr(meta),
+            move |____| constant_from!(1),
+        );
+
+        let memory_table_lookup_stack_read_lhs = allocator.alloc_memory_table_lookup_read_cell(
+            "op_bin_local stack read",
+            constraint_builder,
+            eid,
+            move |____| constant_from!(LocationType::Stack as u64),
+            move |meta| sp.expr(meta) + constant_from!(1),
+            move |meta| is_i32.expr(meta),
+            move |meta| lhs.u64_cell.expr(meta),
+            move |____| constant_from!(1),
+        );
+
+        let memory_table_lookup_stack_write = allocator
+            .alloc_memory_table_lookup_write_cell_with_value(
+                "op_bin_local stack write",
+                constraint_builder,
+                eid,
+                move |____| constant_from!(LocationType::Stack as u64),
+                move |meta| sp.expr(meta) + constant_from!(1),
+                move |meta| is_i32.expr(meta),
+                move |____| constant_from!(1),
+            );
+
+        arith.constrain(
+            constraint_builder,
+            memory_table_lookup_stack_write.value_cell,
+        );
+
+        Box::new(BinLocalConfig {
+            arith,
+            offset_cell,
+            memory_table_lookup_stack_read_rhs,
+            memory_table_lookup_stack_read_lhs,
+            memory_table_lookup_stack_write,
+        })
+    }
+}
+
+impl<F: FieldExt> EventTableOpcodeConfig<F> for BinLocalConfig<F> {
+    fn opcode(&self, meta: &mut VirtualCells<'_, F>) -> Expression<F> {
+        constant!(bn_to_field(
+            &(BigUint::from(OpcodeClass::BinLocal as u64) << OPCODE_CLASS_SHIFT)
+        )) + self.arith.opcode_args(meta)
+            + self.offset_cell.expr(meta)
+    }
+
+    fn assign(
+        &self,
+        ctx: &mut Context<'_, F>,
+        step: &StepStatus,
+        entry: &EventTableEntryWithMemoryInfo,
+    ) -> Result<(), Error> {
+        match &entry.eentry.step_info {
+            StepInfo::BinLocal {
+                class,
+                vtype,
+                depth,
+                left,
+                right,
+                value,
+            } => {
+                self.arith
+                    .assign(ctx, class, *vtype, *left, *right, *value)?;
+
+                self.offset_cell.assign(ctx, F::from(*depth as u64))?;
+
+                self.memory_table_lookup_stack_read_rhs.assign(
+                    ctx,
+                    entry.memory_rw_entires[0].start_eid,
+                    step.current.eid,
+                    entry.memory_rw_entires[0].end_eid,
+                    step.current.sp + *depth,
+                    LocationType::Stack,
+                    *vtype == VarType::I32,
+                    *right,
+                )?;
+
+                self.memory_table_lookup_stack_read_lhs.assign(
+                    ctx,
+                    entry.memory_rw_entires[1].start_eid,
+                    step.current.eid,
+                    entry.memory_rw_entires[1].end_eid,
+                    step.current.sp + 1,
+                    LocationType::Stack,
+                    *vtype == VarType::I32,
+                    *left,
+                )?;
+
+                self.memory_table_lookup_stack_write.assign(
+                    ctx,
+                    step.current.eid,
+                    entry.memory_rw_entires[2].end_eid,
+                    step.current.sp + 1,
+                    LocationType::Stack,
+                    *vtype == VarType::I32,
+                    *value,
+                )?;
+
+                Ok(())
+            }
+            _ => unreachable!(),
+        }
+    }
+
+    fn mops(&self, _meta: &mut VirtualCells<'_, F>) -> Option<Expression<F>> {
+        Some(constant_from!(1))
+    }
+
+    fn memory_writing_ops(&self, _: &EventTableEntry) -> u32 {
+        1
+    }
+
+    fn next_iid(
+        &self,
+        meta: &mut VirtualCells<'_, F>,
+        common_config: &EventTableCommonConfig<F>,
+    ) -> Option<Expression<F>> {
+        // The fused `Bin` is skipped.
+        Some(common_config.iid_cell.expr(meta) + constant_from!(2))
+    }
+}
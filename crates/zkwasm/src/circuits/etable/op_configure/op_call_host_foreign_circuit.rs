@@ -8,6 +8,9 @@ use crate::circuits::utils::step_status::StepStatus;
 use crate::circuits::utils::table_entry::EventTableEntryWithMemoryInfo;
 use crate::circuits::utils::Context;
 use crate::constant_from;
+use crate::foreign::bigint_helper::circuits::encode_u256_result;
+use crate::foreign::bigint_helper::circuits::U256_FOREIGN_TABLE_KEY;
+use crate::foreign::ForeignInst;
 use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::plonk::Error;
 use halo2_proofs::plonk::Expression;
@@ -23,8 +26,11 @@ pub struct ExternalCallHostCircuitConfig<F: FieldExt> {
     op: AllocatedCommonRangeCell<F>,
     value_is_ret: AllocatedBitCell<F>,
     value_is_not_ret: AllocatedBitCell<F>,
+    is_u256_result: AllocatedBitCell<F>,
+    u256_result_diff_inv: AllocatedUnlimitedCell<F>,
 
     external_foreign_call_lookup_cell: AllocatedUnlimitedCell<F>,
+    u256_result_lookup_cell: AllocatedUnlimitedCell<F>,
     memory_table_lookup_stack_read: AllocatedMemoryTableLookupReadCell<F>,
     memory_table_lookup_stack_write: AllocatedMemoryTableLookupWriteCell<F>,
 }
@@ -40,9 +46,12 @@ impl<F: FieldExt> EventTableOpcodeConfigBuilder<F> for ExternalCallHostCircuitCo
         let op = allocator.alloc_common_range_cell();
         let value_is_ret = allocator.alloc_bit_cell();
         let value_is_not_ret = allocator.alloc_bit_cell();
+        let is_u256_result = allocator.alloc_bit_cell();
+        let u256_result_diff_inv = allocator.alloc_unlimited_cell();
 
         let index = common_config.external_host_call_index_cell;
         let external_foreign_call_lookup_cell = common_config.external_foreign_call_lookup_cell;
+        let u256_result_lookup_cell = common_config.u256_result_lookup_cell;
 
         constraint_builder.push(
             "op_call_host is_ret or not",
@@ -98,11 +107,40 @@ impl<F: FieldExt> EventTableOpcodeConfigBuilder<F> for ExternalCallHostCircuitCo
             }),
         );
 
+        // The limbs returned by `u256_result` are constrained by the u256 helper table.
+        constraint_builder.push(
+            "op_call_host is u256 result",
+            Box::new(move |meta| {
+                let diff = op.expr(meta) - constant_from!(ForeignInst::U256Result as u64);
+
+                vec![
+                    is_u256_result.expr(meta) * diff.clone(),
+                    diff * u256_result_diff_inv.expr(meta) + is_u256_result.expr(meta)
+                        - constant_from!(1),
+                    u256_result_lookup_cell.expr(meta)
+                        - is_u256_result.expr(meta)
+                            * encode_u256_result(
+                                index.expr(meta),
+                                memory_table_lookup_stack_write.value_cell.expr(meta),
+                            ),
+                ]
+            }),
+        );
+
+        constraint_builder.lookup(
+            U256_FOREIGN_TABLE_KEY,
+            "op_call_host u256 result lookup",
+            Box::new(move |meta| vec![u256_result_lookup_cell.expr(meta)]),
+        );
+
         Box::new(ExternalCallHostCircuitConfig {
             op,
             value_is_ret,
             value_is_not_ret,
+            is_u256_result,
+            u256_result_diff_inv,
             external_foreign_call_lookup_cell,
+            u256_result_lookup_cell,
             memory_table_lookup_stack_read,
             memory_table_lookup_stack_write,
         })
@@ -125,6 +163,24 @@ impl<F: FieldExt> EventTableOpcodeConfig<F> for ExternalCallHostCircuitConfig<F>
                 self.op.assign(ctx, F::from(*op as u64))?;
                 self.value_is_ret.assign_bool(ctx, sig.is_ret())?;
                 self.value_is_not_ret.assign_bool(ctx, !sig.is_ret())?;
+
+                let is_u256_result = *op == ForeignInst::U256Result as usize;
+                self.is_u256_result.assign_bool(ctx, is_u256_result)?;
+                self.u256_result_diff_inv.assign(
+                    ctx,
+                    (F::from(*op as u64) - F::from(ForeignInst::U256Result as u64))
+                        .invert()
+                        .unwrap_or(F::zero()),
+                )?;
+                if is_u256_result {
+                    self.u256_result_lookup_cell.assign_bn(
+                        ctx,
+                        &encode_u256_result(
+                            BigUint::from(step.current_external_host_call_index),
+                            BigUint::from(value.unwrap()),
+                        ),
+                    )?;
+                }
                 self.external_foreign_call_lookup_cell.assign_bn(
                     ctx,
                     &encode_host_call_entry(
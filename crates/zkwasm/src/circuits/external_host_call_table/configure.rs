@@ -22,6 +22,8 @@ impl<F: FieldExt> ExternalHostCallTableConfig<F> {
     }
 }
 
+/// Looks up an encoded entry together with its index: the opcode and operand columns are not
+/// range checked, the index pins the entry to its row.
 impl<F: FieldExt> ConfigureLookupTable<F> for ExternalHostCallTableConfig<F> {
     fn configure_in_table(
         &self,
@@ -30,14 +32,19 @@ impl<F: FieldExt> ConfigureLookupTable<F> for ExternalHostCallTableConfig<F> {
         expr: impl FnOnce(&mut VirtualCells<'_, F>) -> Vec<Expression<F>>,
     ) {
         meta.lookup_any(key, |meta| {
-            vec![(
-                expr(meta).pop().unwrap(),
-                encode_host_call_entry(
-                    fixed_curr!(meta, self.idx),
-                    curr!(meta, self.opcode),
-                    curr!(meta, self.operand),
+            let mut exprs = expr(meta);
+
+            vec![
+                (
+                    exprs.remove(0),
+                    encode_host_call_entry(
+                        fixed_curr!(meta, self.idx),
+                        curr!(meta, self.opcode),
+                        curr!(meta, self.operand),
+                    ),
                 ),
-            )]
+                (exprs.remove(0), fixed_curr!(meta, self.idx)),
+            ]
         });
     }
 }
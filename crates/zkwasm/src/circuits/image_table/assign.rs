@@ -53,6 +53,10 @@ impl<F: FieldExt> ImageTableChip<F> {
                     maximal_memory_pages_cell,
                 )?;
 
+                let heap_memory_root_cell = assign_one_line!(image_table.heap_memory_root);
+                ctx.region
+                    .constrain_equal(permutation_cells.heap_memory_root, heap_memory_root_cell)?;
+
                 for (static_frame_entry, cell_in_frame_table) in image_table
                     .static_frame_entries
                     .iter()
@@ -70,6 +74,28 @@ impl<F: FieldExt> ImageTableChip<F> {
                     assign_one_line!(*value);
                 }
 
+                Ok(())
+            },
+        )?;
+
+        layouter.assign_region(
+            || "init memory table",
+            |mut region| {
+                for (offset, value) in image_table
+                    .init_memory_entries
+                    .as_ref()
+                    .unwrap()
+                    .iter()
+                    .enumerate()
+                {
+                    region.assign_advice(
+                        || "init memory table",
+                        self.config.init_memory_col,
+                        offset,
+                        || Ok(*value),
+                    )?;
+                }
+
                 Ok(())
             },
         )
@@ -8,14 +8,17 @@ use specs::encode::image_table::ImageTableEncoder;
 
 use super::ImageTableConfig;
 use super::IMAGE_COL_NAME;
+use super::INIT_MEMORY_COL_NAME;
 use crate::curr;
 
 impl<F: FieldExt> ImageTableConfig<F> {
     pub(in crate::circuits) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
         let col = meta.named_advice_column(IMAGE_COL_NAME.to_owned());
         meta.enable_equality(col);
+        let init_memory_col = meta.named_advice_column(INIT_MEMORY_COL_NAME.to_owned());
         Self {
             col,
+            init_memory_col,
             _mark: PhantomData,
         }
     }
@@ -43,7 +46,7 @@ impl<F: FieldExt> ImageTableConfig<F> {
         meta.lookup_any(key, |meta| {
             vec![(
                 ImageTableEncoder::InitMemory.encode(expr(meta)),
-                curr!(meta, self.col),
+                curr!(meta, self.init_memory_col),
             )]
         });
     }
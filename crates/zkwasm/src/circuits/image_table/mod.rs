@@ -13,26 +13,33 @@ use specs::CompilationTable;
 use std::marker::PhantomData;
 
 use crate::circuits::config::max_image_table_rows;
+use crate::circuits::config::max_init_memory_table_rows;
+use crate::circuits::memory_tree::HeapMemoryTree;
 use crate::circuits::utils::bn_to_field;
 
 mod assign;
 mod configure;
 
 pub const IMAGE_COL_NAME: &str = "img_col";
+pub const INIT_MEMORY_COL_NAME: &str = "init_memory_col";
 
 pub struct ImageTableLayouter<T: Clone> {
     pub entry_fid: T,
     pub initial_memory_pages: T,
     pub maximal_memory_pages: T,
+    /// Root of the `HeapMemoryTree` of the initial heap.
+    pub heap_memory_root: T,
     pub static_frame_entries: Vec<(T, T)>,
     /*
      * include:
      *   instruction table
      *   br table
      *   elem table
-     *   init memory table
      */
     pub lookup_entries: Option<Vec<T>>,
+    /// The initial values of the globals, committed in their own column. The initial heap is
+    /// only committed by `heap_memory_root`.
+    pub init_memory_entries: Option<Vec<T>>,
 }
 
 impl<T: Clone> ImageTableLayouter<T> {
@@ -42,6 +49,7 @@ impl<T: Clone> ImageTableLayouter<T> {
         buf.push(self.entry_fid.clone());
         buf.push(self.initial_memory_pages.clone());
         buf.push(self.maximal_memory_pages.clone());
+        buf.push(self.heap_memory_root.clone());
         buf.append(
             &mut self
                 .static_frame_entries
@@ -58,6 +66,20 @@ impl<T: Clone> ImageTableLayouter<T> {
     }
 }
 
+/// Rows of `max_image_table_rows()` taken by the image, each table starts with a zero entry.
+pub fn image_table_lookup_rows(table: &CompilationTable) -> usize {
+    let instructions = 1 + table.itable.entries().len();
+    let br_and_elem_entries =
+        1 + table.itable.create_brtable().entries().len() + table.elem_table.entries().len();
+
+    instructions + br_and_elem_entries
+}
+
+/// Rows of `max_init_memory_table_rows()` taken by the globals, starting with a zero entry.
+pub fn init_memory_table_rows(table: &CompilationTable) -> usize {
+    1 + table.imtable.filter(LocationType::Global).len()
+}
+
 pub trait EncodeCompilationTableValues<F: Clone> {
     fn encode_compilation_table_values(&self) -> ImageTableLayouter<F>;
 }
@@ -99,7 +121,6 @@ impl<F: FieldExt> EncodeCompilationTableValues<F> for CompilationTable {
         }
 
         fn msg_of_init_memory_table<F: FieldExt>(init_memory_table: &InitMemoryTable) -> Vec<F> {
-            let heap_entries = init_memory_table.filter(LocationType::Heap);
             let global_entries = init_memory_table.filter(LocationType::Global);
 
             let mut cells = vec![];
@@ -108,12 +129,23 @@ impl<F: FieldExt> EncodeCompilationTableValues<F> for CompilationTable {
                 &ImageTableEncoder::InitMemory.encode(BigUint::from(0u64)),
             ));
 
-            for v in heap_entries.into_iter().chain(global_entries.into_iter()) {
+            for v in global_entries.into_iter() {
                 cells.push(bn_to_field::<F>(
                     &ImageTableEncoder::InitMemory.encode(v.encode()),
                 ));
             }
 
+            assert!(
+                cells.len() <= max_init_memory_table_rows() as usize,
+                "init memory table needs {} rows but holds at most {}",
+                cells.len(),
+                max_init_memory_table_rows()
+            );
+
+            for _ in cells.len()..(max_init_memory_table_rows() as usize) {
+                cells.push(F::zero());
+            }
+
             cells
         }
 
@@ -121,13 +153,18 @@ impl<F: FieldExt> EncodeCompilationTableValues<F> for CompilationTable {
             instruction_table: &InstructionTable,
             br_table: &BrTable,
             elem_table: &ElemTable,
-            init_memory_table: &InitMemoryTable,
         ) -> Vec<F> {
             let mut cells = vec![];
 
             cells.append(&mut msg_of_instruction_table(instruction_table));
             cells.append(&mut msg_of_br_table(br_table, elem_table));
-            cells.append(&mut msg_of_init_memory_table(init_memory_table));
+
+            assert!(
+                cells.len() <= max_image_table_rows() as usize,
+                "image table needs {} rows but holds at most {}",
+                cells.len(),
+                max_image_table_rows()
+            );
 
             for _ in cells.len()..(max_image_table_rows() as usize) {
                 cells.push(F::zero());
@@ -185,15 +222,18 @@ impl<F: FieldExt> EncodeCompilationTableValues<F> for CompilationTable {
             &self.itable,
             &self.itable.create_brtable(),
             &self.elem_table,
-            &self.imtable,
         );
+        let heap_memory_root = HeapMemoryTree::new(self.imtable.heap_words()).root();
+        let init_memory_entries = msg_of_init_memory_table(&self.imtable);
 
         ImageTableLayouter {
             entry_fid,
             static_frame_entries,
             initial_memory_pages,
             maximal_memory_pages,
+            heap_memory_root,
             lookup_entries: Some(lookup_entries),
+            init_memory_entries: Some(init_memory_entries),
         }
     }
 }
@@ -201,6 +241,7 @@ impl<F: FieldExt> EncodeCompilationTableValues<F> for CompilationTable {
 #[derive(Clone)]
 pub struct ImageTableConfig<F: FieldExt> {
     col: Column<Advice>,
+    init_memory_col: Column<Advice>,
     _mark: PhantomData<F>,
 }
 
@@ -0,0 +1,185 @@
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Cell;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::plonk::Error;
+
+use super::memory_tree_paths;
+use super::HeapMemoryTree;
+use super::MemoryTreeChip;
+use super::MEMORY_TREE_ARITY;
+use super::MEMORY_TREE_DEPTH;
+use super::MEMORY_TREE_NODE_ROWS;
+use crate::circuits::poseidon::PoseidonChip;
+
+/// A node as absorbed by the Poseidon gadget: its level then its children.
+struct NodeCells<F: FieldExt> {
+    inputs: Vec<(F, Cell)>,
+    digest: Cell,
+}
+
+impl<F: FieldExt> MemoryTreeChip<F> {
+    /// Assign the nodes on the paths from the leaves holding the heap words at `offsets` to
+    /// the root of `tree`, returns the cell of the root.
+    pub(crate) fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        tree: &HeapMemoryTree<'_, F>,
+        offsets: impl Iterator<Item = u32>,
+    ) -> Result<Cell, Error> {
+        let mut nodes = memory_tree_paths(offsets)
+            .into_iter()
+            .enumerate()
+            .rev()
+            .flat_map(|(level, indexes)| indexes.into_iter().map(move |index| (level, index)))
+            .collect::<Vec<_>>();
+
+        assert!(
+            nodes.len() <= self.capacity,
+            "memory tree needs {} nodes but holds at most {}",
+            nodes.len(),
+            self.capacity
+        );
+
+        // The remaining nodes repeat the first child of the root, which is a child of a node.
+        nodes.resize(self.capacity, (MEMORY_TREE_DEPTH - 1, 0));
+
+        let nodes = nodes
+            .into_iter()
+            .map(|(level, index)| {
+                (
+                    level,
+                    index,
+                    tree.children(level, index),
+                    tree.node(level, index),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let node_cells = layouter.assign_region(
+            || "memory tree",
+            |mut region| {
+                let config = &self.config;
+                let mut node_cells = vec![];
+
+                for (slot, (level, index, children, digest)) in nodes.iter().enumerate() {
+                    let row = slot * MEMORY_TREE_ARITY;
+                    let is_leaf = if *level == 0 { F::one() } else { F::zero() };
+                    let level = F::from(*level as u64);
+
+                    region.assign_fixed(
+                        || "memory tree node sel",
+                        config.node_sel,
+                        row,
+                        || Ok(F::one()),
+                    )?;
+                    region.assign_fixed(
+                        || "memory tree root or child sel",
+                        if slot == 0 {
+                            config.root_sel
+                        } else {
+                            config.child_sel
+                        },
+                        row,
+                        || Ok(F::one()),
+                    )?;
+
+                    region.assign_advice(
+                        || "memory tree parent",
+                        config.parent,
+                        row,
+                        || Ok(F::from(index / MEMORY_TREE_ARITY as u64)),
+                    )?;
+                    region.assign_advice(
+                        || "memory tree child position",
+                        config.child_position,
+                        row,
+                        || Ok(F::from(index % MEMORY_TREE_ARITY as u64)),
+                    )?;
+                    let digest = region.assign_advice(
+                        || "memory tree digest",
+                        config.digest,
+                        row,
+                        || Ok(*digest),
+                    )?;
+
+                    let mut inputs = vec![];
+
+                    for (position, child) in children.iter().enumerate() {
+                        let row = row + position;
+
+                        region.assign_fixed(
+                            || "memory tree position",
+                            config.position,
+                            row,
+                            || Ok(F::from(position as u64)),
+                        )?;
+                        region.assign_advice(
+                            || "memory tree is leaf",
+                            config.is_leaf,
+                            row,
+                            || Ok(is_leaf),
+                        )?;
+                        let level_cell = region.assign_advice(
+                            || "memory tree level",
+                            config.level,
+                            row,
+                            || Ok(level),
+                        )?;
+                        region.assign_advice(
+                            || "memory tree index",
+                            config.index,
+                            row,
+                            || Ok(F::from(*index)),
+                        )?;
+
+                        if position == 0 {
+                            inputs.push((level, level_cell.cell()));
+                        }
+
+                        let input = region.assign_advice(
+                            || "memory tree input",
+                            config.input,
+                            row,
+                            || Ok(*child),
+                        )?;
+                        inputs.push((*child, input.cell()));
+                    }
+
+                    node_cells.push(NodeCells {
+                        inputs,
+                        digest: digest.cell(),
+                    });
+                }
+
+                Ok(node_cells)
+            },
+        )?;
+
+        let poseidon = PoseidonChip::new(self.config.poseidon.clone(), 1);
+
+        layouter.assign_region(
+            || "memory tree digest",
+            |mut region| {
+                for (slot, node) in node_cells.iter().enumerate() {
+                    let inputs = node
+                        .inputs
+                        .iter()
+                        .map(|(value, cell)| (*value, Some(*cell)))
+                        .collect::<Vec<_>>();
+
+                    let digest = poseidon.assign(
+                        &mut region,
+                        slot * MEMORY_TREE_NODE_ROWS,
+                        &inputs,
+                        inputs.len(),
+                    )?;
+                    region.constrain_equal(digest.cell(), node.digest)?;
+                }
+
+                Ok(())
+            },
+        )?;
+
+        Ok(node_cells[0].digest)
+    }
+}
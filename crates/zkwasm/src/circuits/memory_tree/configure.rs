@@ -0,0 +1,140 @@
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::Expression;
+use halo2_proofs::plonk::VirtualCells;
+use specs::encode::init_memory_table::encode_init_memory_table_entry;
+use specs::mtable::LocationType;
+
+use super::MemoryTreeConfig;
+use super::MEMORY_TREE_ARITY;
+use super::MEMORY_TREE_DEPTH;
+use crate::circuits::poseidon::PoseidonConfig;
+use crate::circuits::traits::ConfigureLookupTable;
+use crate::constant_from;
+use crate::curr;
+use crate::fixed_curr;
+use crate::nextn;
+
+impl<F: FieldExt> MemoryTreeConfig<F> {
+    pub(in crate::circuits) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let node_sel = meta.fixed_column();
+        let child_sel = meta.fixed_column();
+        let root_sel = meta.fixed_column();
+        let position = meta.fixed_column();
+
+        let is_leaf = meta.advice_column();
+        let level = meta.advice_column();
+        let index = meta.advice_column();
+        let parent = meta.advice_column();
+        let child_position = meta.advice_column();
+        let input = meta.advice_column();
+        let digest = meta.advice_column();
+
+        // Bound to the inputs and the digest of the Poseidon gadget.
+        meta.enable_equality(level);
+        meta.enable_equality(input);
+        meta.enable_equality(digest);
+
+        let poseidon = PoseidonConfig::configure(meta);
+
+        meta.create_gate("memory tree node", |meta| {
+            let mut exprs = vec![
+                curr!(meta, is_leaf) * (constant_from!(1) - curr!(meta, is_leaf)),
+                curr!(meta, is_leaf) * curr!(meta, level),
+                (0..MEMORY_TREE_ARITY)
+                    .map(|k| curr!(meta, child_position) - constant_from!(k))
+                    .reduce(|acc, x| acc * x)
+                    .unwrap(),
+                curr!(meta, index)
+                    - curr!(meta, parent) * constant_from!(MEMORY_TREE_ARITY)
+                    - curr!(meta, child_position),
+            ];
+
+            // The rows of the children repeat the node.
+            for k in 1..MEMORY_TREE_ARITY as i32 {
+                exprs.extend(
+                    [is_leaf, level, index]
+                        .map(|column| nextn!(meta, column, k) - curr!(meta, column)),
+                );
+            }
+
+            exprs
+                .into_iter()
+                .map(|expr| expr * fixed_curr!(meta, node_sel))
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("memory tree root", |meta| {
+            vec![
+                curr!(meta, level) - constant_from!(MEMORY_TREE_DEPTH),
+                curr!(meta, index),
+            ]
+            .into_iter()
+            .map(|expr| expr * fixed_curr!(meta, root_sel))
+            .collect::<Vec<_>>()
+        });
+
+        // Each node but the root is a child of a node, the levels increase up to the root.
+        meta.lookup_any("memory tree parent", |meta| {
+            let child_sel = fixed_curr!(meta, child_sel);
+
+            vec![
+                (
+                    child_sel.clone() * (curr!(meta, level) + constant_from!(1)),
+                    curr!(meta, level),
+                ),
+                (child_sel.clone() * curr!(meta, parent), curr!(meta, index)),
+                (
+                    child_sel.clone() * curr!(meta, child_position),
+                    fixed_curr!(meta, position),
+                ),
+                (child_sel * curr!(meta, digest), curr!(meta, input)),
+            ]
+        });
+
+        MemoryTreeConfig {
+            node_sel,
+            child_sel,
+            root_sel,
+            position,
+            is_leaf,
+            level,
+            index,
+            parent,
+            child_position,
+            input,
+            digest,
+            poseidon,
+        }
+    }
+}
+
+impl<F: FieldExt> ConfigureLookupTable<F> for MemoryTreeConfig<F> {
+    /// Look up the init memory table encoding of a heap word in the words of the leaves, as
+    /// `encode_init_memory_table_entry` of a range of a single word.
+    fn configure_in_table(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        key: &'static str,
+        expr: impl FnOnce(&mut VirtualCells<'_, F>) -> Vec<Expression<F>>,
+    ) {
+        meta.lookup_any(key, |meta| {
+            let mut expr = expr(meta);
+
+            let offset = curr!(meta, self.index) * constant_from!(MEMORY_TREE_ARITY)
+                + fixed_curr!(meta, self.position);
+
+            vec![(
+                expr.pop().unwrap(),
+                curr!(meta, self.is_leaf)
+                    * encode_init_memory_table_entry(
+                        constant_from!(LocationType::Heap as u64),
+                        constant_from!(1),
+                        offset.clone(),
+                        offset,
+                        curr!(meta, self.input),
+                    ),
+            )]
+        });
+    }
+}
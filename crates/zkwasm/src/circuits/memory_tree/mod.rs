@@ -0,0 +1,261 @@
+//! Sparse Merkle tree of the initial heap.
+//!
+//! The image commits the initial heap by the root of a tree over its 8-byte words: a leaf
+//! hashes `MEMORY_TREE_ARITY` consecutive words and a node hashes as many children, each hash
+//! absorbing the level of the node first. The nodes over zero words are the same at each
+//! level, so the tree is computed from the non-zero words only and the size of the heap
+//! doesn't matter.
+//!
+//! The circuit doesn't hold the heap: it recomputes the nodes on the paths from the leaves
+//! holding the initial words read by the trace to the root, and the memory table looks the
+//! initial heap words up in the leaves of these paths.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::plonk::Advice;
+use halo2_proofs::plonk::Column;
+use halo2_proofs::plonk::Fixed;
+use specs::mtable::LocationType;
+use specs::mtable::MTable;
+
+use crate::circuits::poseidon::poseidon_hash;
+use crate::circuits::poseidon::poseidon_rows;
+use crate::circuits::poseidon::PoseidonConfig;
+
+mod assign;
+mod configure;
+
+/// Children of a node and words of a leaf.
+pub(crate) const MEMORY_TREE_ARITY: usize = 4;
+
+/// Level of the root, the leaves are at level 0. The tree covers 2^30 words, more than the
+/// 2^29 words of the largest heap.
+pub(crate) const MEMORY_TREE_DEPTH: usize = 14;
+
+/// Each node absorbs its level and its children, which fits in a single Poseidon block.
+const MEMORY_TREE_NODE_ROWS: usize = poseidon_rows(1);
+
+/// Number of nodes the circuit computes, the root included.
+///
+/// A trace reading initial heap words of more leaves is rejected with a `TraceCapacityError`
+/// for `CapacityTable::MemoryTree`. A leaf costs at most `MEMORY_TREE_DEPTH + 1` nodes and
+/// shares the upper ones with the other leaves: about 3.4k nodes at K = 18.
+pub(crate) fn memory_tree_capacity(max_available_rows: usize) -> usize {
+    max_available_rows / MEMORY_TREE_NODE_ROWS
+}
+
+/// Digest of a node at `level` over `children`, the words of a leaf at level 0.
+fn node_digest<F: FieldExt>(level: usize, children: &[F; MEMORY_TREE_ARITY]) -> F {
+    let mut inputs = vec![F::from(level as u64)];
+    inputs.extend_from_slice(children);
+
+    poseidon_hash(&inputs)
+}
+
+/// Indexes of the nodes of each level on the paths from the leaves holding the heap words
+/// at `offsets` to the root.
+pub(crate) fn memory_tree_paths(offsets: impl Iterator<Item = u32>) -> Vec<BTreeSet<u64>> {
+    let leaves = offsets
+        .map(|offset| offset as u64 / MEMORY_TREE_ARITY as u64)
+        .collect::<BTreeSet<_>>();
+
+    let mut paths = vec![leaves];
+    for _ in 0..MEMORY_TREE_DEPTH {
+        let parents = paths
+            .last()
+            .unwrap()
+            .iter()
+            .map(|index| index / MEMORY_TREE_ARITY as u64)
+            .collect();
+
+        paths.push(parents);
+    }
+
+    // The root is computed even if the trace reads no heap word.
+    paths[MEMORY_TREE_DEPTH].insert(0);
+
+    paths
+}
+
+/// Offsets of the initial heap words read by the trace.
+pub(crate) fn heap_init_offsets(mtable: &MTable) -> impl Iterator<Item = u32> + '_ {
+    mtable
+        .entries()
+        .iter()
+        .filter(|entry| entry.atype.is_init() && entry.ltype == LocationType::Heap)
+        .map(|entry| entry.offset)
+}
+
+/// Number of nodes the circuit computes for the heap words at `offsets`.
+pub(crate) fn memory_tree_nodes(offsets: impl Iterator<Item = u32>) -> usize {
+    memory_tree_paths(offsets)
+        .iter()
+        .map(|indexes| indexes.len())
+        .sum()
+}
+
+/// The tree of the initial heap, holding the nodes over non-zero words only.
+pub struct HeapMemoryTree<'a, F: FieldExt> {
+    words: &'a BTreeMap<u32, u64>,
+    /// Digests of the nodes over non-zero words of each level, by index.
+    nodes: Vec<BTreeMap<u64, F>>,
+    /// Digest of the nodes over zero words of each level.
+    zero_nodes: Vec<F>,
+}
+
+impl<'a, F: FieldExt> HeapMemoryTree<'a, F> {
+    /// The tree of the non-zero heap `words` by offset.
+    pub fn new(words: &'a BTreeMap<u32, u64>) -> Self {
+        let mut zero_nodes = vec![node_digest(0, &[F::zero(); MEMORY_TREE_ARITY])];
+        for level in 1..=MEMORY_TREE_DEPTH {
+            zero_nodes.push(node_digest(
+                level,
+                &[zero_nodes[level - 1]; MEMORY_TREE_ARITY],
+            ));
+        }
+
+        let mut tree = HeapMemoryTree {
+            words,
+            nodes: vec![],
+            zero_nodes,
+        };
+
+        let mut indexes = words
+            .keys()
+            .map(|offset| *offset as u64 / MEMORY_TREE_ARITY as u64)
+            .collect::<BTreeSet<_>>();
+
+        assert!(indexes
+            .iter()
+            .all(|index| *index < (MEMORY_TREE_ARITY as u64).pow(MEMORY_TREE_DEPTH as u32)));
+
+        for level in 0..=MEMORY_TREE_DEPTH {
+            let nodes = indexes
+                .iter()
+                .map(|index| (*index, node_digest(level, &tree.children(level, *index))))
+                .collect();
+            tree.nodes.push(nodes);
+
+            indexes = indexes
+                .iter()
+                .map(|index| index / MEMORY_TREE_ARITY as u64)
+                .collect();
+        }
+
+        tree
+    }
+
+    pub fn root(&self) -> F {
+        self.node(MEMORY_TREE_DEPTH, 0)
+    }
+
+    pub(crate) fn node(&self, level: usize, index: u64) -> F {
+        self.nodes[level]
+            .get(&index)
+            .cloned()
+            .unwrap_or(self.zero_nodes[level])
+    }
+
+    /// The children of the node, the words of a leaf.
+    pub(crate) fn children(&self, level: usize, index: u64) -> [F; MEMORY_TREE_ARITY] {
+        let mut children = [F::zero(); MEMORY_TREE_ARITY];
+
+        for (position, child) in children.iter_mut().enumerate() {
+            let index = index * MEMORY_TREE_ARITY as u64 + position as u64;
+
+            *child = if level == 0 {
+                F::from(self.words.get(&(index as u32)).cloned().unwrap_or(0))
+            } else {
+                self.node(level - 1, index)
+            };
+        }
+
+        children
+    }
+}
+
+/*
+ * A node takes `MEMORY_TREE_ARITY` rows, the child of the node `(level, index)` in its `k`-th
+ * row is `(level - 1, index * MEMORY_TREE_ARITY + k)`:
+ * ---------------------------------------------------------------------------------
+ * | node_sel | position | level | index | parent | child_position | input | digest |
+ * |    1     |    0     |   l   |   i   |  i / 4 |     i % 4      |  c_0  |   d    |
+ * |          |    1     |   l   |   i   |        |                |  c_1  |        |
+ * |          |   ...    |  ...  |  ...  |        |                |  ...  |        |
+ * ---------------------------------------------------------------------------------
+ * where `d` is the digest of `l, c_0, .., c_3` computed by the Poseidon gadget. The first
+ * node is the root, every other node is looked up among the children of the nodes, so its
+ * digest is authenticated by the root. The nodes not needed by the trace repeat the first
+ * child of the root. `is_leaf` exposes the words of a leaf to the memory table.
+ */
+#[derive(Clone)]
+pub struct MemoryTreeConfig<F: FieldExt> {
+    node_sel: Column<Fixed>,
+    /// The nodes but the root.
+    child_sel: Column<Fixed>,
+    root_sel: Column<Fixed>,
+    position: Column<Fixed>,
+
+    is_leaf: Column<Advice>,
+    level: Column<Advice>,
+    index: Column<Advice>,
+    parent: Column<Advice>,
+    child_position: Column<Advice>,
+    input: Column<Advice>,
+    digest: Column<Advice>,
+
+    poseidon: PoseidonConfig<F>,
+}
+
+pub struct MemoryTreeChip<F: FieldExt> {
+    config: MemoryTreeConfig<F>,
+    capacity: usize,
+}
+
+impl<F: FieldExt> MemoryTreeChip<F> {
+    pub fn new(config: MemoryTreeConfig<F>, max_available_rows: usize) -> Self {
+        MemoryTreeChip {
+            config,
+            capacity: memory_tree_capacity(max_available_rows),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use halo2_proofs::pairing::bn256::Fr;
+
+    use super::memory_tree_nodes;
+    use super::HeapMemoryTree;
+    use super::MEMORY_TREE_DEPTH;
+
+    #[test]
+    fn test_zero_words_are_elided() {
+        let empty = BTreeMap::new();
+        let words = BTreeMap::from([(1u32 << 28, 1u64)]);
+
+        let zero_tree = HeapMemoryTree::<Fr>::new(&empty);
+        let tree = HeapMemoryTree::<Fr>::new(&words);
+
+        assert_eq!(zero_tree.root(), zero_tree.zero_nodes[MEMORY_TREE_DEPTH]);
+        assert_ne!(tree.root(), zero_tree.root());
+        // A node per level, on the path of the only non-zero word.
+        assert!(tree.nodes.iter().all(|nodes| nodes.len() == 1));
+
+        // Setting a word to zero gives the tree without it.
+        let zeroed = BTreeMap::from([(1u32 << 28, 0u64)]);
+        assert_eq!(HeapMemoryTree::<Fr>::new(&zeroed).root(), zero_tree.root());
+    }
+
+    #[test]
+    fn test_memory_tree_nodes() {
+        assert_eq!(memory_tree_nodes([].into_iter()), 1);
+        assert_eq!(memory_tree_nodes([0, 3].into_iter()), MEMORY_TREE_DEPTH + 1);
+        // The second leaf shares the nodes of levels 1 and above.
+        assert_eq!(memory_tree_nodes([0, 4].into_iter()), MEMORY_TREE_DEPTH + 2);
+    }
+}
@@ -1,6 +1,10 @@
+use crate::circuits::config::with_circuit_params;
+use crate::circuits::config::CircuitParams;
+use crate::circuits::diagnose::CircuitLayout;
 use crate::circuits::utils::Context;
 
 use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::plonk::Circuit;
 use halo2_proofs::plonk::ConstraintSystem;
 use halo2_proofs::plonk::Expression;
 use halo2_proofs::plonk::VirtualCells;
@@ -8,17 +12,21 @@ use num_bigint::BigUint;
 use specs::Tables;
 use std::marker::PhantomData;
 
+pub(crate) mod bit_table;
 pub(crate) mod cell;
 pub(crate) mod etable;
+pub(crate) mod memory_tree;
+pub(crate) mod mtable;
 
-mod bit_table;
 mod external_host_call_table;
-mod mtable;
 mod traits;
 
+pub mod capacity;
 pub mod config;
+pub mod diagnose;
 pub mod image_table;
 pub mod jtable;
+pub mod poseidon;
 pub mod rtable;
 pub mod test_circuit;
 pub mod utils;
@@ -26,16 +34,30 @@ pub mod utils;
 #[derive(Default, Clone)]
 pub struct TestCircuit<F: FieldExt> {
     pub tables: Tables,
+    /// Absorbed into the public input digest if enabled.
+    pub public_inputs_and_outputs: Vec<u64>,
+    /// Configured by `configure` only within `with_circuit_params`, synthesizing the circuit
+    /// configured with other parameters panics.
+    pub params: CircuitParams,
     _data: PhantomData<F>,
 }
 
 impl<F: FieldExt> TestCircuit<F> {
-    pub fn new(tables: Tables) -> Self {
+    pub fn new(tables: Tables, public_inputs_and_outputs: Vec<u64>, params: CircuitParams) -> Self {
         TestCircuit {
             tables,
+            public_inputs_and_outputs,
+            params,
             _data: PhantomData,
         }
     }
+
+    /// Tables of the gates and lookups, to locate the failures of `MockProver`.
+    pub(crate) fn layout(&self) -> CircuitLayout {
+        with_circuit_params(&self.params, || {
+            Self::configure(&mut ConstraintSystem::default()).layout
+        })
+    }
 }
 
 trait Encode {
@@ -58,10 +80,15 @@ pub(self) trait Lookup<F: FieldExt> {
 pub struct ZkWasmCircuitBuilder {
     pub tables: Tables,
     pub public_inputs_and_outputs: Vec<u64>,
+    pub params: CircuitParams,
 }
 
 impl ZkWasmCircuitBuilder {
     pub fn build_circuit<F: FieldExt>(&self) -> TestCircuit<F> {
-        TestCircuit::new(self.tables.clone())
+        TestCircuit::new(
+            self.tables.clone(),
+            self.public_inputs_and_outputs.clone(),
+            self.params.clone(),
+        )
     }
 }
@@ -1,6 +1,7 @@
 use self::allocator::*;
 use super::cell::*;
 use super::image_table::ImageTableConfig;
+use super::memory_tree::MemoryTreeConfig;
 use super::rtable::RangeTableConfig;
 use super::traits::ConfigureLookupTable;
 use crate::constant_from;
@@ -64,6 +65,7 @@ impl<F: FieldExt> MemoryTableConfig<F> {
         cols: &mut (impl Iterator<Item = Column<Advice>> + Clone),
         rtable: &RangeTableConfig<F>,
         image_table: &ImageTableConfig<F>,
+        memory_tree: &MemoryTreeConfig<F>,
     ) -> Self {
         let entry_sel = meta.fixed_column();
 
@@ -197,6 +199,10 @@ impl<F: FieldExt> MemoryTableConfig<F> {
                 is_init_cell.curr_expr(meta)
                     * (offset_cell.curr_expr(meta) + offset_align_right_diff_cell.curr_expr(meta)
                         - offset_align_right.curr_expr(meta)),
+                // The heap is mutable, it is encoded as such in the memory tree lookup.
+                is_init_cell.curr_expr(meta)
+                    * is_heap_cell.curr_expr(meta)
+                    * (is_mutable.curr_expr(meta) - constant_from!(1)),
             ]
             .into_iter()
             .map(|x| x * fixed_curr!(meta, entry_sel))
@@ -239,7 +245,17 @@ impl<F: FieldExt> MemoryTableConfig<F> {
         });
 
         image_table.init_memory_lookup(meta, "mc7c. imtable init", |meta| {
-            init_encode_cell.curr_expr(meta) * fixed_curr!(meta, entry_sel)
+            init_encode_cell.curr_expr(meta)
+                * is_global_cell.curr_expr(meta)
+                * fixed_curr!(meta, entry_sel)
+        });
+
+        memory_tree.configure_in_table(meta, "mc7d. heap init in memory tree", |meta| {
+            vec![
+                init_encode_cell.curr_expr(meta)
+                    * is_heap_cell.curr_expr(meta)
+                    * fixed_curr!(meta, entry_sel),
+            ]
         });
 
         meta.create_gate("mc8. vtype", |meta| {
@@ -0,0 +1,755 @@
+//! Poseidon hash gadget.
+//!
+//! Constrains the sponge of the `poseidon` crate, with the parameters the aggregator uses for
+//! its transcript. The permutation is laid out as in `poseidon::Spec::permute`: the optimized
+//! round constants and the sparse matrices of the partial rounds are taken from the spec.
+//!
+//! The gadget has a fixed number of blocks so that its layout doesn't depend on the witness.
+//! A block absorbs `POSEIDON_RATE` elements, only the leading `active` elements are absorbed,
+//! followed by the padding element one, and the blocks past the padding leave the state as is.
+
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::AssignedCell;
+use halo2_proofs::circuit::Cell;
+use halo2_proofs::circuit::Region;
+use halo2_proofs::plonk::Advice;
+use halo2_proofs::plonk::Column;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::Error;
+use halo2_proofs::plonk::Expression;
+use halo2_proofs::plonk::Fixed;
+use halo2_proofs::plonk::VirtualCells;
+use poseidon::Poseidon;
+use poseidon::Spec;
+
+use crate::constant;
+use crate::constant_from;
+use crate::curr;
+use crate::fixed_curr;
+use crate::next;
+use crate::nextn;
+
+pub const POSEIDON_T: usize = 9;
+pub const POSEIDON_RATE: usize = 8;
+pub const POSEIDON_R_F: usize = 8;
+pub const POSEIDON_R_P: usize = 63;
+
+/// The absorbing row, a row per round and the row holding the permuted state.
+pub(crate) const POSEIDON_BLOCK_ROWS: usize = POSEIDON_R_F + POSEIDON_R_P + 2;
+
+/// Rows of the gadget absorbing `blocks` blocks.
+pub(crate) const fn poseidon_rows(blocks: usize) -> usize {
+    blocks * POSEIDON_BLOCK_ROWS + 2
+}
+
+/// Maximal number of elements absorbed by `blocks` blocks, the padding element must fit in the
+/// last block.
+pub(crate) fn poseidon_capacity(blocks: usize) -> usize {
+    blocks * POSEIDON_RATE - 1
+}
+
+/// Digest of `values`, as constrained by `PoseidonChip::assign`.
+pub fn poseidon_hash<F: FieldExt>(values: &[F]) -> F {
+    let mut hasher = Poseidon::<F, POSEIDON_T, POSEIDON_RATE>::new(POSEIDON_R_F, POSEIDON_R_P);
+    hasher.update(values);
+    hasher.squeeze()
+}
+
+/// Digest of a sequence of words, the first absorbed element is its length plus one.
+pub fn poseidon_hash_with_length<F: FieldExt>(values: &[u64]) -> F {
+    let mut elements = vec![F::from(values.len() as u64 + 1)];
+    elements.extend(values.iter().map(|value| F::from(*value)));
+
+    poseidon_hash(&elements)
+}
+
+/// Initial state of the sponge of `poseidon::Poseidon`.
+fn initial_state<F: FieldExt>() -> [F; POSEIDON_T] {
+    let mut state = [F::zero(); POSEIDON_T];
+    state[0] = F::from_u128(1 << 64);
+    state
+}
+
+fn pow5<F: FieldExt>(x: F) -> F {
+    x.square().square() * x
+}
+
+#[derive(Clone)]
+enum Round<F: FieldExt> {
+    /// S-box of each word, plus `constants`, then the MDS matrix or the pre-sparse one.
+    Full {
+        constants: [F; POSEIDON_T],
+        pre_sparse: bool,
+    },
+    /// S-box of the first word plus `constant`, then a sparse matrix.
+    Partial {
+        constant: F,
+        row: [F; POSEIDON_T],
+        col_hat: [F; POSEIDON_RATE],
+    },
+}
+
+/// Constants of the optimized permutation, in the order `poseidon::Spec::permute` uses them.
+#[derive(Clone)]
+struct PoseidonSpec<F: FieldExt> {
+    /// Added to the state before the first round.
+    start: [F; POSEIDON_T],
+    mds: [[F; POSEIDON_T]; POSEIDON_T],
+    pre_sparse_mds: [[F; POSEIDON_T]; POSEIDON_T],
+    rounds: Vec<Round<F>>,
+}
+
+impl<F: FieldExt> PoseidonSpec<F> {
+    fn new() -> Self {
+        let spec = Spec::<F, POSEIDON_T, POSEIDON_RATE>::new(POSEIDON_R_F, POSEIDON_R_P);
+        let constants = spec.constants();
+        let matrices = spec.mds_matrices();
+
+        let full = |constants: [F; POSEIDON_T], pre_sparse| Round::Full {
+            constants,
+            pre_sparse,
+        };
+
+        let mut rounds = vec![];
+        rounds.extend(
+            constants
+                .start()
+                .iter()
+                .skip(1)
+                .take(POSEIDON_R_F / 2 - 1)
+                .map(|constants| full(*constants, false)),
+        );
+        rounds.push(full(*constants.start().last().unwrap(), true));
+        rounds.extend(
+            constants
+                .partial()
+                .iter()
+                .zip(matrices.sparse_matrices().iter())
+                .map(|(constant, sparse)| Round::Partial {
+                    constant: *constant,
+                    row: *sparse.row(),
+                    col_hat: *sparse.col_hat(),
+                }),
+        );
+        rounds.extend(
+            constants
+                .end()
+                .iter()
+                .map(|constants| full(*constants, false)),
+        );
+        rounds.push(full([F::zero(); POSEIDON_T], false));
+
+        assert_eq!(rounds.len(), POSEIDON_BLOCK_ROWS - 2);
+
+        PoseidonSpec {
+            start: constants.start()[0],
+            mds: matrices.mds().rows(),
+            pre_sparse_mds: matrices.pre_sparse_mds().rows(),
+            rounds,
+        }
+    }
+
+    /// Apply `round` to `state`, returns the values of the square columns of the round.
+    fn apply_round(&self, round: &Round<F>, state: &mut [F; POSEIDON_T]) -> Vec<F> {
+        match round {
+            Round::Full {
+                constants,
+                pre_sparse,
+            } => {
+                let squares = state.iter().map(|word| word.square()).collect();
+                let sbox = state
+                    .iter()
+                    .zip(constants.iter())
+                    .map(|(word, constant)| pow5(*word) + constant)
+                    .collect::<Vec<_>>();
+                let matrix = if *pre_sparse {
+                    &self.pre_sparse_mds
+                } else {
+                    &self.mds
+                };
+
+                for (word, row) in state.iter_mut().zip(matrix.iter()) {
+                    *word = row
+                        .iter()
+                        .zip(sbox.iter())
+                        .fold(F::zero(), |acc, (m, s)| acc + *m * s);
+                }
+
+                squares
+            }
+            Round::Partial {
+                constant,
+                row,
+                col_hat,
+            } => {
+                let squares = vec![state[0].square(), pow5(state[0]) + constant];
+                let sbox = squares[1];
+
+                let first = row
+                    .iter()
+                    .zip(state.iter())
+                    .skip(1)
+                    .fold(row[0] * sbox, |acc, (m, word)| acc + *m * word);
+                for (word, c) in state.iter_mut().skip(1).zip(col_hat.iter()) {
+                    *word += *c * sbox;
+                }
+                state[0] = first;
+
+                squares
+            }
+        }
+    }
+}
+
+/*
+ * Rows of a block, `s` is the state before the block, `a_k` whether the element `x_k` is
+ * absorbed and `a_-1` the flag of the last element of the previous block:
+ * ---------------------------------------------------------------------------------
+ * | absorb_sel | round_sel | chain_sel |   state   | square | input | active |
+ * |     1      |           |           |     s     |        |  x_0  |  a_0   |
+ * |            |     1     |           |  s + x'   |  r_0   |  x_1  |  a_1   |
+ * |            |    ...    |           |    ...    |  ...   |  ...  |  ...   |
+ * |            |     1     |           |    ...    |  r_70  |       |        |
+ * |            |           |     1     | p(s + x') |        |       |  a_7   |
+ * |     1      |           |           |    s'     |        |  x'_0 |  a'_0  |
+ * ---------------------------------------------------------------------------------
+ * where `x'_k = x_k + a_{k-1} - a_k` appends the padding element to the absorbed elements and
+ * the round constants added before the first round are omitted. The state after the block is
+ * `s' = p(s + x')` if `a_-1` is set, `s` otherwise. The blocks are preceded by a row setting
+ * the initial state, and followed by a row holding the digest in the second word of the state.
+ */
+#[derive(Clone)]
+pub(crate) struct PoseidonConfig<F: FieldExt> {
+    init_sel: Column<Fixed>,
+    slot_sel: Column<Fixed>,
+    absorb_sel: Column<Fixed>,
+    full_round_sel: Column<Fixed>,
+    pre_sparse_round_sel: Column<Fixed>,
+    partial_round_sel: Column<Fixed>,
+    chain_sel: Column<Fixed>,
+    /// Constants added after the S-boxes of a round.
+    round_constants: [Column<Fixed>; POSEIDON_T],
+    sparse_row: [Column<Fixed>; POSEIDON_T],
+    sparse_col_hat: [Column<Fixed>; POSEIDON_RATE],
+
+    state: [Column<Advice>; POSEIDON_T],
+    /// Squares of the words in full rounds. In partial rounds, the square of the first word
+    /// and its S-box output.
+    square: [Column<Advice>; POSEIDON_T],
+    input: Column<Advice>,
+    active: Column<Advice>,
+
+    spec: PoseidonSpec<F>,
+}
+
+fn full_round<F: FieldExt>(
+    meta: &mut VirtualCells<'_, F>,
+    matrix: &[[F; POSEIDON_T]; POSEIDON_T],
+    state: &[Column<Advice>; POSEIDON_T],
+    square: &[Column<Advice>; POSEIDON_T],
+    round_constants: &[Column<Fixed>; POSEIDON_T],
+) -> Vec<Expression<F>> {
+    let sbox = (0..POSEIDON_T)
+        .map(|i| {
+            curr!(meta, square[i]) * curr!(meta, square[i]) * curr!(meta, state[i])
+                + fixed_curr!(meta, round_constants[i])
+        })
+        .collect::<Vec<_>>();
+
+    let squares = (0..POSEIDON_T)
+        .map(|i| curr!(meta, square[i]) - curr!(meta, state[i]) * curr!(meta, state[i]));
+
+    let words = matrix.iter().enumerate().map(|(j, row)| {
+        next!(meta, state[j])
+            - row
+                .iter()
+                .zip(sbox.iter())
+                .map(|(m, s)| constant!(*m) * s.clone())
+                .reduce(|acc, x| acc + x)
+                .unwrap()
+    });
+
+    squares.chain(words).collect()
+}
+
+impl<F: FieldExt> PoseidonConfig<F> {
+    pub(crate) fn configure(meta: &mut ConstraintSystem<F>) -> Self {
+        let init_sel = meta.fixed_column();
+        let slot_sel = meta.fixed_column();
+        let absorb_sel = meta.fixed_column();
+        let full_round_sel = meta.fixed_column();
+        let pre_sparse_round_sel = meta.fixed_column();
+        let partial_round_sel = meta.fixed_column();
+        let chain_sel = meta.fixed_column();
+        let round_constants = [(); POSEIDON_T].map(|_| meta.fixed_column());
+        let sparse_row = [(); POSEIDON_T].map(|_| meta.fixed_column());
+        let sparse_col_hat = [(); POSEIDON_RATE].map(|_| meta.fixed_column());
+
+        let state = [(); POSEIDON_T].map(|_| meta.advice_column());
+        let square = [(); POSEIDON_T].map(|_| meta.advice_column());
+        let input = meta.advice_column();
+        let active = meta.advice_column();
+
+        meta.enable_equality(state[1]);
+        meta.enable_equality(input);
+        meta.enable_equality(active);
+
+        let spec = PoseidonSpec::<F>::new();
+        let block_rows = POSEIDON_BLOCK_ROWS as i32;
+
+        meta.create_gate("poseidon init", |meta| {
+            let mut exprs = vec![curr!(meta, active) - constant_from!(1)];
+            exprs.extend(
+                initial_state::<F>()
+                    .iter()
+                    .zip(state.iter())
+                    .map(|(word, column)| next!(meta, *column) - constant!(*word)),
+            );
+
+            exprs
+                .into_iter()
+                .map(|expr| expr * fixed_curr!(meta, init_sel))
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon slot", |meta| {
+            vec![
+                curr!(meta, active) * (constant_from!(1) - curr!(meta, active)),
+                // Elements past the absorbed ones are zero.
+                (constant_from!(1) - curr!(meta, active)) * curr!(meta, input),
+                // Absorbed elements are a prefix of all elements.
+                curr!(meta, active) * (constant_from!(1) - nextn!(meta, active, -1)),
+            ]
+            .into_iter()
+            .map(|expr| expr * fixed_curr!(meta, slot_sel))
+            .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon absorb", |meta| {
+            let start = spec.start;
+
+            let mut exprs =
+                vec![next!(meta, state[0]) - curr!(meta, state[0]) - constant!(start[0])];
+            exprs.extend((1..POSEIDON_T).map(|j| {
+                let k = j as i32 - 1;
+                // The element followed by the padding element if it is the first not absorbed.
+                let element =
+                    nextn!(meta, input, k) + nextn!(meta, active, k - 1) - nextn!(meta, active, k);
+
+                next!(meta, state[j]) - curr!(meta, state[j]) - constant!(start[j]) - element
+            }));
+
+            exprs
+                .into_iter()
+                .map(|expr| expr * fixed_curr!(meta, absorb_sel))
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon full round", |meta| {
+            full_round(meta, &spec.mds, &state, &square, &round_constants)
+                .into_iter()
+                .map(|expr| expr * fixed_curr!(meta, full_round_sel))
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon pre-sparse round", |meta| {
+            full_round(
+                meta,
+                &spec.pre_sparse_mds,
+                &state,
+                &square,
+                &round_constants,
+            )
+            .into_iter()
+            .map(|expr| expr * fixed_curr!(meta, pre_sparse_round_sel))
+            .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon partial round", |meta| {
+            let sbox = curr!(meta, square[1]);
+
+            let mut exprs = vec![
+                curr!(meta, square[0]) - curr!(meta, state[0]) * curr!(meta, state[0]),
+                sbox.clone()
+                    - curr!(meta, square[0]) * curr!(meta, square[0]) * curr!(meta, state[0])
+                    - fixed_curr!(meta, round_constants[0]),
+                next!(meta, state[0])
+                    - (1..POSEIDON_T)
+                        .map(|i| fixed_curr!(meta, sparse_row[i]) * curr!(meta, state[i]))
+                        .fold(fixed_curr!(meta, sparse_row[0]) * sbox.clone(), |acc, x| {
+                            acc + x
+                        }),
+            ];
+            exprs.extend((1..POSEIDON_T).map(|i| {
+                next!(meta, state[i])
+                    - curr!(meta, state[i])
+                    - fixed_curr!(meta, sparse_col_hat[i - 1]) * sbox.clone()
+            }));
+
+            exprs
+                .into_iter()
+                .map(|expr| expr * fixed_curr!(meta, partial_round_sel))
+                .collect::<Vec<_>>()
+        });
+
+        meta.create_gate("poseidon chain", |meta| {
+            // Whether the last element of the previous block is absorbed.
+            let run = nextn!(meta, active, -block_rows);
+
+            let mut exprs =
+                vec![curr!(meta, active) - nextn!(meta, active, POSEIDON_RATE as i32 - block_rows)];
+            exprs.extend(state.iter().map(|column| {
+                next!(meta, *column)
+                    - run.clone() * curr!(meta, *column)
+                    - (constant_from!(1) - run.clone()) * nextn!(meta, *column, 1 - block_rows)
+            }));
+
+            exprs
+                .into_iter()
+                .map(|expr| expr * fixed_curr!(meta, chain_sel))
+                .collect::<Vec<_>>()
+        });
+
+        PoseidonConfig {
+            init_sel,
+            slot_sel,
+            absorb_sel,
+            full_round_sel,
+            pre_sparse_round_sel,
+            partial_round_sel,
+            chain_sel,
+            round_constants,
+            sparse_row,
+            sparse_col_hat,
+            state,
+            square,
+            input,
+            active,
+            spec,
+        }
+    }
+}
+
+pub(crate) struct PoseidonChip<F: FieldExt> {
+    config: PoseidonConfig<F>,
+    /// Number of blocks, each absorbing `POSEIDON_RATE` elements.
+    blocks: usize,
+}
+
+impl<F: FieldExt> PoseidonChip<F> {
+    pub(crate) fn new(config: PoseidonConfig<F>, blocks: usize) -> Self {
+        PoseidonChip { config, blocks }
+    }
+
+    fn assign_state(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        state: &[F; POSEIDON_T],
+    ) -> Result<Vec<AssignedCell<F, F>>, Error> {
+        state
+            .iter()
+            .zip(self.config.state.iter())
+            .map(|(word, column)| {
+                region.assign_advice(|| "poseidon state", *column, row, || Ok(*word))
+            })
+            .collect()
+    }
+
+    fn assign_round(
+        &self,
+        region: &mut Region<'_, F>,
+        row: usize,
+        round: &Round<F>,
+        squares: &[F],
+    ) -> Result<(), Error> {
+        let one = || Ok(F::one());
+
+        match round {
+            Round::Full {
+                constants,
+                pre_sparse,
+            } => {
+                let sel = if *pre_sparse {
+                    self.config.pre_sparse_round_sel
+                } else {
+                    self.config.full_round_sel
+                };
+                region.assign_fixed(|| "poseidon round sel", sel, row, one)?;
+
+                for (constant, column) in constants.iter().zip(self.config.round_constants) {
+                    region.assign_fixed(
+                        || "poseidon round constant",
+                        column,
+                        row,
+                        || Ok(*constant),
+                    )?;
+                }
+            }
+            Round::Partial {
+                constant,
+                row: sparse_row,
+                col_hat,
+            } => {
+                region.assign_fixed(
+                    || "poseidon round sel",
+                    self.config.partial_round_sel,
+                    row,
+                    one,
+                )?;
+                region.assign_fixed(
+                    || "poseidon round constant",
+                    self.config.round_constants[0],
+                    row,
+                    || Ok(*constant),
+                )?;
+
+                for (m, column) in sparse_row.iter().zip(self.config.sparse_row) {
+                    region.assign_fixed(|| "poseidon sparse row", column, row, || Ok(*m))?;
+                }
+                for (m, column) in col_hat.iter().zip(self.config.sparse_col_hat) {
+                    region.assign_fixed(|| "poseidon sparse col", column, row, || Ok(*m))?;
+                }
+            }
+        }
+
+        for (square, column) in squares.iter().zip(self.config.square) {
+            region.assign_advice(|| "poseidon square", column, row, || Ok(*square))?;
+        }
+
+        Ok(())
+    }
+
+    /// Absorb the first `active` inputs at `offset` and return the digest cell. Each input is
+    /// copied from its cell if any, missing and inactive inputs must be zero.
+    pub(crate) fn assign(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        inputs: &[(F, Option<Cell>)],
+        active: usize,
+    ) -> Result<AssignedCell<F, F>, Error> {
+        assert!(inputs.len() <= poseidon_capacity(self.blocks));
+        assert!(active <= inputs.len());
+
+        let spec = &self.config.spec;
+
+        region.assign_fixed(
+            || "poseidon init sel",
+            self.config.init_sel,
+            offset,
+            || Ok(F::one()),
+        )?;
+        region.assign_advice_from_constant(
+            || "poseidon active",
+            self.config.active,
+            offset,
+            F::one(),
+        )?;
+
+        let mut state = initial_state::<F>();
+
+        for block in 0..self.blocks {
+            let base = offset + 1 + block * POSEIDON_BLOCK_ROWS;
+            let chain = base + POSEIDON_BLOCK_ROWS - 1;
+
+            region.assign_fixed(
+                || "poseidon absorb sel",
+                self.config.absorb_sel,
+                base,
+                || Ok(F::one()),
+            )?;
+            region.assign_fixed(
+                || "poseidon chain sel",
+                self.config.chain_sel,
+                chain,
+                || Ok(F::one()),
+            )?;
+
+            let mut absorbed = state;
+            for (word, constant) in absorbed.iter_mut().zip(spec.start.iter()) {
+                *word += constant;
+            }
+
+            for slot in 0..POSEIDON_RATE {
+                let index = block * POSEIDON_RATE + slot;
+                let (input, bound_cell) = inputs.get(index).cloned().unwrap_or((F::zero(), None));
+                let is_active = |index: usize| if index < active { F::one() } else { F::zero() };
+                // The element before the first is considered absorbed.
+                let prev_active = if index == 0 {
+                    F::one()
+                } else {
+                    is_active(index - 1)
+                };
+
+                region.assign_fixed(
+                    || "poseidon slot sel",
+                    self.config.slot_sel,
+                    base + slot,
+                    || Ok(F::one()),
+                )?;
+                let input_cell = region.assign_advice(
+                    || "poseidon input",
+                    self.config.input,
+                    base + slot,
+                    || Ok(input),
+                )?;
+                region.assign_advice(
+                    || "poseidon active",
+                    self.config.active,
+                    base + slot,
+                    || Ok(is_active(index)),
+                )?;
+
+                if let Some(cell) = bound_cell {
+                    region.constrain_equal(input_cell.cell(), cell)?;
+                }
+
+                absorbed[slot + 1] += input + prev_active - is_active(index);
+            }
+
+            let last_active = (block + 1) * POSEIDON_RATE <= active;
+            if block + 1 == self.blocks {
+                // The padding element is absorbed by the last block at the latest.
+                region.assign_advice_from_constant(
+                    || "poseidon active",
+                    self.config.active,
+                    chain,
+                    F::zero(),
+                )?;
+            } else {
+                region.assign_advice(
+                    || "poseidon active",
+                    self.config.active,
+                    chain,
+                    || Ok(if last_active { F::one() } else { F::zero() }),
+                )?;
+            }
+
+            self.assign_state(region, base, &state)?;
+            self.assign_state(region, base + 1, &absorbed)?;
+
+            let mut permuted = absorbed;
+            for (index, round) in spec.rounds.iter().enumerate() {
+                let row = base + 1 + index;
+
+                let squares = spec.apply_round(round, &mut permuted);
+                self.assign_round(region, row, round, &squares)?;
+                self.assign_state(region, row + 1, &permuted)?;
+            }
+
+            // Blocks past the padding element leave the state as is.
+            if block * POSEIDON_RATE <= active {
+                state = permuted;
+            }
+        }
+
+        let terminal = offset + 1 + self.blocks * POSEIDON_BLOCK_ROWS;
+        let cells = self.assign_state(region, terminal, &state)?;
+
+        Ok(cells[1].clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::circuit::Layouter;
+    use halo2_proofs::circuit::SimpleFloorPlanner;
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::Circuit;
+    use halo2_proofs::plonk::Column;
+    use halo2_proofs::plonk::ConstraintSystem;
+    use halo2_proofs::plonk::Error;
+    use halo2_proofs::plonk::Instance;
+
+    use super::poseidon_capacity;
+    use super::poseidon_hash;
+    use super::poseidon_rows;
+    use super::PoseidonChip;
+    use super::PoseidonConfig;
+
+    const BLOCKS: usize = 3;
+
+    #[derive(Default)]
+    struct HashCircuit {
+        inputs: Vec<Fr>,
+    }
+
+    impl Circuit<Fr> for HashCircuit {
+        type Config = (PoseidonConfig<Fr>, Column<Instance>);
+        type FloorPlanner = SimpleFloorPlanner;
+
+        fn without_witnesses(&self) -> Self {
+            Self::default()
+        }
+
+        fn configure(meta: &mut ConstraintSystem<Fr>) -> Self::Config {
+            let constants = meta.fixed_column();
+            meta.enable_constant(constants);
+
+            let digest = meta.instance_column();
+            meta.enable_equality(digest);
+
+            (PoseidonConfig::configure(meta), digest)
+        }
+
+        fn synthesize(
+            &self,
+            (config, digest): Self::Config,
+            mut layouter: impl Layouter<Fr>,
+        ) -> Result<(), Error> {
+            let chip = PoseidonChip::new(config, BLOCKS);
+
+            let cell = layouter.assign_region(
+                || "poseidon",
+                |mut region| {
+                    let inputs = self
+                        .inputs
+                        .iter()
+                        .map(|input| (*input, None))
+                        .collect::<Vec<_>>();
+
+                    chip.assign(&mut region, 0, &inputs, inputs.len())
+                },
+            )?;
+
+            layouter.constrain_instance(cell.cell(), digest, 0)
+        }
+    }
+
+    #[test]
+    fn test_poseidon_chip() {
+        let k = 9;
+        assert!(poseidon_rows(BLOCKS) < 1 << k);
+
+        for len in [0, 1, 7, 8, 9, 16, poseidon_capacity(BLOCKS)] {
+            let inputs = (0..len as u64).map(|i| Fr::from(i + 1)).collect::<Vec<_>>();
+            let digest = poseidon_hash(&inputs);
+            let circuit = HashCircuit { inputs };
+
+            assert_eq!(
+                MockProver::run(k, &circuit, vec![vec![digest]])
+                    .unwrap()
+                    .verify(),
+                Ok(()),
+                "{} inputs",
+                len
+            );
+
+            assert!(MockProver::run(k, &circuit, vec![vec![digest + Fr::one()]])
+                .unwrap()
+                .verify()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_poseidon_hash() {
+        let a = poseidon_hash(&[Fr::from(1), Fr::from(2)]);
+
+        assert_eq!(a, poseidon_hash(&[Fr::from(1), Fr::from(2)]));
+        assert_ne!(a, poseidon_hash(&[Fr::from(2), Fr::from(1)]));
+        assert_ne!(a, poseidon_hash(&[Fr::from(1), Fr::from(2), Fr::from(0)]));
+    }
+}
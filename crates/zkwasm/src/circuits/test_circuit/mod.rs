@@ -16,6 +16,10 @@ use specs::Tables;
 
 use crate::circuits::bit_table::BitTableChip;
 use crate::circuits::bit_table::BitTableConfig;
+use crate::circuits::config::circuit_params;
+use crate::circuits::config::CircuitParams;
+use crate::circuits::diagnose::CircuitLayout;
+use crate::circuits::diagnose::FailureTable;
 use crate::circuits::etable::EventTableChip;
 use crate::circuits::etable::EventTableConfig;
 use crate::circuits::external_host_call_table::ExternalHostCallChip;
@@ -25,20 +29,29 @@ use crate::circuits::image_table::ImageTableChip;
 use crate::circuits::image_table::ImageTableLayouter;
 use crate::circuits::jtable::JumpTableChip;
 use crate::circuits::jtable::JumpTableConfig;
+use crate::circuits::memory_tree::heap_init_offsets;
+use crate::circuits::memory_tree::HeapMemoryTree;
+use crate::circuits::memory_tree::MemoryTreeChip;
+use crate::circuits::memory_tree::MemoryTreeConfig;
 use crate::circuits::mtable::MemoryTableChip;
 use crate::circuits::mtable::MemoryTableConfig;
 use crate::circuits::rtable::RangeTableChip;
 use crate::circuits::rtable::RangeTableConfig;
+use crate::circuits::traits::ConfigureLookupTable;
 use crate::circuits::utils::table_entry::EventTableWithMemoryInfo;
 use crate::circuits::utils::table_entry::MemoryWritingTable;
 use crate::circuits::utils::Context;
 use crate::circuits::TestCircuit;
 use crate::exec_with_profile;
+use crate::foreign::bigint_helper::circuits::assign::U256HelperTableChip;
+use crate::foreign::bigint_helper::circuits::U256HelperTableConfig;
+use crate::foreign::bigint_helper::circuits::U256_FOREIGN_TABLE_KEY;
 use crate::foreign::context::circuits::assign::ContextContHelperTableChip;
 use crate::foreign::context::circuits::assign::ExtractContextFromTrace;
 use crate::foreign::context::circuits::ContextContHelperTableConfig;
 use crate::foreign::context::circuits::CONTEXT_FOREIGN_TABLE_KEY;
 use crate::foreign::foreign_table_enable_lines;
+use crate::foreign::wasm_input_helper::circuits::assign::WasmInputHelperTableChip;
 use crate::foreign::wasm_input_helper::circuits::WasmInputHelperTableConfig;
 use crate::foreign::wasm_input_helper::circuits::WASM_INPUT_FOREIGN_TABLE_KEY;
 use crate::foreign::ForeignTableConfig;
@@ -46,7 +59,8 @@ use crate::foreign::ForeignTableConfig;
 use super::config::zkwasm_k;
 use super::image_table::ImageTableConfig;
 
-pub const VAR_COLUMNS: usize = 51;
+// Columns of the memory table and the jump table, the event table creates its own.
+pub const VAR_COLUMNS: usize = 10;
 
 // Reserve a few rows to keep usable rows away from blind rows.
 // The maximal step size of all tables is bit_table::STEP_SIZE.
@@ -56,16 +70,22 @@ const RESERVE_ROWS: usize = crate::circuits::bit_table::STEP_SIZE;
 pub struct TestCircuitConfig<F: FieldExt> {
     rtable: RangeTableConfig<F>,
     image_table: ImageTableConfig<F>,
+    memory_tree: MemoryTreeConfig<F>,
     mtable: MemoryTableConfig<F>,
     jtable: JumpTableConfig<F>,
-    etable: EventTableConfig<F>,
+    pub(crate) etable: EventTableConfig<F>,
     bit_table: BitTableConfig<F>,
     external_host_call_table: ExternalHostCallTableConfig<F>,
+    wasm_input_helper_table: WasmInputHelperTableConfig<F>,
     context_helper_table: ContextContHelperTableConfig<F>,
+    u256_helper_table: U256HelperTableConfig<F>,
 
     foreign_table_from_zero_index: Column<Fixed>,
 
-    max_available_rows: usize,
+    pub(crate) max_available_rows: usize,
+    pub(crate) layout: CircuitLayout,
+    /// The parameters in scope of `configure`.
+    params: CircuitParams,
 }
 
 impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
@@ -74,10 +94,14 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
     type FloorPlanner = SimpleFloorPlanner;
 
     fn without_witnesses(&self) -> Self {
-        TestCircuit::new(Tables {
-            compilation_tables: self.tables.compilation_tables.clone(),
-            execution_tables: ExecutionTable::default(),
-        })
+        TestCircuit::new(
+            Tables {
+                compilation_tables: self.tables.compilation_tables.clone(),
+                execution_tables: ExecutionTable::default(),
+            },
+            vec![],
+            self.params.clone(),
+        )
     }
 
     fn configure(meta: &mut ConstraintSystem<F>) -> Self::Config {
@@ -92,18 +116,53 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
         let foreign_table_from_zero_index = meta.fixed_column();
 
         let mut cols = [(); VAR_COLUMNS].map(|_| meta.advice_column()).into_iter();
-
-        let rtable = RangeTableConfig::configure(meta);
-        let image_table = ImageTableConfig::configure(meta);
-        let mtable = MemoryTableConfig::configure(meta, &mut cols, &rtable, &image_table);
-        let jtable = JumpTableConfig::configure(meta, &mut cols);
-        let external_host_call_table = ExternalHostCallTableConfig::configure(meta);
-        let bit_table = BitTableConfig::configure(meta, &rtable);
+        let mut layout = CircuitLayout::default();
+
+        let rtable = layout.configure(meta, FailureTable::RangeTable, |meta| {
+            RangeTableConfig::configure(meta)
+        });
+        let image_table = layout.configure(meta, FailureTable::ImageTable, |meta| {
+            ImageTableConfig::configure(meta)
+        });
+        let memory_tree = layout.configure(meta, FailureTable::MemoryTree, |meta| {
+            MemoryTreeConfig::configure(meta)
+        });
+        let mtable = layout.configure(meta, FailureTable::Mtable, |meta| {
+            MemoryTableConfig::configure(meta, &mut cols, &rtable, &image_table, &memory_tree)
+        });
+        let jtable = layout.configure(meta, FailureTable::Jtable, |meta| {
+            JumpTableConfig::configure(meta, &mut cols)
+        });
+        let external_host_call_table =
+            layout.configure(meta, FailureTable::ExternalHostCallTable, |meta| {
+                ExternalHostCallTableConfig::configure(meta)
+            });
+        let bit_table = layout.configure(meta, FailureTable::BitTable, |meta| {
+            BitTableConfig::configure(meta, &rtable)
+        });
 
         let wasm_input_helper_table =
-            WasmInputHelperTableConfig::configure(meta, foreign_table_from_zero_index);
+            layout.configure(meta, FailureTable::WasmInputHelperTable, |meta| {
+                WasmInputHelperTableConfig::configure(meta, foreign_table_from_zero_index)
+            });
         let context_helper_table =
-            ContextContHelperTableConfig::configure(meta, foreign_table_from_zero_index);
+            layout.configure(meta, FailureTable::ContextHelperTable, |meta| {
+                ContextContHelperTableConfig::configure(
+                    meta,
+                    foreign_table_from_zero_index,
+                    &rtable,
+                )
+            });
+        let u256_helper_table = layout.configure(meta, FailureTable::U256HelperTable, |meta| {
+            let u256_helper_table = U256HelperTableConfig::configure(meta, &rtable);
+            external_host_call_table.configure_in_table(
+                meta,
+                "u256 helper slot in external host call table",
+                |meta| u256_helper_table.external_host_call_lookup(meta),
+            );
+
+            u256_helper_table
+        });
 
         let mut foreign_table_configs: BTreeMap<_, Box<(dyn ForeignTableConfig<F>)>> =
             BTreeMap::new();
@@ -115,18 +174,20 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
             CONTEXT_FOREIGN_TABLE_KEY,
             Box::new(context_helper_table.clone()),
         );
-
-        let etable = EventTableConfig::configure(
-            meta,
-            &mut cols,
-            &rtable,
-            &image_table,
-            &mtable,
-            &jtable,
-            &bit_table,
-            &external_host_call_table,
-            &foreign_table_configs,
-        );
+        foreign_table_configs.insert(U256_FOREIGN_TABLE_KEY, Box::new(u256_helper_table.clone()));
+
+        let etable = layout.configure(meta, FailureTable::Etable, |meta| {
+            EventTableConfig::configure(
+                meta,
+                &rtable,
+                &image_table,
+                &mtable,
+                &jtable,
+                &bit_table,
+                &external_host_call_table,
+                &foreign_table_configs,
+            )
+        });
 
         assert_eq!(cols.count(), 0);
 
@@ -136,15 +197,20 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
         Self::Config {
             rtable,
             image_table,
+            memory_tree,
             mtable,
             jtable,
             etable,
             bit_table,
             external_host_call_table,
+            wasm_input_helper_table,
             context_helper_table,
+            u256_helper_table,
             foreign_table_from_zero_index,
 
             max_available_rows,
+            layout,
+            params: circuit_params(),
         }
     }
 
@@ -153,17 +219,31 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
         config: Self::Config,
         mut layouter: impl Layouter<F>,
     ) -> Result<(), Error> {
+        assert_eq!(
+            config.params, self.params,
+            "the circuit is configured with other parameters than its own, \
+            run the halo2 call within `with_circuit_params`"
+        );
+
         let assign_timer = start_timer!(|| "Assign");
 
         let rchip = RangeTableChip::new(config.rtable);
         let image_chip = ImageTableChip::new(config.image_table);
+        let memory_tree_chip = MemoryTreeChip::new(config.memory_tree, config.max_available_rows);
         let mchip = MemoryTableChip::new(config.mtable, config.max_available_rows);
         let jchip = JumpTableChip::new(config.jtable, config.max_available_rows);
         let echip = EventTableChip::new(config.etable, config.max_available_rows);
         let bit_chip = BitTableChip::new(config.bit_table, config.max_available_rows);
         let external_host_call_chip =
             ExternalHostCallChip::new(config.external_host_call_table, config.max_available_rows);
-        let context_chip = ContextContHelperTableChip::new(config.context_helper_table);
+        let wasm_input_chip = WasmInputHelperTableChip::new(
+            config.wasm_input_helper_table,
+            config.max_available_rows,
+        );
+        let context_chip =
+            ContextContHelperTableChip::new(config.context_helper_table, config.max_available_rows);
+        let u256_helper_chip =
+            U256HelperTableChip::new(config.u256_helper_table, config.max_available_rows);
 
         layouter.assign_region(
             || "foreign helper",
@@ -183,94 +263,121 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
 
         exec_with_profile!(|| "Init range chip", rchip.init(&mut layouter)?);
 
+        let external_host_call_table = self
+            .tables
+            .execution_tables
+            .etable
+            .filter_external_host_call_table();
+
         exec_with_profile!(
             || "Assign external host call table",
-            external_host_call_chip.assign(
-                &mut layouter,
-                &self
-                    .tables
-                    .execution_tables
-                    .etable
-                    .filter_external_host_call_table(),
-            )?
+            external_host_call_chip.assign(&mut layouter, &external_host_call_table)?
         );
 
-        let (entry_fid, static_frame_entries, initial_memory_pages, maximal_memory_pages) =
-            layouter.assign_region(
-                || "jtable mtable etable",
-                |region| {
-                    let mut ctx = Context::new(region);
-
-                    let memory_writing_table: MemoryWritingTable =
-                        self.tables.execution_tables.mtable.clone().into();
+        exec_with_profile!(
+            || "Assign u256 helper chip",
+            u256_helper_chip.assign(&mut layouter, &external_host_call_table)?
+        );
 
-                    let etable = exec_with_profile!(
-                        || "Prepare memory info for etable",
-                        EventTableWithMemoryInfo::new(
-                            &self.tables.execution_tables.etable,
+        let (
+            entry_fid,
+            static_frame_entries,
+            initial_memory_pages,
+            maximal_memory_pages,
+            input_index,
+            context_input_index,
+            context_output_index,
+        ) = layouter.assign_region(
+            || "jtable mtable etable",
+            |region| {
+                let mut ctx = Context::new(region);
+
+                let memory_writing_table: MemoryWritingTable =
+                    self.tables.execution_tables.mtable.clone().into();
+
+                let etable = exec_with_profile!(
+                    || "Prepare memory info for etable",
+                    EventTableWithMemoryInfo::new(
+                        &self.tables.execution_tables.etable,
+                        &memory_writing_table,
+                    )
+                );
+
+                let etable_permutation_cells = exec_with_profile!(
+                    || "Assign etable",
+                    echip.assign(
+                        &mut ctx,
+                        &etable,
+                        &self.tables.compilation_tables.configure_table,
+                        self.tables.compilation_tables.fid_of_entry,
+                    )?
+                );
+
+                {
+                    ctx.reset();
+                    exec_with_profile!(
+                        || "Assign mtable",
+                        mchip.assign(
+                            &mut ctx,
+                            etable_permutation_cells.rest_mops,
                             &memory_writing_table,
-                        )
+                            &self.tables.compilation_tables.imtable
+                        )?
                     );
+                }
 
-                    let etable_permutation_cells = exec_with_profile!(
-                        || "Assign etable",
-                        echip.assign(
+                let jtable_info = {
+                    ctx.reset();
+                    exec_with_profile!(
+                        || "Assign frame table",
+                        jchip.assign(
                             &mut ctx,
-                            &etable,
-                            &self.tables.compilation_tables.configure_table,
-                            self.tables.compilation_tables.fid_of_entry,
+                            &self.tables.execution_tables.jtable,
+                            etable_permutation_cells.rest_jops,
+                            &self.tables.compilation_tables.static_jtable,
                         )?
-                    );
+                    )
+                };
+
+                {
+                    ctx.reset();
+                    exec_with_profile!(|| "Assign bit table", bit_chip.assign(&mut ctx, &etable)?);
+                }
+
+                Ok((
+                    etable_permutation_cells.fid_of_entry,
+                    jtable_info,
+                    etable_permutation_cells.initial_memory_pages,
+                    etable_permutation_cells.maximal_memory_pages,
+                    etable_permutation_cells.input_index,
+                    etable_permutation_cells.context_input_index,
+                    etable_permutation_cells.context_output_index,
+                ))
+            },
+        )?;
 
-                    {
-                        ctx.reset();
-                        exec_with_profile!(
-                            || "Assign mtable",
-                            mchip.assign(
-                                &mut ctx,
-                                etable_permutation_cells.rest_mops,
-                                &memory_writing_table,
-                                &self.tables.compilation_tables.imtable
-                            )?
-                        );
-                    }
-
-                    let jtable_info = {
-                        ctx.reset();
-                        exec_with_profile!(
-                            || "Assign frame table",
-                            jchip.assign(
-                                &mut ctx,
-                                &self.tables.execution_tables.jtable,
-                                etable_permutation_cells.rest_jops,
-                                &self.tables.compilation_tables.static_jtable,
-                            )?
-                        )
-                    };
-
-                    {
-                        ctx.reset();
-                        exec_with_profile!(
-                            || "Assign bit table",
-                            bit_chip.assign(&mut ctx, &etable)?
-                        );
-                    }
-
-                    Ok((
-                        etable_permutation_cells.fid_of_entry,
-                        jtable_info,
-                        etable_permutation_cells.initial_memory_pages,
-                        etable_permutation_cells.maximal_memory_pages,
-                    ))
-                },
-            )?;
+        exec_with_profile!(
+            || "Assign wasm input chip",
+            wasm_input_chip.assign(&mut layouter, &self.public_inputs_and_outputs, input_index)?
+        );
 
         exec_with_profile!(
             || "Assign context cont chip",
             context_chip.assign(
                 &mut layouter,
                 &self.tables.execution_tables.etable.get_context_inputs(),
-                &self.tables.execution_tables.etable.get_context_outputs()
+                &self.tables.execution_tables.etable.get_context_outputs(),
+                context_input_index,
+                context_output_index,
+            )?
+        );
+
+        let heap_memory_root = exec_with_profile!(
+            || "Assign memory tree",
+            memory_tree_chip.assign(
+                &mut layouter,
+                &HeapMemoryTree::new(self.tables.compilation_tables.imtable.heap_words()),
+                heap_init_offsets(&self.tables.execution_tables.mtable),
             )?
         );
 
@@ -286,7 +393,9 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
                     static_frame_entries,
                     initial_memory_pages,
                     maximal_memory_pages,
-                    lookup_entries: None
+                    heap_memory_root,
+                    lookup_entries: None,
+                    init_memory_entries: None,
                 }
             )?
         );
@@ -296,3 +405,29 @@ impl<F: FieldExt> Circuit<F> for TestCircuit<F> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pairing::bn256::Fr;
+    use halo2_proofs::plonk::Circuit;
+    use halo2_proofs::plonk::ConstraintSystem;
+
+    use super::TestCircuit;
+    use crate::circuits::etable::EVENT_TABLE_ENTRY_ROWS;
+
+    #[test]
+    fn test_etable_columns_fit_cell_usage() {
+        let config = TestCircuit::<Fr>::configure(&mut ConstraintSystem::default());
+        let profiler = config.etable.cell_profiler;
+
+        for utilization in profiler.utilization() {
+            assert!(
+                utilization.cells - utilization.u64_part - utilization.peak
+                    < EVENT_TABLE_ENTRY_ROWS as usize,
+                "unused {:?} column\n{}",
+                utilization.cell_type,
+                profiler
+            );
+        }
+    }
+}
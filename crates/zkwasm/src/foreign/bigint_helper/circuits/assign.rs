@@ -0,0 +1,453 @@
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::circuit::Region;
+use halo2_proofs::plonk::Error;
+use num_bigint::BigInt;
+use num_bigint::BigUint;
+use num_traits::One;
+use num_traits::Zero;
+use specs::external_host_call_table::ExternalHostCallTable;
+
+use crate::foreign::bigint_helper::bn_to_limbs;
+use crate::foreign::bigint_helper::limbs_to_bn;
+use crate::foreign::bigint_helper::runtime::u256_compute;
+use crate::foreign::bigint_helper::runtime::U256Op;
+use crate::foreign::ForeignInst;
+
+use super::*;
+
+pub struct U256HelperTableChip<F: FieldExt> {
+    config: U256HelperTableConfig<F>,
+    max_available_rows: usize,
+}
+
+/// Witness of the block of one `u256_op` call.
+struct U256Block {
+    /// Index of the `u256_op` call.
+    op_idx: usize,
+    op: U256Op,
+    /// The opcode and value of the call before the operands.
+    prev: (usize, u64),
+    operands: Vec<u64>,
+    results: Vec<u64>,
+    /// Number of result limbs read by the guest.
+    read: usize,
+    high: Vec<u64>,
+    diff: Vec<u64>,
+    /// Carries of the relation with `CARRY_OFFSET_BITS` added.
+    carries: Vec<BigUint>,
+    diff_carries: Vec<bool>,
+    divisor_nonzero: bool,
+}
+
+impl U256Block {
+    /// `op_idx` is the index of a `u256_op` call, the call at the index `i` is the entry
+    /// `i - 1` of the table. A call with an unsupported operation or without enough calls
+    /// ahead of it for the operands, which the runtime rejects, fails the synthesis.
+    fn new(table: &ExternalHostCallTable, op_idx: usize) -> Result<Self, Error> {
+        let entries = table.entries();
+        let op = U256Op::try_from(entries[op_idx - 1].value).map_err(|_| Error::Synthesis)?;
+
+        let operand_limbs = op.operands() * U256_LIMBS;
+        let first_operand = (op_idx - 1)
+            .checked_sub(operand_limbs)
+            .ok_or(Error::Synthesis)?;
+        let operands = entries[first_operand..op_idx - 1]
+            .iter()
+            .map(|entry| entry.value)
+            .collect::<Vec<_>>();
+        let prev = match first_operand {
+            0 => (0, 0),
+            prev_idx => (entries[prev_idx - 1].op, entries[prev_idx - 1].value),
+        };
+
+        let values = operands
+            .chunks(U256_LIMBS)
+            .map(limbs_to_bn)
+            .collect::<Vec<_>>();
+        let results = u256_compute(op, &values);
+
+        let read = entries[op_idx..]
+            .iter()
+            .take(results.len() * U256_LIMBS)
+            .take_while(|entry| entry.op == ForeignInst::U256Result as usize)
+            .count();
+
+        let a = &values[0];
+        let b = &values[1];
+        let divisor = match op {
+            U256Op::MulMod => Some(&values[2]),
+            U256Op::DivMod => Some(b),
+            _ => None,
+        };
+        let divisor_nonzero = divisor.map_or(false, |divisor| !divisor.is_zero());
+
+        let high = match op {
+            U256Op::Add => (a + b) >> 256,
+            U256Op::Sub => BigUint::from((a < b) as u64),
+            U256Op::Mul => (a * b) >> 256,
+            U256Op::MulMod if divisor_nonzero => (a * b) / &values[2],
+            _ => BigUint::zero(),
+        };
+
+        // The remainder and its bound.
+        let (diff, diff_carries) = match (op, divisor_nonzero) {
+            (U256Op::MulMod, true) => Self::diff(&results[0], &values[2]),
+            (U256Op::DivMod, true) => Self::diff(&results[1], b),
+            _ => (BigUint::zero(), vec![false; U256_LIMBS - 1]),
+        };
+
+        let mut block = U256Block {
+            op_idx,
+            op,
+            prev,
+            operands,
+            results: results
+                .iter()
+                .flat_map(|result| bn_to_limbs(result, U256_LIMBS))
+                .collect(),
+            read,
+            high: bn_to_limbs(&high, HIGH_LIMBS_NUM),
+            diff: bn_to_limbs(&diff, U256_LIMBS),
+            carries: vec![],
+            diff_carries,
+            divisor_nonzero,
+        };
+        block.carries = block.carries();
+
+        Ok(block)
+    }
+
+    /// `bound - value - 1` and the carries of `value + diff + 1 = bound`.
+    fn diff(value: &BigUint, bound: &BigUint) -> (BigUint, Vec<bool>) {
+        let diff = bound - value - BigUint::one();
+        let value = bn_to_limbs(value, U256_LIMBS);
+        let diff_limbs = bn_to_limbs(&diff, U256_LIMBS);
+
+        let mut carry = 0u128;
+        let carries = (0..U256_LIMBS - 1)
+            .map(|k| {
+                carry = (value[k] as u128 + diff_limbs[k] as u128 + (k == 0) as u128 + carry) >> 64;
+                carry == 1
+            })
+            .collect();
+
+        (diff, carries)
+    }
+
+    fn limbs(values: &[u64]) -> Vec<BigInt> {
+        values.iter().map(|value| BigInt::from(*value)).collect()
+    }
+
+    /// Mirrors the relation gate: the difference of both sides in each column plus the
+    /// incoming carry is a multiple of 2^64.
+    fn carries(&self) -> Vec<BigUint> {
+        let a = Self::limbs(&self.operands[..U256_LIMBS]);
+        let b = Self::limbs(&self.operands[U256_LIMBS..2 * U256_LIMBS]);
+        let m = Self::limbs(&self.operands[2 * U256_LIMBS..]);
+        let r = Self::limbs(&self.results[..U256_LIMBS]);
+        let rem = Self::limbs(&self.results[U256_LIMBS..]);
+        let h = Self::limbs(&self.high);
+        let h_low = &h[..U256_LIMBS];
+
+        let limbs = |x: &[BigInt], shift: usize, k: usize| {
+            (k >= shift)
+                .then(|| x.get(k - shift).cloned())
+                .flatten()
+                .unwrap_or_default()
+        };
+        let product = |x: &[BigInt], y: &[BigInt], k: usize| {
+            (0..x.len())
+                .filter(|i| k >= *i && k - i < y.len())
+                .map(|i| &x[i] * &y[k - i])
+                .sum::<BigInt>()
+        };
+
+        let column = |k: usize| match (self.op, self.divisor_nonzero) {
+            (U256Op::Add, _) => {
+                limbs(&a, 0, k) + limbs(&b, 0, k) - limbs(&r, 0, k) - limbs(h_low, U256_LIMBS, k)
+            }
+            (U256Op::Sub, _) => {
+                limbs(&r, 0, k) + limbs(&b, 0, k) - limbs(&a, 0, k) - limbs(h_low, U256_LIMBS, k)
+            }
+            (U256Op::Mul, _) => product(&a, &b, k) - limbs(&r, 0, k) - limbs(h_low, U256_LIMBS, k),
+            (U256Op::MulMod, true) => product(&a, &b, k) - product(&h, &m, k) - limbs(&r, 0, k),
+            (U256Op::DivMod, true) => product(&r, &b, k) + limbs(&rem, 0, k) - limbs(&a, 0, k),
+            _ => BigInt::zero(),
+        };
+
+        let offset = BigInt::one() << CARRY_OFFSET_BITS;
+        let mut carry = BigInt::zero();
+
+        let carries = (0..COLUMNS - 1)
+            .map(|k| {
+                let sum = column(k) + &carry;
+                assert!((&sum % (BigInt::one() << 64)).is_zero());
+                carry = sum >> 64;
+
+                (&carry + &offset).to_biguint().unwrap()
+            })
+            .collect();
+        assert!((column(COLUMNS - 1) + carry).is_zero());
+
+        carries
+    }
+}
+
+impl<F: FieldExt> U256HelperTableChip<F> {
+    pub fn new(config: U256HelperTableConfig<F>, max_available_rows: usize) -> Self {
+        Self {
+            config,
+            max_available_rows,
+        }
+    }
+
+    fn assign_limb(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        value: u64,
+    ) -> Result<(), Error> {
+        region.assign_advice(
+            || "u256 helper limb",
+            self.config.limb,
+            offset,
+            || Ok(F::from(value)),
+        )?;
+
+        for (i, u16_limb) in self.config.u16_limbs.iter().enumerate() {
+            region.assign_advice(
+                || "u256 helper u16 limb",
+                *u16_limb,
+                offset,
+                || Ok(F::from((value >> (16 * i)) & 0xffff)),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn assign_slot(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        enabled: bool,
+        idx: usize,
+        opcode: usize,
+        value: u64,
+    ) -> Result<(), Error> {
+        region.assign_advice(
+            || "u256 helper enabled",
+            self.config.enabled,
+            offset,
+            || Ok(F::from(enabled as u64)),
+        )?;
+        region.assign_advice(
+            || "u256 helper idx",
+            self.config.idx,
+            offset,
+            || Ok(F::from(idx as u64)),
+        )?;
+        region.assign_advice(
+            || "u256 helper opcode",
+            self.config.opcode,
+            offset,
+            || Ok(F::from(opcode as u64)),
+        )?;
+
+        self.assign_limb(region, offset, value)
+    }
+
+    fn assign_aux(&self, region: &mut Region<'_, F>, offset: usize, value: F) -> Result<(), Error> {
+        region.assign_advice(|| "u256 helper aux", self.config.aux, offset, || Ok(value))?;
+
+        Ok(())
+    }
+
+    fn assign_block(
+        &self,
+        region: &mut Region<'_, F>,
+        base: usize,
+        block: &U256Block,
+    ) -> Result<(), Error> {
+        let operand_limbs = block.operands.len();
+        let first_operand_idx = block.op_idx - operand_limbs;
+
+        self.assign_slot(
+            region,
+            base + PREV_SLOT,
+            true,
+            first_operand_idx - 1,
+            block.prev.0,
+            block.prev.1,
+        )?;
+        self.assign_aux(
+            region,
+            base + PREV_OPCODE_INV,
+            (F::from(block.prev.0 as u64) - F::from(ForeignInst::U256Push as u64))
+                .invert()
+                .unwrap_or(F::zero()),
+        )?;
+
+        for (i, operand) in block.operands.iter().enumerate() {
+            self.assign_slot(
+                region,
+                base + OPERAND_SLOTS + i,
+                true,
+                first_operand_idx + i,
+                ForeignInst::U256Push as usize,
+                *operand,
+            )?;
+        }
+
+        self.assign_slot(
+            region,
+            base + OP_SLOT,
+            true,
+            block.op_idx,
+            ForeignInst::U256Op as usize,
+            block.op as u64,
+        )?;
+
+        for (i, result) in block.results.iter().enumerate() {
+            self.assign_slot(
+                region,
+                base + RESULT_SLOTS + i,
+                i < block.read,
+                block.op_idx + 1 + i,
+                ForeignInst::U256Result as usize,
+                *result,
+            )?;
+        }
+
+        for (i, high) in block.high.iter().enumerate() {
+            self.assign_limb(region, base + HIGH_LIMBS + i, *high)?;
+        }
+
+        for (i, diff) in block.diff.iter().enumerate() {
+            self.assign_limb(region, base + DIFF_LIMBS + i, *diff)?;
+        }
+
+        for (k, carry) in block.carries.iter().enumerate() {
+            let limbs = bn_to_limbs(carry, 2);
+
+            self.assign_limb(region, base + CARRY_LIMBS + 2 * k, limbs[0])?;
+            self.assign_limb(region, base + CARRY_LIMBS + 2 * k + 1, limbs[1])?;
+        }
+
+        for op in U256Op::ALL {
+            self.assign_aux(
+                region,
+                base + OP_FLAGS + op as usize,
+                F::from((op == block.op) as u64),
+            )?;
+        }
+
+        let divisor = match block.op {
+            U256Op::MulMod => &block.operands[2 * U256_LIMBS..],
+            U256Op::DivMod => &block.operands[U256_LIMBS..2 * U256_LIMBS],
+            _ => &[],
+        };
+        let divisor = divisor
+            .iter()
+            .fold(F::zero(), |acc, limb| acc + F::from(*limb));
+
+        self.assign_aux(
+            region,
+            base + DIVISOR_NONZERO,
+            F::from(block.divisor_nonzero as u64),
+        )?;
+        self.assign_aux(
+            region,
+            base + DIVISOR_INV,
+            divisor.invert().unwrap_or(F::zero()),
+        )?;
+        self.assign_aux(
+            region,
+            base + CHECKED_MULMOD,
+            F::from((block.op == U256Op::MulMod && block.divisor_nonzero) as u64),
+        )?;
+        self.assign_aux(
+            region,
+            base + CHECKED_DIVMOD,
+            F::from((block.op == U256Op::DivMod && block.divisor_nonzero) as u64),
+        )?;
+
+        for (k, carry) in block.diff_carries.iter().enumerate() {
+            self.assign_aux(region, base + DIFF_CARRIES + k, F::from(*carry as u64))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        table: &ExternalHostCallTable,
+    ) -> Result<(), Error> {
+        let capacity = u256_capacity(self.max_available_rows);
+
+        let blocks = table
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.op == ForeignInst::U256Op as usize)
+            .map(|(index, _)| U256Block::new(table, index + 1))
+            .collect::<Result<Vec<_>, _>>()?;
+        // `TraceCapacity::check` reports the overflow before the synthesis.
+        if blocks.len() > capacity {
+            return Err(Error::Synthesis);
+        }
+
+        layouter.assign_region(
+            || "u256 helper",
+            |mut region| {
+                for block_index in 0..capacity {
+                    let base = block_index * U256_BLOCK_ROWS;
+
+                    region.assign_fixed(
+                        || "u256 helper block sel",
+                        self.config.block_sel,
+                        base,
+                        || Ok(F::one()),
+                    )?;
+
+                    for offset in 0..U256_BLOCK_ROWS {
+                        region.assign_fixed(
+                            || "u256 helper block row",
+                            self.config.block_row,
+                            base + offset,
+                            || Ok(F::one()),
+                        )?;
+                    }
+
+                    for offset in 0..SLOTS {
+                        region.assign_fixed(
+                            || "u256 helper slot",
+                            self.config.slot,
+                            base + offset,
+                            || Ok(F::one()),
+                        )?;
+                    }
+
+                    for offset in RESULT_SLOTS..SLOTS {
+                        region.assign_fixed(
+                            || "u256 helper result slot",
+                            self.config.result_slot,
+                            base + offset,
+                            || Ok(F::one()),
+                        )?;
+                    }
+                }
+
+                for (block_index, block) in blocks.iter().enumerate() {
+                    self.assign_block(&mut region, block_index * U256_BLOCK_ROWS, block)?;
+                }
+
+                Ok(())
+            },
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,434 @@
+use std::marker::PhantomData;
+
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::plonk::ConstraintSystem;
+use halo2_proofs::plonk::Expression;
+use halo2_proofs::plonk::VirtualCells;
+use num_bigint::BigUint;
+use specs::external_host_call_table::encode::encode_host_call_entry;
+
+use crate::circuits::rtable::RangeTableConfig;
+use crate::circuits::utils::bn_to_field;
+use crate::constant_from;
+use crate::constant_from_bn;
+use crate::curr;
+use crate::fixed_curr;
+use crate::foreign::bigint_helper::runtime::U256Op;
+use crate::foreign::ForeignInst;
+use crate::foreign::ForeignTableConfig;
+use crate::nextn;
+
+use super::*;
+
+impl<F: FieldExt> U256HelperTableConfig<F> {
+    pub fn configure(meta: &mut ConstraintSystem<F>, rtable: &RangeTableConfig<F>) -> Self {
+        let config = U256HelperTableConfig {
+            block_sel: meta.fixed_column(),
+            block_row: meta.fixed_column(),
+            slot: meta.fixed_column(),
+            result_slot: meta.fixed_column(),
+            enabled: meta.advice_column(),
+            idx: meta.advice_column(),
+            opcode: meta.advice_column(),
+            limb: meta.advice_column(),
+            u16_limbs: [(); 4].map(|_| meta.advice_column()),
+            aux: meta.advice_column(),
+            _mark: PhantomData,
+        };
+
+        for u16_limb in config.u16_limbs {
+            rtable
+                .configure_in_u16_range(meta, "u256 helper u16 limb", |meta| curr!(meta, u16_limb));
+        }
+
+        rtable.configure_in_common_range(meta, "u256 helper opcode", |meta| {
+            curr!(meta, config.opcode)
+        });
+
+        meta.create_gate("u256 helper limb", |meta| {
+            let composed = config
+                .u16_limbs
+                .iter()
+                .enumerate()
+                .map(|(i, u16_limb)| curr!(meta, *u16_limb) * constant_from!(1u64 << (16 * i)))
+                .reduce(|acc, x| acc + x)
+                .unwrap();
+
+            vec![fixed_curr!(meta, config.block_row) * (curr!(meta, config.limb) - composed)]
+        });
+
+        config.configure_slots(meta);
+        config.configure_op(meta);
+        config.configure_relation(meta);
+
+        config
+    }
+
+    fn enabled(&self, meta: &mut VirtualCells<'_, F>, offset: usize) -> Expression<F> {
+        nextn!(meta, self.enabled, offset as i32)
+    }
+
+    fn idx(&self, meta: &mut VirtualCells<'_, F>, offset: usize) -> Expression<F> {
+        nextn!(meta, self.idx, offset as i32)
+    }
+
+    fn opcode(&self, meta: &mut VirtualCells<'_, F>, offset: usize) -> Expression<F> {
+        nextn!(meta, self.opcode, offset as i32)
+    }
+
+    fn limb(&self, meta: &mut VirtualCells<'_, F>, offset: usize) -> Expression<F> {
+        nextn!(meta, self.limb, offset as i32)
+    }
+
+    fn aux(&self, meta: &mut VirtualCells<'_, F>, offset: usize) -> Expression<F> {
+        nextn!(meta, self.aux, offset as i32)
+    }
+
+    fn op_flag(&self, meta: &mut VirtualCells<'_, F>, op: U256Op) -> Expression<F> {
+        self.aux(meta, OP_FLAGS + op as usize)
+    }
+
+    fn operand(&self, meta: &mut VirtualCells<'_, F>, operand: usize, i: usize) -> Expression<F> {
+        self.limb(meta, OPERAND_SLOTS + operand * U256_LIMBS + i)
+    }
+
+    fn result(&self, meta: &mut VirtualCells<'_, F>, result: usize, i: usize) -> Expression<F> {
+        self.limb(meta, RESULT_SLOTS + result * U256_LIMBS + i)
+    }
+
+    /// Carry out of the column `k` of the relation.
+    fn carry(&self, meta: &mut VirtualCells<'_, F>, k: usize) -> Expression<F> {
+        self.limb(meta, CARRY_LIMBS + 2 * k)
+            + self.limb(meta, CARRY_LIMBS + 2 * k + 1)
+                * constant_from_bn!(&(BigUint::from(1u64) << 64))
+            - self.enabled(meta, OP_SLOT)
+                * constant_from_bn!(&(BigUint::from(1u64) << CARRY_OFFSET_BITS))
+    }
+
+    /// Binds the slots to consecutive external host calls ending with the result limbs.
+    fn configure_slots(&self, meta: &mut ConstraintSystem<F>) {
+        meta.create_gate("u256 helper slots", |meta| {
+            let en = self.enabled(meta, OP_SLOT);
+            let is_mulmod = self.op_flag(meta, U256Op::MulMod);
+            let is_divmod = self.op_flag(meta, U256Op::DivMod);
+            let op_idx = self.idx(meta, OP_SLOT);
+            let operand_limbs =
+                constant_from!(2 * U256_LIMBS) + is_mulmod.clone() * constant_from!(U256_LIMBS);
+
+            let mut constraints = (0..SLOTS)
+                .map(|offset| {
+                    let enabled = self.enabled(meta, offset);
+
+                    enabled.clone() * (constant_from!(1) - enabled)
+                })
+                .collect::<Vec<_>>();
+
+            // The call before the operands.
+            let enabled = self.enabled(meta, PREV_SLOT);
+            constraints.push(enabled.clone() - en.clone());
+            constraints.push(
+                enabled
+                    * (self.idx(meta, PREV_SLOT) - op_idx.clone()
+                        + operand_limbs.clone()
+                        + constant_from!(1)),
+            );
+            constraints.push(
+                (self.opcode(meta, PREV_SLOT) - constant_from!(ForeignInst::U256Push as u64))
+                    * self.aux(meta, PREV_OPCODE_INV)
+                    - en.clone(),
+            );
+
+            // The operand limbs, m is pushed only for `MulMod`.
+            for i in 0..OPERAND_LIMBS {
+                let enabled = self.enabled(meta, OPERAND_SLOTS + i);
+
+                constraints.push(if i < 2 * U256_LIMBS {
+                    enabled.clone() - en.clone()
+                } else {
+                    enabled.clone() - is_mulmod.clone()
+                });
+                constraints.push(
+                    enabled.clone()
+                        * (self.idx(meta, OPERAND_SLOTS + i) - op_idx.clone()
+                            + operand_limbs.clone()
+                            - constant_from!(i)),
+                );
+                constraints.push(
+                    enabled
+                        * (self.opcode(meta, OPERAND_SLOTS + i)
+                            - constant_from!(ForeignInst::U256Push as u64)),
+                );
+            }
+
+            // The `u256_op` call.
+            constraints.push(
+                en.clone()
+                    * (self.opcode(meta, OP_SLOT) - constant_from!(ForeignInst::U256Op as u64)),
+            );
+
+            // The result limbs read by the guest.
+            for i in 0..RESULT_LIMBS {
+                let enabled = self.enabled(meta, RESULT_SLOTS + i);
+
+                constraints.push(if i < U256_LIMBS {
+                    enabled.clone() * (constant_from!(1) - en.clone())
+                } else {
+                    enabled.clone() * (constant_from!(1) - is_divmod.clone())
+                });
+                constraints.push(
+                    enabled.clone()
+                        * (self.idx(meta, RESULT_SLOTS + i)
+                            - op_idx.clone()
+                            - constant_from!(i + 1)),
+                );
+                constraints.push(
+                    enabled
+                        * (self.opcode(meta, RESULT_SLOTS + i)
+                            - constant_from!(ForeignInst::U256Result as u64)),
+                );
+            }
+
+            constraints
+                .into_iter()
+                .map(|constraint| constraint * fixed_curr!(meta, self.block_sel))
+                .collect::<Vec<_>>()
+        });
+    }
+
+    /// Decodes the operation and detects a zero divisor.
+    fn configure_op(&self, meta: &mut ConstraintSystem<F>) {
+        meta.create_gate("u256 helper op", |meta| {
+            let en = self.enabled(meta, OP_SLOT);
+            let flags = U256Op::ALL.map(|op| self.op_flag(meta, op));
+
+            let mut constraints = flags
+                .iter()
+                .map(|flag| flag.clone() * (constant_from!(1) - flag.clone()))
+                .collect::<Vec<_>>();
+
+            constraints.push(
+                flags
+                    .iter()
+                    .cloned()
+                    .reduce(|acc, flag| acc + flag)
+                    .unwrap()
+                    - en,
+            );
+            constraints.push(
+                flags
+                    .iter()
+                    .enumerate()
+                    .map(|(op, flag)| flag.clone() * constant_from!(op))
+                    .reduce(|acc, x| acc + x)
+                    .unwrap()
+                    - self.limb(meta, OP_SLOT),
+            );
+
+            let is_mulmod = self.op_flag(meta, U256Op::MulMod);
+            let is_divmod = self.op_flag(meta, U256Op::DivMod);
+            let nonzero = self.aux(meta, DIVISOR_NONZERO);
+            let checked_mulmod = self.aux(meta, CHECKED_MULMOD);
+            let checked_divmod = self.aux(meta, CHECKED_DIVMOD);
+
+            // Limbs are u64, their sum is zero only if all of them are.
+            let divisor = (0..U256_LIMBS)
+                .map(|i| {
+                    is_mulmod.clone() * self.operand(meta, 2, i)
+                        + is_divmod.clone() * self.operand(meta, 1, i)
+                })
+                .reduce(|acc, x| acc + x)
+                .unwrap();
+
+            constraints.push(divisor.clone() * self.aux(meta, DIVISOR_INV) - nonzero.clone());
+            constraints.push(divisor * (constant_from!(1) - nonzero.clone()));
+            constraints.push(checked_mulmod.clone() - is_mulmod.clone() * nonzero.clone());
+            constraints.push(checked_divmod.clone() - is_divmod.clone() * nonzero);
+
+            // Division and modulo by zero yield zero.
+            for i in 0..U256_LIMBS {
+                constraints.push(
+                    (is_mulmod.clone() - checked_mulmod.clone() + is_divmod.clone()
+                        - checked_divmod.clone())
+                        * self.result(meta, 0, i),
+                );
+                constraints
+                    .push((is_divmod.clone() - checked_divmod.clone()) * self.result(meta, 1, i));
+            }
+
+            constraints
+                .into_iter()
+                .map(|constraint| constraint * fixed_curr!(meta, self.block_sel))
+                .collect::<Vec<_>>()
+        });
+    }
+
+    fn configure_relation(&self, meta: &mut ConstraintSystem<F>) {
+        meta.create_gate("u256 helper relation", |meta| {
+            let limbs = |meta: &mut VirtualCells<'_, F>, offset: usize, n: usize| {
+                (0..n)
+                    .map(|i| self.limb(meta, offset + i))
+                    .collect::<Vec<_>>()
+            };
+
+            let a = limbs(meta, OPERAND_SLOTS, U256_LIMBS);
+            let b = limbs(meta, OPERAND_SLOTS + U256_LIMBS, U256_LIMBS);
+            let m = limbs(meta, OPERAND_SLOTS + 2 * U256_LIMBS, U256_LIMBS);
+            let r = limbs(meta, RESULT_SLOTS, U256_LIMBS);
+            let rem = limbs(meta, RESULT_SLOTS + U256_LIMBS, U256_LIMBS);
+            let h = limbs(meta, HIGH_LIMBS, HIGH_LIMBS_NUM);
+            let diff = limbs(meta, DIFF_LIMBS, U256_LIMBS);
+
+            let relations = [
+                (
+                    self.op_flag(meta, U256Op::Add),
+                    vec![Term::Limbs(&a, 0), Term::Limbs(&b, 0)],
+                    vec![
+                        Term::Limbs(&r, 0),
+                        Term::Limbs(&h[..U256_LIMBS], U256_LIMBS),
+                    ],
+                ),
+                (
+                    self.op_flag(meta, U256Op::Sub),
+                    vec![Term::Limbs(&r, 0), Term::Limbs(&b, 0)],
+                    vec![
+                        Term::Limbs(&a, 0),
+                        Term::Limbs(&h[..U256_LIMBS], U256_LIMBS),
+                    ],
+                ),
+                (
+                    self.op_flag(meta, U256Op::Mul),
+                    vec![Term::Product(&a, &b)],
+                    vec![
+                        Term::Limbs(&r, 0),
+                        Term::Limbs(&h[..U256_LIMBS], U256_LIMBS),
+                    ],
+                ),
+                (
+                    self.aux(meta, CHECKED_MULMOD),
+                    vec![Term::Product(&a, &b)],
+                    vec![Term::Product(&h, &m), Term::Limbs(&r, 0)],
+                ),
+                (
+                    self.aux(meta, CHECKED_DIVMOD),
+                    vec![Term::Product(&r, &b), Term::Limbs(&rem, 0)],
+                    vec![Term::Limbs(&a, 0)],
+                ),
+            ];
+
+            let mut constraints = (0..COLUMNS)
+                .map(|k| {
+                    let mut column = relations
+                        .iter()
+                        .map(|(flag, lhs, rhs)| {
+                            flag.clone() * (sum_column(lhs, k) - sum_column(rhs, k))
+                        })
+                        .reduce(|acc, x| acc + x)
+                        .unwrap();
+
+                    if k > 0 {
+                        column = column + self.carry(meta, k - 1);
+                    }
+                    if k < COLUMNS - 1 {
+                        column = column
+                            - self.carry(meta, k) * constant_from_bn!(&(BigUint::from(1u64) << 64));
+                    }
+
+                    column * fixed_curr!(meta, self.block_sel)
+                })
+                .collect::<Vec<_>>();
+
+            // r + diff + 1 = m for `MulMod`, rem + diff + 1 = b for `DivMod`.
+            let checked_mulmod = self.aux(meta, CHECKED_MULMOD);
+            let checked_divmod = self.aux(meta, CHECKED_DIVMOD);
+
+            for k in 0..U256_LIMBS {
+                let carry = self.aux(meta, DIFF_CARRIES + k);
+
+                let mut column = checked_mulmod.clone() * (r[k].clone() - m[k].clone())
+                    + checked_divmod.clone() * (rem[k].clone() - b[k].clone())
+                    + (checked_mulmod.clone() + checked_divmod.clone())
+                        * (diff[k].clone() + constant_from!((k == 0) as u64));
+
+                if k > 0 {
+                    column = column + self.aux(meta, DIFF_CARRIES + k - 1);
+                }
+                if k < U256_LIMBS - 1 {
+                    column =
+                        column - carry.clone() * constant_from_bn!(&(BigUint::from(1u64) << 64));
+                    constraints.push(
+                        carry.clone()
+                            * (constant_from!(1) - carry)
+                            * fixed_curr!(meta, self.block_sel),
+                    );
+                }
+
+                constraints.push(column * fixed_curr!(meta, self.block_sel));
+            }
+
+            constraints
+        });
+    }
+
+    /// Looks up the enabled slots in the external host call table by their index.
+    pub(crate) fn external_host_call_lookup(
+        &self,
+        meta: &mut VirtualCells<'_, F>,
+    ) -> Vec<Expression<F>> {
+        let enable = fixed_curr!(meta, self.slot) * curr!(meta, self.enabled);
+
+        vec![
+            enable.clone()
+                * encode_host_call_entry(
+                    curr!(meta, self.idx),
+                    curr!(meta, self.opcode),
+                    curr!(meta, self.limb),
+                ),
+            enable * curr!(meta, self.idx),
+        ]
+    }
+}
+
+/// Limbs of a value starting at a column, or the product of two values.
+enum Term<'a, F: FieldExt> {
+    Limbs(&'a [Expression<F>], usize),
+    Product(&'a [Expression<F>], &'a [Expression<F>]),
+}
+
+/// The column `k` of a sum of terms.
+fn sum_column<F: FieldExt>(terms: &[Term<'_, F>], k: usize) -> Expression<F> {
+    terms
+        .iter()
+        .flat_map(|term| match term {
+            Term::Limbs(x, shift) => (k >= *shift)
+                .then(|| x.get(k - shift).cloned())
+                .flatten()
+                .into_iter()
+                .collect::<Vec<_>>(),
+            Term::Product(x, y) => (0..x.len())
+                .filter(|i| k >= *i && k - i < y.len())
+                .map(|i| x[i].clone() * y[k - i].clone())
+                .collect(),
+        })
+        .reduce(|acc, x| acc + x)
+        .unwrap_or(constant_from!(0))
+}
+
+impl<F: FieldExt> ForeignTableConfig<F> for U256HelperTableConfig<F> {
+    fn configure_in_table(
+        &self,
+        meta: &mut ConstraintSystem<F>,
+        key: &'static str,
+        expr: &dyn Fn(&mut VirtualCells<'_, F>) -> Vec<Expression<F>>,
+    ) {
+        meta.lookup_any(key, |meta| {
+            let mut exprs = expr(meta);
+
+            vec![(
+                exprs.remove(0),
+                fixed_curr!(meta, self.result_slot)
+                    * curr!(meta, self.enabled)
+                    * encode_u256_result(curr!(meta, self.idx), curr!(meta, self.limb)),
+            )]
+        });
+    }
+}
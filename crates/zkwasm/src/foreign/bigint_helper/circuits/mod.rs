@@ -0,0 +1,95 @@
+//! Constrains the results of the u256 host functions.
+//!
+//! Each `u256_op` call owns a block of `U256_BLOCK_ROWS` rows. The leading rows are slots
+//! bound to external host calls by their index: the call before the operands, the limbs of
+//! the operands, the `u256_op` call and the limbs of the result. The other rows hold the
+//! witness of the relation between operands and result, every limb is range checked to u64.
+//!
+//! The relation is checked over 64-bit columns with signed carries:
+//!
+//! | op     | relation                               |
+//! |--------|----------------------------------------|
+//! | Add    | a + b = h * 2^256 + r                  |
+//! | Sub    | r + b = h * 2^256 + a                  |
+//! | Mul    | a * b = h * 2^256 + r                  |
+//! | MulMod | a * b = h * m + r, r < m if m != 0     |
+//! | DivMod | q * b + r = a, r < b if b != 0         |
+//!
+//! The limbs of an operation must be pushed and read without other external host calls in
+//! between. Every `u256_result` call of the etable is looked up in the result slots.
+
+use std::marker::PhantomData;
+
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::plonk::Advice;
+use halo2_proofs::plonk::Column;
+use halo2_proofs::plonk::Fixed;
+use num_bigint::BigUint;
+use specs::encode::FromBn;
+
+pub mod assign;
+pub mod config;
+
+pub const U256_FOREIGN_TABLE_KEY: &'static str = "u256-helper-table";
+
+/// Slot of the external host call preceding the operands, which must not be a `u256_push`.
+const PREV_SLOT: usize = 0;
+/// Slots of the operands a, b and m, 4 limbs each.
+const OPERAND_SLOTS: usize = 1;
+const OP_SLOT: usize = 13;
+/// Slots of the result limbs, the remainder of `DivMod` takes the last 4.
+const RESULT_SLOTS: usize = 14;
+const SLOTS: usize = 22;
+/// 8 limbs of h.
+const HIGH_LIMBS: usize = 22;
+/// 4 limbs of the difference `m - r - 1` or `b - r - 1`.
+const DIFF_LIMBS: usize = 30;
+/// Each carry of the relation takes two limbs.
+const CARRY_LIMBS: usize = 34;
+
+pub(crate) const U256_BLOCK_ROWS: usize = 54;
+
+/// Offsets of the auxiliary column within a block.
+const PREV_OPCODE_INV: usize = 0;
+const OP_FLAGS: usize = 1;
+const DIVISOR_NONZERO: usize = 6;
+const DIVISOR_INV: usize = 7;
+const CHECKED_MULMOD: usize = 8;
+const CHECKED_DIVMOD: usize = 9;
+const DIFF_CARRIES: usize = 10;
+
+const U256_LIMBS: usize = 4;
+const OPERAND_LIMBS: usize = 12;
+const RESULT_LIMBS: usize = 8;
+const HIGH_LIMBS_NUM: usize = 8;
+/// 64-bit columns of the relation, the product of h and m spans 11 of them.
+const COLUMNS: usize = 11;
+/// Carries are stored with this offset to keep them non-negative.
+const CARRY_OFFSET_BITS: usize = 70;
+
+#[derive(Clone)]
+pub struct U256HelperTableConfig<F: FieldExt> {
+    /// Enabled on the first row of each block.
+    block_sel: Column<Fixed>,
+    /// Enabled on every row of the blocks, the limb of these rows is range checked.
+    block_row: Column<Fixed>,
+    slot: Column<Fixed>,
+    result_slot: Column<Fixed>,
+    enabled: Column<Advice>,
+    idx: Column<Advice>,
+    opcode: Column<Advice>,
+    limb: Column<Advice>,
+    u16_limbs: [Column<Advice>; 4],
+    aux: Column<Advice>,
+    _mark: PhantomData<F>,
+}
+
+/// Number of u256 operations the circuit holds.
+pub(crate) fn u256_capacity(max_available_rows: usize) -> usize {
+    max_available_rows / U256_BLOCK_ROWS
+}
+
+/// A `u256_result` call looked up by the etable in the result slots.
+pub(crate) fn encode_u256_result<T: FromBn>(host_call_idx: T, value: T) -> T {
+    host_call_idx * T::from_bn(&(BigUint::from(1u64) << 64)) + value
+}
@@ -0,0 +1,21 @@
+use num_bigint::BigUint;
+use num_traits::Zero;
+
+pub mod circuits;
+pub mod runtime;
+pub mod test;
+
+/// Decodes a little-endian sequence of u64 limbs.
+pub fn limbs_to_bn(limbs: &[u64]) -> BigUint {
+    limbs.iter().rev().fold(BigUint::zero(), |acc, limb| {
+        (acc << 64) + BigUint::from(*limb)
+    })
+}
+
+/// Encodes `bn` into `n` little-endian u64 limbs.
+pub fn bn_to_limbs(bn: &BigUint, n: usize) -> Vec<u64> {
+    let mut limbs = bn.to_u64_digits();
+    assert!(limbs.len() <= n);
+    limbs.resize(n, 0);
+    limbs
+}
@@ -0,0 +1,254 @@
+use std::rc::Rc;
+
+use num_bigint::BigUint;
+use num_traits::One;
+use num_traits::Zero;
+use specs::external_host_call_table::ExternalHostCallSignature;
+
+use crate::foreign::bigint_helper::bn_to_limbs;
+use crate::foreign::bigint_helper::limbs_to_bn;
+use crate::foreign::ForeignInst;
+use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::host::ForeignContext;
+
+/// Limbs of a u256 operand
+const U256_LIMBS: usize = 4;
+
+/// Operations accepted by `u256_op`. All results are reduced modulo 2^256, division
+/// and modulo by zero yield zero as in the EVM.
+///
+/// | op     | operands | result                  |
+/// |--------|----------|-------------------------|
+/// | Add    | a, b     | a + b                   |
+/// | Sub    | a, b     | a - b                   |
+/// | Mul    | a, b     | a * b                   |
+/// | MulMod | a, b, m  | a * b % m               |
+/// | DivMod | a, b     | a / b followed by a % b |
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum U256Op {
+    Add = 0,
+    Sub,
+    Mul,
+    MulMod,
+    DivMod,
+}
+
+impl U256Op {
+    pub const ALL: [U256Op; 5] = [
+        U256Op::Add,
+        U256Op::Sub,
+        U256Op::Mul,
+        U256Op::MulMod,
+        U256Op::DivMod,
+    ];
+
+    pub fn operands(&self) -> usize {
+        match self {
+            U256Op::MulMod => 3,
+            _ => 2,
+        }
+    }
+
+    pub fn results(&self) -> usize {
+        match self {
+            U256Op::DivMod => 2,
+            _ => 1,
+        }
+    }
+}
+
+impl TryFrom<u64> for U256Op {
+    type Error = ();
+
+    fn try_from(value: u64) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(U256Op::Add),
+            1 => Ok(U256Op::Sub),
+            2 => Ok(U256Op::Mul),
+            3 => Ok(U256Op::MulMod),
+            4 => Ok(U256Op::DivMod),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn u256_compute(op: U256Op, operands: &[BigUint]) -> Vec<BigUint> {
+    assert_eq!(operands.len(), op.operands());
+
+    let modulus = BigUint::one() << 256;
+    let a = &operands[0];
+    let b = &operands[1];
+
+    match op {
+        U256Op::Add => vec![(a + b) % &modulus],
+        U256Op::Sub => vec![(a + &modulus - b) % &modulus],
+        U256Op::Mul => vec![(a * b) % &modulus],
+        U256Op::MulMod => {
+            let m = &operands[2];
+
+            if m.is_zero() {
+                vec![BigUint::zero()]
+            } else {
+                vec![(a * b) % m]
+            }
+        }
+        U256Op::DivMod => {
+            if b.is_zero() {
+                vec![BigUint::zero(), BigUint::zero()]
+            } else {
+                vec![a / b, a % b]
+            }
+        }
+    }
+}
+
+/// A guest pushes the limbs of all operands with `u256_push`, triggers the operation with
+/// `u256_op` and reads the limbs of the result with `u256_result`. The circuit requires these
+/// calls to be contiguous among the external host calls, so the sequence is open from the
+/// first push until the last result limb is read.
+struct Context {
+    operand_limbs: Vec<u64>,
+    result_limbs: Vec<u64>,
+    /// Number of operand limbs pushed and result limbs left in the open sequence, tracked
+    /// apart from the limbs since the callbacks are skipped in replay mode.
+    pushed: usize,
+    unread: usize,
+}
+
+impl Context {
+    fn push(&mut self, limb: u64) {
+        assert!(
+            self.result_limbs.is_empty(),
+            "result of the previous u256 operation is not consumed"
+        );
+
+        self.operand_limbs.push(limb);
+    }
+
+    fn apply(&mut self, op: u64) {
+        let op = U256Op::try_from(op).expect("unsupported u256 operation");
+
+        assert_eq!(
+            self.operand_limbs.len(),
+            op.operands() * U256_LIMBS,
+            "operands of {:?} are incomplete",
+            op
+        );
+
+        let operands = self
+            .operand_limbs
+            .chunks(U256_LIMBS)
+            .map(limbs_to_bn)
+            .collect::<Vec<_>>();
+
+        self.result_limbs = u256_compute(op, &operands)
+            .iter()
+            .flat_map(|bn| bn_to_limbs(bn, U256_LIMBS))
+            .rev()
+            .collect();
+        self.operand_limbs.clear();
+    }
+
+    fn pop_result(&mut self) -> u64 {
+        self.result_limbs
+            .pop()
+            .expect("no pending u256 result, please call u256_op first")
+    }
+}
+
+impl ForeignContext for Context {
+    fn sequence_call(&mut self, op: usize, arg: Option<u64>) -> Result<(), String> {
+        if op == ForeignInst::U256Push as usize {
+            if self.unread != 0 {
+                return Err("result of the previous u256 operation is not consumed".to_owned());
+            }
+            if self.pushed == U256Op::MulMod.operands() * U256_LIMBS {
+                return Err("too many u256 operand limbs".to_owned());
+            }
+
+            self.pushed += 1;
+        } else if op == ForeignInst::U256Op as usize {
+            let op = arg
+                .and_then(|op| U256Op::try_from(op).ok())
+                .ok_or_else(|| format!("unsupported u256 operation {:?}", arg))?;
+
+            if self.unread != 0 {
+                return Err("result of the previous u256 operation is not consumed".to_owned());
+            }
+            if self.pushed != op.operands() * U256_LIMBS {
+                return Err(format!("operands of {:?} are incomplete", op));
+            }
+
+            self.pushed = 0;
+            self.unread = op.results() * U256_LIMBS;
+        } else if op == ForeignInst::U256Result as usize {
+            if self.unread == 0 {
+                return Err("no pending u256 result, please call u256_op first".to_owned());
+            }
+
+            self.unread -= 1;
+        }
+
+        Ok(())
+    }
+
+    fn in_sequence(&self) -> bool {
+        self.pushed != 0 || self.unread != 0
+    }
+}
+
+pub fn register_bigint_foreign(env: &mut HostEnv) {
+    let foreign_u256_plugin = env.external_env.register_plugin(
+        "foreign_u256",
+        Box::new(Context {
+            operand_limbs: vec![],
+            result_limbs: vec![],
+            pushed: 0,
+            unread: 0,
+        }),
+    );
+
+    env.external_env.register_function(
+        "u256_push",
+        ForeignInst::U256Push as usize,
+        ExternalHostCallSignature::Argument,
+        foreign_u256_plugin.clone(),
+        Rc::new(
+            |context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+                context.push(args.nth(0));
+
+                None
+            },
+        ),
+    );
+
+    env.external_env.register_function(
+        "u256_op",
+        ForeignInst::U256Op as usize,
+        ExternalHostCallSignature::Argument,
+        foreign_u256_plugin.clone(),
+        Rc::new(
+            |context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+                context.apply(args.nth(0));
+
+                None
+            },
+        ),
+    );
+
+    env.external_env.register_function(
+        "u256_result",
+        ForeignInst::U256Result as usize,
+        ExternalHostCallSignature::Return,
+        foreign_u256_plugin,
+        Rc::new(
+            |context: &mut dyn ForeignContext, _args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+
+                Some(wasmi::RuntimeValue::I64(context.pop_result() as i64))
+            },
+        ),
+    );
+}
@@ -0,0 +1,369 @@
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use num_bigint::BigUint;
+    use num_traits::One;
+    use specs::external_host_call_table::ExternalHostCallSignature;
+
+    use crate::foreign::bigint_helper::bn_to_limbs;
+    use crate::foreign::bigint_helper::limbs_to_bn;
+    use crate::foreign::bigint_helper::runtime::u256_compute;
+    use crate::foreign::bigint_helper::runtime::U256Op;
+    use crate::foreign::require_helper::register_require_foreign;
+    use crate::foreign::wasm_input_helper::runtime::register_wasm_input_foreign;
+    use crate::foreign::ForeignInst;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::host::ForeignContext;
+    use crate::test::test_circuit_with_env;
+
+    #[test]
+    fn test_u256_wrapping() {
+        let max = (BigUint::one() << 256) - BigUint::one();
+
+        assert_eq!(
+            u256_compute(U256Op::Add, &[max.clone(), BigUint::one()]),
+            vec![BigUint::from(0u64)]
+        );
+        assert_eq!(
+            u256_compute(U256Op::Sub, &[BigUint::from(0u64), BigUint::one()]),
+            vec![max.clone()]
+        );
+        assert_eq!(
+            u256_compute(U256Op::Mul, &[max.clone(), max.clone()]),
+            vec![BigUint::one()]
+        );
+        assert_eq!(
+            u256_compute(
+                U256Op::MulMod,
+                &[max.clone(), max.clone(), BigUint::from(7u64)]
+            ),
+            vec![(&max * &max) % BigUint::from(7u64)]
+        );
+        assert_eq!(
+            u256_compute(U256Op::DivMod, &[max.clone(), BigUint::from(0u64)]),
+            vec![BigUint::from(0u64), BigUint::from(0u64)]
+        );
+    }
+
+    #[test]
+    fn test_foreign_u256_divmod() {
+        // Pushes two operands from the private inputs, then checks quotient and remainder
+        // against the public inputs.
+        let textual_repr = r#"
+                (module
+                    (import "env" "wasm_input" (func $wasm_input (param i32) (result i64)))
+                    (import "env" "require" (func $require (param i32)))
+                    (import "env" "u256_push" (func $u256_push (param i64)))
+                    (import "env" "u256_op" (func $u256_op (param i64)))
+                    (import "env" "u256_result" (func $u256_result (result i64)))
+                    (export "main" (func $main))
+                    (func $main (local i32)
+                        (loop
+                            (call $u256_push (call $wasm_input (i32.const 0)))
+                            (local.set 0 (i32.add (local.get 0) (i32.const 1)))
+                            (br_if 0 (i32.lt_u (local.get 0) (i32.const 8)))
+                        )
+                        (call $u256_op (i64.const 4))
+                        (local.set 0 (i32.const 0))
+                        (loop
+                            (call $require
+                                (i64.eq (call $u256_result) (call $wasm_input (i32.const 1)))
+                            )
+                            (local.set 0 (i32.add (local.get 0) (i32.const 1)))
+                            (br_if 0 (i32.lt_u (local.get 0) (i32.const 8)))
+                        )
+                    )
+                )
+            "#;
+
+        let a = (BigUint::one() << 200) + BigUint::from(0x1234_5678u64);
+        let b = (BigUint::one() << 70) + BigUint::from(3u64);
+
+        let private_inputs = [bn_to_limbs(&a, 4), bn_to_limbs(&b, 4)].concat();
+        let public_inputs = [bn_to_limbs(&(&a / &b), 4), bn_to_limbs(&(&a % &b), 4)].concat();
+
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+        let (env, wasm_runtime_io) = HostEnv::new_with_full_foreign_plugins(
+            public_inputs,
+            private_inputs,
+            vec![],
+            Arc::new(Mutex::new(vec![])),
+        );
+
+        test_circuit_with_env(env, wasm_runtime_io, wasm, "main").unwrap();
+    }
+
+    /// Runs the operations in order: for each of them, the guest reads the number of operand
+    /// limbs, the limbs, the op and the number of result limbs from the private inputs, then
+    /// requires each result limb read to equal the next public input.
+    const U256_OPS: &str = r#"
+            (module
+                (import "env" "wasm_input" (func $wasm_input (param i32) (result i64)))
+                (import "env" "require" (func $require (param i32)))
+                (import "env" "u256_push" (func $u256_push (param i64)))
+                (import "env" "u256_op" (func $u256_op (param i64)))
+                (import "env" "u256_result" (func $u256_result (result i64)))
+                (export "main" (func $main))
+                (func $main (local $ops i64) (local $n i64)
+                    (local.set $ops (call $wasm_input (i32.const 1)))
+                    (block $done
+                        (loop $next_op
+                            (br_if $done (i64.eqz (local.get $ops)))
+                            (local.set $n (call $wasm_input (i32.const 0)))
+                            (block $pushed
+                                (loop $push
+                                    (br_if $pushed (i64.eqz (local.get $n)))
+                                    (call $u256_push (call $wasm_input (i32.const 0)))
+                                    (local.set $n (i64.sub (local.get $n) (i64.const 1)))
+                                    (br $push)
+                                )
+                            )
+                            (call $u256_op (call $wasm_input (i32.const 0)))
+                            (local.set $n (call $wasm_input (i32.const 0)))
+                            (block $read
+                                (loop $result
+                                    (br_if $read (i64.eqz (local.get $n)))
+                                    (call $require
+                                        (i64.eq (call $u256_result) (call $wasm_input (i32.const 1)))
+                                    )
+                                    (local.set $n (i64.sub (local.get $n) (i64.const 1)))
+                                    (br $result)
+                                )
+                            )
+                            (local.set $ops (i64.sub (local.get $ops) (i64.const 1)))
+                            (br $next_op)
+                        )
+                    )
+                )
+            )
+        "#;
+
+    /// Public and private inputs of `U256_OPS`, each operation reads the leading `read` limbs
+    /// of `results`.
+    fn u256_ops_inputs(ops: &[(U256Op, Vec<BigUint>, usize, Vec<u64>)]) -> (Vec<u64>, Vec<u64>) {
+        let mut public_inputs = vec![ops.len() as u64];
+        let mut private_inputs = vec![];
+
+        for (op, operands, read, results) in ops {
+            private_inputs.push((operands.len() * 4) as u64);
+            private_inputs.extend(operands.iter().flat_map(|operand| bn_to_limbs(operand, 4)));
+            private_inputs.push(*op as u64);
+            private_inputs.push(*read as u64);
+            public_inputs.extend(&results[..*read]);
+        }
+
+        (public_inputs, private_inputs)
+    }
+
+    fn u256_results(op: U256Op, operands: &[BigUint]) -> Vec<u64> {
+        u256_compute(op, operands)
+            .iter()
+            .flat_map(|result| bn_to_limbs(result, 4))
+            .collect()
+    }
+
+    fn u256_ops() -> Vec<(U256Op, Vec<BigUint>, usize, Vec<u64>)> {
+        let max = (BigUint::one() << 256) - BigUint::one();
+        let a = (BigUint::one() << 200) + BigUint::from(0x1234_5678u64);
+        let b = (BigUint::one() << 70) + BigUint::from(3u64);
+        let zero = BigUint::from(0u64);
+
+        vec![
+            (U256Op::Add, vec![max.clone(), BigUint::one()]),
+            (U256Op::Sub, vec![zero.clone(), BigUint::one()]),
+            (U256Op::Mul, vec![max.clone(), a.clone()]),
+            (U256Op::MulMod, vec![max.clone(), max.clone(), b.clone()]),
+            (U256Op::MulMod, vec![a.clone(), b.clone(), zero.clone()]),
+            (U256Op::DivMod, vec![zero.clone(), b.clone()]),
+            (U256Op::DivMod, vec![a.clone(), zero.clone()]),
+            // The last result may be read partially.
+            (U256Op::DivMod, vec![a.clone(), b.clone()]),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(index, (op, operands))| {
+            let results = u256_results(op, &operands);
+            let read = if index == 7 { 5 } else { results.len() };
+
+            (op, operands, read, results)
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_foreign_u256_ops() {
+        let (public_inputs, private_inputs) = u256_ops_inputs(&u256_ops());
+        let wasm = wabt::wat2wasm(U256_OPS).expect("failed to parse wat");
+
+        let (env, wasm_runtime_io) = HostEnv::new_with_full_foreign_plugins(
+            public_inputs,
+            private_inputs,
+            vec![],
+            Arc::new(Mutex::new(vec![])),
+        );
+
+        test_circuit_with_env(env, wasm_runtime_io, wasm, "main").unwrap();
+    }
+
+    #[test]
+    fn test_foreign_u256_out_of_sequence_call_is_rejected() {
+        let run = |body: &str| {
+            let textual_repr = format!(
+                r#"
+                (module
+                    (import "env" "log" (func $log (param i64)))
+                    (import "env" "u256_push" (func $u256_push (param i64)))
+                    (import "env" "u256_op" (func $u256_op (param i64)))
+                    (import "env" "u256_result" (func $u256_result (result i64)))
+                    (export "main" (func $main))
+                    (func $main
+                        {}
+                    )
+                )
+            "#,
+                body
+            );
+            let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+            let (env, wasm_runtime_io) = HostEnv::new_with_full_foreign_plugins(
+                vec![],
+                vec![],
+                vec![],
+                Arc::new(Mutex::new(vec![])),
+            );
+
+            test_circuit_with_env(env, wasm_runtime_io, wasm, "main")
+        };
+        let push = |n: usize| "(call $u256_push (i64.const 1))".repeat(n);
+
+        assert!(run(&format!(
+            "{} (call $u256_op (i64.const 0)) (drop (call $u256_result)) {}",
+            push(8),
+            "(drop (call $u256_result))".repeat(3)
+        ))
+        .is_ok());
+
+        for body in [
+            // Another plugin interleaves with the operands.
+            format!("{} (call $log (i64.const 0)) {}", push(4), push(4)),
+            // Another plugin interleaves with the results.
+            format!(
+                "{} (call $u256_op (i64.const 0)) (drop (call $u256_result)) (call $log (i64.const 0))",
+                push(8)
+            ),
+            format!("{} (call $u256_op (i64.const 0))", push(7)),
+            format!("{} (call $u256_op (i64.const 5))", push(8)),
+            "(drop (call $u256_result))".to_owned(),
+        ] {
+            let err = run(&body).unwrap_err();
+            // Either rendering of `HostCallSequenceError` names the sequence.
+            assert!(
+                format!("{:?}", err).to_lowercase().contains("sequence"),
+                "{}",
+                body
+            );
+        }
+    }
+
+    /// A host returning the u256 results with the lowest limb incremented.
+    struct TamperedU256Context {
+        operand_limbs: Vec<u64>,
+        result_limbs: Vec<u64>,
+    }
+
+    impl ForeignContext for TamperedU256Context {}
+
+    fn register_tampered_u256_foreign(env: &mut HostEnv) {
+        let plugin = env.external_env.register_plugin(
+            "foreign_u256",
+            Box::new(TamperedU256Context {
+                operand_limbs: vec![],
+                result_limbs: vec![],
+            }),
+        );
+
+        env.external_env.register_function(
+            "u256_push",
+            ForeignInst::U256Push as usize,
+            ExternalHostCallSignature::Argument,
+            plugin.clone(),
+            Rc::new(
+                |context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+                    let context = context.downcast_mut::<TamperedU256Context>().unwrap();
+                    context.operand_limbs.push(args.nth(0));
+
+                    None
+                },
+            ),
+        );
+
+        env.external_env.register_function(
+            "u256_op",
+            ForeignInst::U256Op as usize,
+            ExternalHostCallSignature::Argument,
+            plugin.clone(),
+            Rc::new(
+                |context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+                    let context = context.downcast_mut::<TamperedU256Context>().unwrap();
+                    let op = U256Op::try_from(args.nth::<u64>(0)).unwrap();
+                    let operands = context
+                        .operand_limbs
+                        .chunks(4)
+                        .map(limbs_to_bn)
+                        .collect::<Vec<_>>();
+
+                    let mut results = u256_results(op, &operands);
+                    results[0] = results[0].wrapping_add(1);
+                    results.reverse();
+
+                    context.result_limbs = results;
+                    context.operand_limbs.clear();
+
+                    None
+                },
+            ),
+        );
+
+        env.external_env.register_function(
+            "u256_result",
+            ForeignInst::U256Result as usize,
+            ExternalHostCallSignature::Return,
+            plugin,
+            Rc::new(
+                |context: &mut dyn ForeignContext, _args: wasmi::RuntimeArgs| {
+                    let context = context.downcast_mut::<TamperedU256Context>().unwrap();
+
+                    Some(wasmi::RuntimeValue::I64(
+                        context.result_limbs.pop().unwrap() as i64,
+                    ))
+                },
+            ),
+        );
+    }
+
+    #[test]
+    fn test_foreign_u256_tampered_result_is_rejected() {
+        let a = (BigUint::one() << 200) + BigUint::from(0x1234_5678u64);
+        let b = (BigUint::one() << 70) + BigUint::from(3u64);
+
+        // The guest expects the tampered limbs, so only the circuit can tell them apart.
+        let mut results = u256_results(U256Op::Add, &[a.clone(), b.clone()]);
+        results[0] = results[0].wrapping_add(1);
+        let (public_inputs, private_inputs) =
+            u256_ops_inputs(&[(U256Op::Add, vec![a, b], 4, results)]);
+
+        let wasm = wabt::wat2wasm(U256_OPS).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        let wasm_runtime_io = register_wasm_input_foreign(&mut env, public_inputs, private_inputs);
+        register_require_foreign(&mut env);
+        register_tampered_u256_foreign(&mut env);
+        env.finalize();
+
+        assert!(test_circuit_with_env(env, wasm_runtime_io, wasm, "main").is_err());
+    }
+}
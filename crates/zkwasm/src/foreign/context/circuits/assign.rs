@@ -1,54 +1,200 @@
 use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Cell;
 use halo2_proofs::circuit::Layouter;
+use halo2_proofs::circuit::Region;
 use halo2_proofs::plonk::Error;
 use specs::etable::EventTable;
 use specs::host_function::HostPlugin;
 use specs::step::StepInfo;
 
+use crate::circuits::poseidon::poseidon_rows;
+use crate::circuits::poseidon::PoseidonChip;
 use crate::foreign::context::Op;
 
+use super::context_capacity;
+use super::context_digest_blocks;
+use super::pack_words;
 use super::ContextContHelperTableConfig;
+use super::CONTEXT_WORDS_PER_ELEMENT;
 
 pub struct ContextContHelperTableChip<F: FieldExt> {
     pub(crate) config: ContextContHelperTableConfig<F>,
+    max_available_rows: usize,
+}
+
+/// Elements absorbed by a context digest: the count cell of the etable followed by the packed
+/// values.
+fn digest_inputs<F: FieldExt>(
+    values: &[u64],
+    count_cell: Cell,
+    packed_cells: &[(F, Cell)],
+) -> Vec<(F, Option<Cell>)> {
+    let mut inputs = vec![(F::from(values.len() as u64 + 1), Some(count_cell))];
+    inputs.extend(
+        packed_cells
+            .iter()
+            .map(|(packed, cell)| (*packed, Some(*cell))),
+    );
+
+    inputs
+}
+
+/// Number of digest elements absorbing `len` values and their count.
+fn active_elements(len: usize) -> usize {
+    1 + (len + CONTEXT_WORDS_PER_ELEMENT - 1) / CONTEXT_WORDS_PER_ELEMENT
 }
 
 impl<F: FieldExt> ContextContHelperTableChip<F> {
-    pub fn new(config: ContextContHelperTableConfig<F>) -> Self {
-        Self { config }
+    pub fn new(config: ContextContHelperTableConfig<F>, max_available_rows: usize) -> Self {
+        Self {
+            config,
+            max_available_rows,
+        }
+    }
+
+    /// Copies the words of `value_cells` from `offset` and packs them, returns the packed cells.
+    fn assign_words(
+        &self,
+        region: &mut Region<'_, F>,
+        offset: usize,
+        values: &[u64],
+        value_cells: &[Cell],
+    ) -> Result<Vec<(F, Cell)>, Error> {
+        let mut packed_cells = vec![];
+
+        for (index, value_cell) in value_cells.iter().enumerate() {
+            let row = offset + index;
+            let value = values.get(index).cloned().unwrap_or(0);
+
+            region.assign_fixed(
+                || "context cont word sel",
+                self.config.word_sel,
+                row,
+                || Ok(F::one()),
+            )?;
+            let word = region.assign_advice(
+                || "context cont word",
+                self.config.word,
+                row,
+                || Ok(F::from(value)),
+            )?;
+            region.constrain_equal(word.cell(), *value_cell)?;
+
+            for (i, u16_limb) in self.config.u16_limbs.iter().enumerate() {
+                region.assign_advice(
+                    || "context cont word u16 limb",
+                    *u16_limb,
+                    row,
+                    || Ok(F::from((value >> (16 * i)) & 0xffff)),
+                )?;
+            }
+
+            if index % CONTEXT_WORDS_PER_ELEMENT == 0 {
+                let words = (index..index + CONTEXT_WORDS_PER_ELEMENT)
+                    .map(|index| values.get(index).cloned().unwrap_or(0))
+                    .collect::<Vec<_>>();
+                let packed = pack_words::<F>(&words);
+
+                region.assign_fixed(
+                    || "context cont pack sel",
+                    self.config.pack_sel,
+                    row,
+                    || Ok(F::one()),
+                )?;
+                let packed_cell = region.assign_advice(
+                    || "context cont packed",
+                    self.config.packed,
+                    row,
+                    || Ok(packed),
+                )?;
+                packed_cells.push((packed, packed_cell.cell()));
+            }
+        }
+
+        Ok(packed_cells)
     }
 
+    /// `input_index` and `output_index` are the etable cells holding the number of context
+    /// reads and writes plus one, which are absorbed before the values.
     pub fn assign(
         &self,
         layouter: &mut impl Layouter<F>,
         inputs: &Vec<u64>,
         outputs: &Vec<u64>,
+        input_index: Cell,
+        output_index: Cell,
     ) -> Result<(), Error> {
-        layouter.assign_region(
+        let capacity = context_capacity(self.max_available_rows);
+        let blocks = context_digest_blocks(self.max_available_rows);
+        assert!(inputs.len() <= capacity && outputs.len() <= capacity);
+
+        let (input_cells, output_cells) = layouter.assign_region(
             || "context cont helper assign",
             |mut region| {
-                for (offset, input) in inputs.iter().enumerate() {
-                    region.assign_advice(
+                let mut input_cells = vec![];
+                let mut output_cells = vec![];
+
+                // Rows are assigned up to the capacity to keep the copy constraints in place.
+                for offset in 0..capacity {
+                    let input = region.assign_advice(
                         || "context cont input index",
                         self.config.input,
                         offset + 1, // The first fixed index should be 1.
-                        || Ok(F::from(*input)),
+                        || Ok(F::from(inputs.get(offset).cloned().unwrap_or(0))),
                     )?;
-                }
+                    input_cells.push(input.cell());
 
-                for (offset, output) in outputs.iter().enumerate() {
-                    region.assign_advice(
+                    let output = region.assign_advice(
                         || "context cont output index",
                         self.config.output,
                         offset + 1, // The first fixed index should be 1.
-                        || Ok(F::from(*output)),
+                        || Ok(F::from(outputs.get(offset).cloned().unwrap_or(0))),
                     )?;
+                    output_cells.push(output.cell());
                 }
 
-                Ok(())
+                Ok((input_cells, output_cells))
+            },
+        )?;
+
+        let (packed_inputs, packed_outputs) = layouter.assign_region(
+            || "context cont pack",
+            |mut region| {
+                let packed_inputs = self.assign_words(&mut region, 0, inputs, &input_cells)?;
+                let packed_outputs =
+                    self.assign_words(&mut region, capacity, outputs, &output_cells)?;
+
+                Ok((packed_inputs, packed_outputs))
             },
         )?;
 
+        let poseidon = PoseidonChip::new(self.config.poseidon.clone(), blocks);
+
+        let digests = layouter.assign_region(
+            || "context digest",
+            |mut region| {
+                let input_digest = poseidon.assign(
+                    &mut region,
+                    0,
+                    &digest_inputs(inputs, input_index, &packed_inputs),
+                    active_elements(inputs.len()),
+                )?;
+
+                let output_digest = poseidon.assign(
+                    &mut region,
+                    poseidon_rows(blocks),
+                    &digest_inputs(outputs, output_index, &packed_outputs),
+                    active_elements(outputs.len()),
+                )?;
+
+                Ok([input_digest.cell(), output_digest.cell()])
+            },
+        )?;
+
+        for (row, digest) in digests.into_iter().enumerate() {
+            layouter.constrain_instance(digest, self.config.digest, row)?;
+        }
+
         Ok(())
     }
 }
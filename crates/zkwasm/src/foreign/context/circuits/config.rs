@@ -6,24 +6,87 @@ use halo2_proofs::plonk::ConstraintSystem;
 use halo2_proofs::plonk::Expression;
 use halo2_proofs::plonk::Fixed;
 use halo2_proofs::plonk::VirtualCells;
+use num_bigint::BigUint;
 
+use crate::circuits::poseidon::PoseidonConfig;
+use crate::circuits::rtable::RangeTableConfig;
+use crate::circuits::utils::bn_to_field;
+use crate::constant_from;
+use crate::constant_from_bn;
 use crate::curr;
 use crate::fixed_curr;
 use crate::foreign::ForeignTableConfig;
+use crate::nextn;
 
 use super::ContextContHelperTableConfig;
+use super::CONTEXT_WORDS_PER_ELEMENT;
 
 impl<F: FieldExt> ContextContHelperTableConfig<F> {
-    pub fn configure(meta: &mut ConstraintSystem<F>, from_zero_index: Column<Fixed>) -> Self {
+    pub fn configure(
+        meta: &mut ConstraintSystem<F>,
+        from_zero_index: Column<Fixed>,
+        rtable: &RangeTableConfig<F>,
+    ) -> Self {
         let input = meta.advice_column();
         let output = meta.advice_column();
         meta.enable_equality(input);
         meta.enable_equality(output);
 
+        let pack_sel = meta.fixed_column();
+        let word_sel = meta.fixed_column();
+        let word = meta.advice_column();
+        let u16_limbs = [(); 4].map(|_| meta.advice_column());
+        let packed = meta.advice_column();
+        meta.enable_equality(word);
+        meta.enable_equality(packed);
+
+        for u16_limb in u16_limbs {
+            rtable.configure_in_u16_range(meta, "context cont word u16 limb", |meta| {
+                curr!(meta, u16_limb)
+            });
+        }
+
+        // Words are u64 so that packing them is injective, including the unread words of the
+        // last element.
+        meta.create_gate("context cont word", |meta| {
+            let composed = u16_limbs
+                .iter()
+                .enumerate()
+                .map(|(i, u16_limb)| curr!(meta, *u16_limb) * constant_from!(1u64 << (16 * i)))
+                .reduce(|acc, x| acc + x)
+                .unwrap();
+
+            vec![fixed_curr!(meta, word_sel) * (curr!(meta, word) - composed)]
+        });
+
+        meta.create_gate("context cont pack", |meta| {
+            let composed = (0..CONTEXT_WORDS_PER_ELEMENT)
+                .map(|i| {
+                    nextn!(meta, word, i as i32)
+                        * constant_from_bn!(&(BigUint::from(1u64) << (64 * i)))
+                })
+                .reduce(|acc, x| acc + x)
+                .unwrap();
+
+            vec![fixed_curr!(meta, pack_sel) * (curr!(meta, packed) - composed)]
+        });
+
+        let digest = meta.instance_column();
+        meta.enable_equality(digest);
+
+        let poseidon = PoseidonConfig::configure(meta);
+
         ContextContHelperTableConfig {
             from_zero_index,
             input,
             output,
+            pack_sel,
+            word_sel,
+            word,
+            u16_limbs,
+            packed,
+            digest,
+            poseidon,
             _mark: PhantomData,
         }
     }
@@ -4,6 +4,14 @@ use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::plonk::Advice;
 use halo2_proofs::plonk::Column;
 use halo2_proofs::plonk::Fixed;
+use halo2_proofs::plonk::Instance;
+use num_bigint::BigUint;
+
+use crate::circuits::poseidon::poseidon_capacity;
+use crate::circuits::poseidon::poseidon_hash;
+use crate::circuits::poseidon::PoseidonConfig;
+use crate::circuits::poseidon::POSEIDON_BLOCK_ROWS;
+use crate::circuits::utils::bn_to_field;
 
 pub mod assign;
 pub mod config;
@@ -15,5 +23,63 @@ pub struct ContextContHelperTableConfig<F: FieldExt> {
     from_zero_index: Column<Fixed>,
     input: Column<Advice>,
     output: Column<Advice>,
+    /// Packing of the context values into digest elements, see `pack_words`.
+    pack_sel: Column<Fixed>,
+    word_sel: Column<Fixed>,
+    word: Column<Advice>,
+    u16_limbs: [Column<Advice>; 4],
+    packed: Column<Advice>,
+    /// Digests of the context inputs and outputs.
+    digest: Column<Instance>,
+    poseidon: PoseidonConfig<F>,
     _mark: PhantomData<F>,
 }
+
+/// Number of blocks of each digest, both digests fit in the available rows.
+pub(crate) fn context_digest_blocks(max_available_rows: usize) -> usize {
+    (max_available_rows / 2 - 2) / POSEIDON_BLOCK_ROWS
+}
+
+/// Number of context values packed into one digest element.
+pub(crate) const CONTEXT_WORDS_PER_ELEMENT: usize = 3;
+
+/// Number of context inputs or outputs the circuit holds, a digest absorbs their count and
+/// elements packing `CONTEXT_WORDS_PER_ELEMENT` values.
+///
+/// An execution reading or writing more values is rejected with a `TraceCapacityError` for
+/// `CapacityTable::Context`. The limit grows linearly with the rows: about 43k values at K = 18.
+pub(crate) fn context_capacity(max_available_rows: usize) -> usize {
+    (poseidon_capacity(context_digest_blocks(max_available_rows)) - 1) * CONTEXT_WORDS_PER_ELEMENT
+}
+
+/// `words[0] + words[1] * 2^64 + words[2] * 2^128`, injective on u64 words.
+pub(crate) fn pack_words<F: FieldExt>(words: &[u64]) -> F {
+    let shift = bn_to_field::<F>(&(BigUint::from(1u64) << 64));
+
+    words
+        .iter()
+        .rev()
+        .fold(F::zero(), |acc, word| acc * shift + F::from(*word))
+}
+
+/// Digest of a sequence of context values, the first absorbed element is its length plus one
+/// and each following one packs `CONTEXT_WORDS_PER_ELEMENT` values.
+pub fn context_digest<F: FieldExt>(values: &[u64]) -> F {
+    let mut elements = vec![F::from(values.len() as u64 + 1)];
+    elements.extend(
+        values
+            .chunks(CONTEXT_WORDS_PER_ELEMENT)
+            .map(pack_words::<F>),
+    );
+
+    poseidon_hash(&elements)
+}
+
+/// Size of the context digest column.
+pub const CONTEXT_INSTANCE_SIZE: usize = 2;
+
+/// Instances of the context digest column: the digests of the inputs read and of the outputs
+/// written by an execution.
+pub fn context_instances<F: FieldExt>(inputs: &[u64], outputs: &[u64]) -> Vec<F> {
+    vec![context_digest(inputs), context_digest(outputs)]
+}
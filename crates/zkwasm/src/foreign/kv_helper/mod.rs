@@ -0,0 +1,3 @@
+pub mod runtime;
+pub mod test;
+pub mod tree;
@@ -0,0 +1,95 @@
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use specs::external_host_call_table::ExternalHostCallSignature;
+
+use crate::foreign::ForeignInst;
+use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::host::ForeignContext;
+
+use super::tree::KvMerkleTree;
+
+/// Host functions of the plugin. The storage is not constrained by the circuit, its roots
+/// are not part of the public instances.
+pub const KV_FUNCTIONS: [&str; 3] = ["kv_address", "kv_set", "kv_get"];
+
+/// `kv_address(key)` selects the key accessed by the following `kv_get()` and `kv_set(value)`.
+struct Context {
+    address: Option<u64>,
+    storage: Arc<Mutex<KvMerkleTree>>,
+}
+
+impl Context {
+    fn address(&self) -> u64 {
+        self.address
+            .expect("kv address is not set, please call kv_address first")
+    }
+
+    fn get(&self) -> u64 {
+        self.storage.lock().unwrap().get(self.address())
+    }
+
+    fn set(&mut self, value: u64) {
+        self.storage.lock().unwrap().set(self.address(), value)
+    }
+}
+
+impl ForeignContext for Context {}
+
+/// The values read by `kv_get()` are not constrained by the circuit, so the plugin is not
+/// registered by `HostEnv::new_with_full_foreign_plugins` and images importing it are rejected
+/// by the loader.
+pub fn register_kv_foreign(env: &mut HostEnv, storage: Arc<Mutex<KvMerkleTree>>) {
+    let foreign_kv_plugin = env.external_env.register_persistent_plugin(
+        "foreign_kv",
+        Box::new(Context {
+            address: None,
+            storage,
+        }),
+    );
+
+    env.external_env.register_function(
+        KV_FUNCTIONS[0],
+        ForeignInst::KvAddress as usize,
+        ExternalHostCallSignature::Argument,
+        foreign_kv_plugin.clone(),
+        Rc::new(
+            |context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+                context.address = Some(args.nth(0));
+
+                None
+            },
+        ),
+    );
+
+    env.external_env.register_function(
+        KV_FUNCTIONS[1],
+        ForeignInst::KvSet as usize,
+        ExternalHostCallSignature::Argument,
+        foreign_kv_plugin.clone(),
+        Rc::new(
+            |context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+                context.set(args.nth(0));
+
+                None
+            },
+        ),
+    );
+
+    env.external_env.register_function(
+        KV_FUNCTIONS[2],
+        ForeignInst::KvGet as usize,
+        ExternalHostCallSignature::Return,
+        foreign_kv_plugin,
+        Rc::new(
+            |context: &mut dyn ForeignContext, _args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+
+                Some(wasmi::RuntimeValue::I64(context.get() as i64))
+            },
+        ),
+    );
+}
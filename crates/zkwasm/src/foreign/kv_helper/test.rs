@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use halo2_proofs::pairing::bn256::Bn256;
+
+    use crate::circuits::config::zkwasm_k;
+    use crate::foreign::kv_helper::runtime::register_kv_foreign;
+    use crate::foreign::kv_helper::tree::KvMerkleTree;
+    use crate::foreign::require_helper::register_require_foreign;
+    use crate::loader::ZkWasmLoader;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::test_circuit_with_env;
+
+    #[test]
+    fn test_foreign_kv() {
+        let textual_repr = r#"
+                (module
+                    (import "env" "require" (func $require (param i32)))
+                    (import "env" "kv_address" (func $kv_address (param i64)))
+                    (import "env" "kv_set" (func $kv_set (param i64)))
+                    (import "env" "kv_get" (func $kv_get (result i64)))
+                    (export "main" (func $main))
+                    (func $main
+                        (call $kv_address (i64.const 1))
+                        (call $require (i64.eq (call $kv_get) (i64.const 10)))
+                        (call $kv_set (i64.const 11))
+                        (call $kv_address (i64.const 2))
+                        (call $kv_set (i64.const 20))
+                        (call $require (i64.eq (call $kv_get) (i64.const 20)))
+                    )
+                )
+            "#;
+
+        let mut tree = KvMerkleTree::new();
+        tree.set(1, 10);
+
+        let kv_storage = Arc::new(Mutex::new(tree));
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        register_require_foreign(&mut env);
+        register_kv_foreign(&mut env, kv_storage.clone());
+        env.finalize();
+
+        test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "main").unwrap();
+
+        let mut expected = KvMerkleTree::new();
+        expected.set(1, 11);
+        expected.set(2, 20);
+
+        assert_eq!(kv_storage.lock().unwrap().root(), expected.root());
+    }
+    #[test]
+    fn test_foreign_kv_is_not_resolved_by_loader() {
+        let textual_repr = r#"
+                (module
+                    (import "env" "kv_get" (func $kv_get (result i64)))
+                    (func (export "zkmain")
+                        (drop (call $kv_get))
+                    )
+                )
+            "#;
+
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+        assert!(ZkWasmLoader::<Bn256>::new(zkwasm_k(), wasm, vec![]).is_err());
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use sha2::Digest;
+use sha2::Sha256;
+
+/// Every u64 key addresses a leaf, so the tree has 64 levels above the leaves.
+pub const KV_TREE_DEPTH: usize = 64;
+
+pub type Hash = [u8; 32];
+
+lazy_static! {
+    /// `EMPTY_HASHES[level]` is the root of an empty subtree of height `level`.
+    static ref EMPTY_HASHES: Vec<Hash> = {
+        let mut hashes = vec![[0; 32]];
+
+        for level in 0..KV_TREE_DEPTH {
+            hashes.push(hash_node(&hashes[level], &hashes[level]));
+        }
+
+        hashes
+    };
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// A zero value denotes an absent key, so its leaf is the empty hash.
+pub fn hash_leaf(key: u64, value: u64) -> Hash {
+    if value == 0 {
+        EMPTY_HASHES[0]
+    } else {
+        let mut hasher = Sha256::new();
+        hasher.update(key.to_le_bytes());
+        hasher.update(value.to_le_bytes());
+        hasher.finalize().into()
+    }
+}
+
+/// Sibling hashes from the leaf up to the root.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MerkleProof {
+    pub key: u64,
+    pub value: u64,
+    pub siblings: Vec<Hash>,
+}
+
+impl MerkleProof {
+    pub fn compute_root(&self) -> Hash {
+        self.siblings.iter().enumerate().fold(
+            hash_leaf(self.key, self.value),
+            |node, (level, sibling)| {
+                if (self.key >> level) & 1 == 0 {
+                    hash_node(&node, sibling)
+                } else {
+                    hash_node(sibling, &node)
+                }
+            },
+        )
+    }
+
+    pub fn verify(&self, root: &Hash) -> bool {
+        self.siblings.len() == KV_TREE_DEPTH && &self.compute_root() == root
+    }
+}
+
+/// Sparse Merkle tree over u64 keys and u64 values. Only non-empty nodes are stored.
+#[derive(Clone, Debug, Default)]
+pub struct KvMerkleTree {
+    leaves: BTreeMap<u64, u64>,
+    // (level, index within the level) -> hash
+    nodes: HashMap<(usize, u64), Hash>,
+}
+
+impl KvMerkleTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_leaves(leaves: BTreeMap<u64, u64>) -> Self {
+        let mut tree = Self::new();
+
+        for (key, value) in leaves {
+            tree.set(key, value);
+        }
+
+        tree
+    }
+
+    /// Non-empty leaves, which is all that is needed to rebuild the tree.
+    pub fn leaves(&self) -> &BTreeMap<u64, u64> {
+        &self.leaves
+    }
+
+    fn node(&self, level: usize, index: u64) -> Hash {
+        self.nodes
+            .get(&(level, index))
+            .cloned()
+            .unwrap_or(EMPTY_HASHES[level])
+    }
+
+    pub fn root(&self) -> Hash {
+        self.node(KV_TREE_DEPTH, 0)
+    }
+
+    pub fn get(&self, key: u64) -> u64 {
+        self.leaves.get(&key).cloned().unwrap_or(0)
+    }
+
+    pub fn set(&mut self, key: u64, value: u64) {
+        if value == 0 {
+            self.leaves.remove(&key);
+        } else {
+            self.leaves.insert(key, value);
+        }
+
+        let mut node = hash_leaf(key, value);
+        let mut index = key;
+
+        for level in 0..=KV_TREE_DEPTH {
+            if node == EMPTY_HASHES[level] {
+                self.nodes.remove(&(level, index));
+            } else {
+                self.nodes.insert((level, index), node);
+            }
+
+            if level == KV_TREE_DEPTH {
+                break;
+            }
+
+            let sibling = self.node(level, index ^ 1);
+            node = if index & 1 == 0 {
+                hash_node(&node, &sibling)
+            } else {
+                hash_node(&sibling, &node)
+            };
+            index >>= 1;
+        }
+    }
+
+    pub fn prove(&self, key: u64) -> MerkleProof {
+        let siblings = (0..KV_TREE_DEPTH)
+            .map(|level| self.node(level, (key >> level) ^ 1))
+            .collect();
+
+        MerkleProof {
+            key,
+            value: self.get(key),
+            siblings,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::KvMerkleTree;
+    use super::KV_TREE_DEPTH;
+
+    #[test]
+    fn test_kv_tree_set_and_reset() {
+        let empty_root = KvMerkleTree::new().root();
+        let mut tree = KvMerkleTree::new();
+
+        tree.set(1, 10);
+        tree.set(u64::MAX, 20);
+        assert_eq!(tree.get(1), 10);
+        assert_ne!(tree.root(), empty_root);
+
+        tree.set(1, 0);
+        tree.set(u64::MAX, 0);
+        assert_eq!(tree.root(), empty_root);
+        assert!(tree.leaves().is_empty());
+    }
+
+    #[test]
+    fn test_kv_tree_insertion_order() {
+        let mut lhs = KvMerkleTree::new();
+        lhs.set(3, 1);
+        lhs.set(4, 2);
+        lhs.set(1 << 40, 3);
+
+        let rhs = KvMerkleTree::from_leaves(lhs.leaves().clone());
+
+        assert_eq!(lhs.root(), rhs.root());
+    }
+
+    #[test]
+    fn test_kv_tree_proof() {
+        let mut tree = KvMerkleTree::new();
+        tree.set(3, 1);
+        tree.set(4, 2);
+
+        for key in [3, 4, 5] {
+            let proof = tree.prove(key);
+
+            assert_eq!(proof.siblings.len(), KV_TREE_DEPTH);
+            assert!(proof.verify(&tree.root()));
+        }
+
+        let mut proof = tree.prove(3);
+        proof.value = 2;
+        assert!(!proof.verify(&tree.root()));
+    }
+}
@@ -2,6 +2,7 @@ use std::rc::Rc;
 
 use specs::external_host_call_table::ExternalHostCallSignature;
 
+use crate::foreign::ForeignInst;
 use crate::runtime::host::host_env::HostEnv;
 use crate::runtime::host::ForeignContext;
 
@@ -23,11 +24,29 @@ pub fn register_log_foreign(env: &mut HostEnv) {
         },
     );
 
+    let print_char = Rc::new(
+        |_context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+            let value: u64 = args.nth(0);
+
+            print!("{}", value as u8 as char);
+
+            None
+        },
+    );
+
     env.external_env.register_function(
         "log",
-        0,
+        ForeignInst::Log as usize,
         ExternalHostCallSignature::Argument,
-        foreign_log_plugin,
+        foreign_log_plugin.clone(),
         print,
     );
+
+    env.external_env.register_function(
+        "log_char",
+        ForeignInst::LogChar as usize,
+        ExternalHostCallSignature::Argument,
+        foreign_log_plugin,
+        print_char,
+    );
 }
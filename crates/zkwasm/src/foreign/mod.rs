@@ -14,17 +14,34 @@ use halo2_proofs::plonk::ConstraintSystem;
 use halo2_proofs::plonk::Expression;
 use halo2_proofs::plonk::VirtualCells;
 
+use self::bigint_helper::runtime::register_bigint_foreign;
 use self::context::runtime::register_context_foreign;
 use self::log_helper::register_log_foreign;
 use self::require_helper::register_require_foreign;
 use self::wasm_input_helper::runtime::register_wasm_input_foreign;
 
+pub mod bigint_helper;
 pub mod context;
 pub mod keccak_helper;
+pub mod kv_helper;
 pub mod log_helper;
 pub mod require_helper;
+pub mod wasi_helper;
 pub mod wasm_input_helper;
 
+/// Op indices of external host functions, recorded in the external host call table.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ForeignInst {
+    Log = 0,
+    U256Push,
+    U256Op,
+    U256Result,
+    KvAddress,
+    KvSet,
+    KvGet,
+    LogChar,
+}
+
 pub fn foreign_table_enable_lines() -> usize {
     1 << (zkwasm_k() - 1)
 }
@@ -63,6 +80,7 @@ impl HostEnv {
         let wasm_runtime_io = register_wasm_input_foreign(&mut env, public_inputs, private_inputs);
         register_require_foreign(&mut env);
         register_log_foreign(&mut env);
+        register_bigint_foreign(&mut env);
         register_context_foreign(&mut env, context_input, context_output);
         env.finalize();
 
@@ -0,0 +1,9 @@
+//! A deterministic subset of WASI preview1.
+//!
+//! WASI functions write their results into the guest memory, which host functions cannot do
+//! without escaping the trace. Instead, each imported WASI function is replaced by a wasm
+//! function built on top of `wasm_input` and `log_char`, so that every memory access stays in
+//! the execution table.
+
+pub mod shim;
+pub mod test;
@@ -0,0 +1,560 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use parity_wasm::elements::BlockType;
+use parity_wasm::elements::External;
+use parity_wasm::elements::Func;
+use parity_wasm::elements::FuncBody;
+use parity_wasm::elements::FunctionType;
+use parity_wasm::elements::GlobalEntry;
+use parity_wasm::elements::GlobalSection;
+use parity_wasm::elements::GlobalType;
+use parity_wasm::elements::ImportEntry;
+use parity_wasm::elements::IndexMap;
+use parity_wasm::elements::InitExpr;
+use parity_wasm::elements::Instruction;
+use parity_wasm::elements::Instructions;
+use parity_wasm::elements::Internal;
+use parity_wasm::elements::Local;
+use parity_wasm::elements::Module;
+use parity_wasm::elements::Section;
+use parity_wasm::elements::Type;
+use parity_wasm::elements::ValueType;
+
+pub const WASI_MODULE: &str = "wasi_snapshot_preview1";
+
+const ERRNO_SUCCESS: i32 = 0;
+const PRIVATE_INPUT: i32 = 0;
+
+#[derive(Debug)]
+pub enum WasiShimErr {
+    UnsupportedFunction(String),
+    SignatureMismatch(String),
+    MemoryNotExists,
+}
+
+impl Display for WasiShimErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Supported WASI functions.
+///
+/// * `fd_write` sends every byte to `log_char` regardless of the file descriptor.
+/// * `proc_exit` traps, a successful execution returns from `zkmain` instead.
+/// * `args_*` and `environ_*` read the count, the buffer size and then the bytes of the
+///   NUL-terminated strings from private inputs, one input per byte.
+/// * `clock_time_get` reads the timestamp from a private input.
+/// * `random_get` reads each byte from a private input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum WasiFunction {
+    FdWrite,
+    ProcExit,
+    ArgsSizesGet,
+    ArgsGet,
+    EnvironSizesGet,
+    EnvironGet,
+    ClockTimeGet,
+    RandomGet,
+}
+
+impl WasiFunction {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "fd_write" => Some(WasiFunction::FdWrite),
+            "proc_exit" => Some(WasiFunction::ProcExit),
+            "args_sizes_get" => Some(WasiFunction::ArgsSizesGet),
+            "args_get" => Some(WasiFunction::ArgsGet),
+            "environ_sizes_get" => Some(WasiFunction::EnvironSizesGet),
+            "environ_get" => Some(WasiFunction::EnvironGet),
+            "clock_time_get" => Some(WasiFunction::ClockTimeGet),
+            "random_get" => Some(WasiFunction::RandomGet),
+            _ => None,
+        }
+    }
+
+    fn signature(&self) -> FunctionType {
+        use ValueType::*;
+
+        match self {
+            WasiFunction::FdWrite => FunctionType::new(vec![I32; 4], vec![I32]),
+            WasiFunction::ProcExit => FunctionType::new(vec![I32], vec![]),
+            WasiFunction::ArgsSizesGet
+            | WasiFunction::ArgsGet
+            | WasiFunction::EnvironSizesGet
+            | WasiFunction::EnvironGet
+            | WasiFunction::RandomGet => FunctionType::new(vec![I32; 2], vec![I32]),
+            WasiFunction::ClockTimeGet => FunctionType::new(vec![I32, I64, I32], vec![I32]),
+        }
+    }
+}
+
+/// Indices of the functions and globals the shim bodies depend on.
+struct ShimContext {
+    wasm_input: u32,
+    log_char: u32,
+    args_buf_size: u32,
+    environ_buf_size: u32,
+}
+
+/// `for (counter = 0; counter < bound; counter++) { body }`
+fn for_loop(counter: u32, bound: Vec<Instruction>, body: Vec<Instruction>) -> Vec<Instruction> {
+    use Instruction::*;
+
+    [
+        vec![I32Const(0), SetLocal(counter), Block(BlockType::NoResult)],
+        vec![Loop(BlockType::NoResult), GetLocal(counter)],
+        bound,
+        vec![I32GeU, BrIf(1)],
+        body,
+        vec![GetLocal(counter), I32Const(1), I32Add, SetLocal(counter)],
+        vec![Br(0), End, End],
+    ]
+    .concat()
+}
+
+impl ShimContext {
+    fn read_private_i32(&self) -> Vec<Instruction> {
+        use Instruction::*;
+
+        vec![I32Const(PRIVATE_INPUT), Call(self.wasm_input), I32WrapI64]
+    }
+
+    /// Returns the extra locals and the body.
+    fn body(&self, function: WasiFunction) -> (Vec<Local>, Vec<Instruction>) {
+        use Instruction::*;
+
+        match function {
+            WasiFunction::FdWrite => {
+                // params: fd, iovs, iovs_len, nwritten
+                let (iovs, iovs_len, nwritten) = (1, 2, 3);
+                let (i, iov, j, total) = (4, 5, 6, 7);
+
+                let write_iov = for_loop(
+                    j,
+                    vec![GetLocal(iov), I32Load(2, 4)],
+                    vec![
+                        GetLocal(iov),
+                        I32Load(2, 0),
+                        GetLocal(j),
+                        I32Add,
+                        I32Load8U(0, 0),
+                        I64ExtendUI32,
+                        Call(self.log_char),
+                    ],
+                );
+
+                let body = for_loop(
+                    i,
+                    vec![GetLocal(iovs_len)],
+                    [
+                        vec![GetLocal(iovs), GetLocal(i), I32Const(8), I32Mul, I32Add],
+                        vec![SetLocal(iov)],
+                        write_iov,
+                        vec![GetLocal(total), GetLocal(iov), I32Load(2, 4), I32Add],
+                        vec![SetLocal(total)],
+                    ]
+                    .concat(),
+                );
+
+                (
+                    vec![Local::new(4, ValueType::I32)],
+                    [
+                        body,
+                        vec![GetLocal(nwritten), GetLocal(total), I32Store(2, 0)],
+                        vec![I32Const(ERRNO_SUCCESS), End],
+                    ]
+                    .concat(),
+                )
+            }
+            WasiFunction::ProcExit => (vec![], vec![Unreachable, End]),
+            WasiFunction::ArgsSizesGet | WasiFunction::EnvironSizesGet => {
+                // params: count, buf_size
+                let buf_size_global = if function == WasiFunction::ArgsSizesGet {
+                    self.args_buf_size
+                } else {
+                    self.environ_buf_size
+                };
+
+                (
+                    vec![],
+                    [
+                        vec![GetLocal(0)],
+                        self.read_private_i32(),
+                        vec![I32Store(2, 0)],
+                        self.read_private_i32(),
+                        vec![SetGlobal(buf_size_global)],
+                        vec![GetLocal(1), GetGlobal(buf_size_global), I32Store(2, 0)],
+                        vec![I32Const(ERRNO_SUCCESS), End],
+                    ]
+                    .concat(),
+                )
+            }
+            WasiFunction::ArgsGet | WasiFunction::EnvironGet => {
+                // params: pointers, buf
+                let buf_size_global = if function == WasiFunction::ArgsGet {
+                    self.args_buf_size
+                } else {
+                    self.environ_buf_size
+                };
+                let (pointers, buf) = (0, 1);
+                let (j, k, start) = (2, 3, 4);
+
+                let read_buf = for_loop(
+                    j,
+                    vec![GetGlobal(buf_size_global)],
+                    [
+                        vec![GetLocal(buf), GetLocal(j), I32Add],
+                        self.read_private_i32(),
+                        vec![I32Store8(0, 0)],
+                    ]
+                    .concat(),
+                );
+
+                // Every NUL byte terminates the string starting at `start`.
+                let collect_pointers = for_loop(
+                    j,
+                    vec![GetGlobal(buf_size_global)],
+                    vec![
+                        GetLocal(buf),
+                        GetLocal(j),
+                        I32Add,
+                        I32Load8U(0, 0),
+                        I32Eqz,
+                        If(BlockType::NoResult),
+                        GetLocal(pointers),
+                        GetLocal(k),
+                        I32Const(4),
+                        I32Mul,
+                        I32Add,
+                        GetLocal(start),
+                        I32Store(2, 0),
+                        GetLocal(k),
+                        I32Const(1),
+                        I32Add,
+                        SetLocal(k),
+                        GetLocal(buf),
+                        GetLocal(j),
+                        I32Add,
+                        I32Const(1),
+                        I32Add,
+                        SetLocal(start),
+                        End,
+                    ],
+                );
+
+                (
+                    vec![Local::new(3, ValueType::I32)],
+                    [
+                        read_buf,
+                        vec![GetLocal(buf), SetLocal(start)],
+                        collect_pointers,
+                        vec![I32Const(ERRNO_SUCCESS), End],
+                    ]
+                    .concat(),
+                )
+            }
+            WasiFunction::ClockTimeGet => (
+                // params: id, precision, time
+                vec![],
+                vec![
+                    GetLocal(2),
+                    I32Const(PRIVATE_INPUT),
+                    Call(self.wasm_input),
+                    I64Store(3, 0),
+                    I32Const(ERRNO_SUCCESS),
+                    End,
+                ],
+            ),
+            WasiFunction::RandomGet => {
+                // params: buf, len
+                let j = 2;
+
+                (
+                    vec![Local::new(1, ValueType::I32)],
+                    [
+                        for_loop(
+                            j,
+                            vec![GetLocal(1)],
+                            [
+                                vec![GetLocal(0), GetLocal(j), I32Add],
+                                self.read_private_i32(),
+                                vec![I32Store8(0, 0)],
+                            ]
+                            .concat(),
+                        ),
+                        vec![I32Const(ERRNO_SUCCESS), End],
+                    ]
+                    .concat(),
+                )
+            }
+        }
+    }
+}
+
+fn find_or_push_type(module: &mut Module, signature: FunctionType) -> u32 {
+    // Type section exists since the module imports WASI functions.
+    let types = module.type_section_mut().unwrap().types_mut();
+
+    match types.iter().position(|Type::Function(ty)| ty == &signature) {
+        Some(index) => index as u32,
+        None => {
+            types.push(Type::Function(signature));
+            types.len() as u32 - 1
+        }
+    }
+}
+
+fn function_type(module: &Module, type_ref: u32) -> Option<&FunctionType> {
+    module
+        .type_section()
+        .and_then(|section| section.types().get(type_ref as usize))
+        .map(|Type::Function(ty)| ty)
+}
+
+fn find_or_push_import(
+    imports: &mut Vec<ImportEntry>,
+    module: &mut Module,
+    field: &str,
+    signature: FunctionType,
+) -> Result<()> {
+    for entry in imports.iter() {
+        if let External::Function(type_ref) = entry.external() {
+            if entry.module() == "env" && entry.field() == field {
+                if function_type(module, *type_ref) != Some(&signature) {
+                    return Err(anyhow!(WasiShimErr::SignatureMismatch(field.to_owned())));
+                }
+
+                return Ok(());
+            }
+        }
+    }
+
+    let type_ref = find_or_push_type(module, signature);
+    imports.push(ImportEntry::new(
+        "env".to_owned(),
+        field.to_owned(),
+        External::Function(type_ref),
+    ));
+
+    Ok(())
+}
+
+/// Replaces the functions imported from `wasi_snapshot_preview1` with wasm implementations.
+/// An image without WASI imports is returned as is.
+pub fn link_wasi_shim(image: &[u8]) -> Result<Vec<u8>> {
+    let module: Module = parity_wasm::deserialize_buffer(image)?;
+
+    let has_wasi_imports = module.import_section().map_or(false, |section| {
+        section
+            .entries()
+            .iter()
+            .any(|entry| entry.module() == WASI_MODULE)
+    });
+
+    if !has_wasi_imports {
+        return Ok(image.to_vec());
+    }
+
+    let module = module.parse_names().unwrap_or_else(|(_, module)| module);
+
+    Ok(parity_wasm::serialize(link_module(module)?)?)
+}
+
+fn link_module(mut module: Module) -> Result<Module> {
+    if module.memory_space() == 0 {
+        return Err(anyhow!(WasiShimErr::MemoryNotExists));
+    }
+
+    let old_imports = module.import_section().unwrap().entries().to_vec();
+
+    // Split the imported functions into the kept ones and the WASI ones, by old index.
+    let mut imports = vec![];
+    let mut kept_functions = vec![];
+    let mut wasi_functions = vec![];
+    let mut function_index = 0;
+
+    for entry in old_imports {
+        let is_wasi = entry.module() == WASI_MODULE;
+
+        match entry.external() {
+            External::Function(type_ref) if is_wasi => {
+                let function = WasiFunction::from_name(entry.field()).ok_or_else(|| {
+                    anyhow!(WasiShimErr::UnsupportedFunction(entry.field().to_owned()))
+                })?;
+
+                if function_type(&module, *type_ref) != Some(&function.signature()) {
+                    return Err(anyhow!(WasiShimErr::SignatureMismatch(
+                        entry.field().to_owned()
+                    )));
+                }
+
+                wasi_functions.push((function_index, function, *type_ref));
+                function_index += 1;
+            }
+            _ if is_wasi => {
+                return Err(anyhow!(WasiShimErr::UnsupportedFunction(
+                    entry.field().to_owned()
+                )))
+            }
+            External::Function(_) => {
+                kept_functions.push(function_index);
+                function_index += 1;
+                imports.push(entry);
+            }
+            _ => imports.push(entry),
+        }
+    }
+
+    let old_imported_functions = function_index;
+
+    find_or_push_import(
+        &mut imports,
+        &mut module,
+        "wasm_input",
+        FunctionType::new(vec![ValueType::I32], vec![ValueType::I64]),
+    )?;
+    find_or_push_import(
+        &mut imports,
+        &mut module,
+        "log_char",
+        FunctionType::new(vec![ValueType::I64], vec![]),
+    )?;
+
+    let lookup_import = |field: &str| {
+        imports
+            .iter()
+            .filter(|entry| matches!(entry.external(), External::Function(_)))
+            .position(|entry| entry.module() == "env" && entry.field() == field)
+            .unwrap() as u32
+    };
+    let wasm_input = lookup_import("wasm_input");
+    let log_char = lookup_import("log_char");
+
+    let new_imported_functions = imports
+        .iter()
+        .filter(|entry| matches!(entry.external(), External::Function(_)))
+        .count() as u32;
+    let defined_functions = module
+        .function_section()
+        .map_or(0, |section| section.entries().len() as u32);
+
+    let mut mapping = HashMap::new();
+    for (new_index, old_index) in kept_functions.iter().enumerate() {
+        mapping.insert(*old_index, new_index as u32);
+    }
+    for defined in 0..defined_functions {
+        mapping.insert(
+            old_imported_functions + defined,
+            new_imported_functions + defined,
+        );
+    }
+    for (shim, (old_index, _, _)) in wasi_functions.iter().enumerate() {
+        mapping.insert(
+            *old_index,
+            new_imported_functions + defined_functions + shim as u32,
+        );
+    }
+
+    *module.import_section_mut().unwrap().entries_mut() = imports;
+
+    // Buffer sizes recorded by `*_sizes_get` for the following `*_get`.
+    let (args_buf_size, environ_buf_size) = {
+        if module.global_section().is_none() {
+            module.insert_section(Section::Global(GlobalSection::default()))?;
+        }
+
+        let base = module.globals_space() as u32;
+        let globals = module.global_section_mut().unwrap().entries_mut();
+        for _ in 0..2 {
+            globals.push(GlobalEntry::new(
+                GlobalType::new(ValueType::I32, true),
+                InitExpr::new(vec![Instruction::I32Const(0), Instruction::End]),
+            ));
+        }
+
+        (base, base + 1)
+    };
+
+    // Rewrite every reference to a function index.
+    for section in module.sections_mut() {
+        match section {
+            Section::Code(section) => {
+                for body in section.bodies_mut() {
+                    for instruction in body.code_mut().elements_mut() {
+                        if let Instruction::Call(index) = instruction {
+                            *index = mapping[&*index];
+                        }
+                    }
+                }
+            }
+            Section::Element(section) => {
+                for segment in section.entries_mut() {
+                    for member in segment.members_mut() {
+                        *member = mapping[&*member];
+                    }
+                }
+            }
+            Section::Export(section) => {
+                for entry in section.entries_mut() {
+                    if let Internal::Function(index) = entry.internal_mut() {
+                        *index = mapping[&*index];
+                    }
+                }
+            }
+            Section::Start(index) => *index = mapping[&*index],
+            Section::Name(section) => {
+                if let Some(functions) = section.functions_mut() {
+                    let names = functions.names_mut();
+                    let mut remapped = IndexMap::default();
+                    for (index, name) in names.iter() {
+                        remapped.insert(mapping[&index], name.clone());
+                    }
+                    *names = remapped;
+                }
+
+                if let Some(locals) = section.locals_mut() {
+                    let names = locals.local_names_mut();
+                    let mut remapped = IndexMap::default();
+                    for (index, local_names) in names.iter() {
+                        remapped.insert(mapping[&index], local_names.clone());
+                    }
+                    *names = remapped;
+                }
+            }
+            _ => (),
+        }
+    }
+
+    let context = ShimContext {
+        wasm_input,
+        log_char,
+        args_buf_size,
+        environ_buf_size,
+    };
+
+    if module.function_section().is_none() {
+        module.insert_section(Section::Function(Default::default()))?;
+        module.insert_section(Section::Code(Default::default()))?;
+    }
+
+    for (_, function, type_ref) in wasi_functions {
+        let (locals, body) = context.body(function);
+
+        module
+            .function_section_mut()
+            .unwrap()
+            .entries_mut()
+            .push(Func::new(type_ref));
+        module
+            .code_section_mut()
+            .unwrap()
+            .bodies_mut()
+            .push(FuncBody::new(locals, Instructions::new(body)));
+    }
+
+    Ok(module)
+}
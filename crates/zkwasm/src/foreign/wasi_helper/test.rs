@@ -0,0 +1,92 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use crate::foreign::wasi_helper::shim::link_wasi_shim;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::test::test_circuit_with_env;
+
+    fn run_wasi(textual_repr: &str, private_inputs: Vec<u64>) {
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+        let wasm = link_wasi_shim(&wasm).unwrap();
+
+        let (env, wasm_runtime_io) = HostEnv::new_with_full_foreign_plugins(
+            vec![],
+            private_inputs,
+            vec![],
+            Arc::new(Mutex::new(vec![])),
+        );
+
+        test_circuit_with_env(env, wasm_runtime_io, wasm, "zkmain").unwrap();
+    }
+
+    #[test]
+    fn test_wasi_fd_write() {
+        let textual_repr = r#"
+                (module
+                    (import "wasi_snapshot_preview1" "fd_write"
+                        (func $fd_write (param i32 i32 i32 i32) (result i32)))
+                    (import "env" "require" (func $require (param i32)))
+                    (memory 1)
+                    (data (i32.const 16) "zkwasm\n")
+                    (func (export "zkmain")
+                        (i32.store (i32.const 0) (i32.const 16))
+                        (i32.store (i32.const 4) (i32.const 7))
+                        (call $require
+                            (i32.eqz
+                                (call $fd_write (i32.const 1) (i32.const 0) (i32.const 1) (i32.const 8))
+                            )
+                        )
+                        (call $require (i32.eq (i32.load (i32.const 8)) (i32.const 7)))
+                    )
+                )
+            "#;
+
+        run_wasi(textual_repr, vec![]);
+    }
+
+    #[test]
+    fn test_wasi_args_and_clock() {
+        let textual_repr = r#"
+                (module
+                    (import "wasi_snapshot_preview1" "args_sizes_get"
+                        (func $args_sizes_get (param i32 i32) (result i32)))
+                    (import "wasi_snapshot_preview1" "args_get"
+                        (func $args_get (param i32 i32) (result i32)))
+                    (import "wasi_snapshot_preview1" "clock_time_get"
+                        (func $clock_time_get (param i32 i64 i32) (result i32)))
+                    (import "env" "require" (func $require (param i32)))
+                    (memory 1)
+                    (func (export "zkmain")
+                        (drop (call $args_sizes_get (i32.const 0) (i32.const 4)))
+                        (call $require (i32.eq (i32.load (i32.const 0)) (i32.const 2)))
+                        (call $require (i32.eq (i32.load (i32.const 4)) (i32.const 4)))
+                        (drop (call $args_get (i32.const 16) (i32.const 32)))
+                        (call $require (i32.eq (i32.load (i32.const 20)) (i32.const 34)))
+                        (call $require (i32.eq (i32.load8_u (i32.const 34)) (i32.const 98)))
+                        (drop (call $clock_time_get (i32.const 0) (i64.const 1) (i32.const 64)))
+                        (call $require (i64.eq (i64.load (i32.const 64)) (i64.const 1000)))
+                    )
+                )
+            "#;
+
+        // argc, argv buffer size, "a\0b\0", timestamp
+        run_wasi(textual_repr, vec![2, 4, 97, 0, 98, 0, 1000]);
+    }
+
+    #[test]
+    fn test_wasi_unsupported_function() {
+        let textual_repr = r#"
+                (module
+                    (import "wasi_snapshot_preview1" "fd_read"
+                        (func (param i32 i32 i32 i32) (result i32)))
+                    (memory 1)
+                )
+            "#;
+
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+        assert!(link_wasi_shim(&wasm).is_err());
+    }
+}
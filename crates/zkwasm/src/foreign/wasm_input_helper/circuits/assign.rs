@@ -0,0 +1,108 @@
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::circuit::Cell;
+use halo2_proofs::circuit::Layouter;
+use halo2_proofs::plonk::Error;
+
+use crate::circuits::poseidon::PoseidonChip;
+use crate::foreign::foreign_table_enable_lines;
+
+use super::public_input_digest_blocks;
+use super::public_input_digest_capacity;
+use super::WasmInputHelperTableConfig;
+
+pub struct WasmInputHelperTableChip<F: FieldExt> {
+    config: WasmInputHelperTableConfig<F>,
+    max_available_rows: usize,
+}
+
+impl<F: FieldExt> WasmInputHelperTableChip<F> {
+    pub fn new(config: WasmInputHelperTableConfig<F>, max_available_rows: usize) -> Self {
+        Self {
+            config,
+            max_available_rows,
+        }
+    }
+
+    /// Witness the public inputs and outputs and expose their digest, nothing is assigned
+    /// if the values are exposed as instances. `input_index` is the etable cell holding the
+    /// number of public inputs and outputs plus one, which is absorbed before the values.
+    pub fn assign(
+        &self,
+        layouter: &mut impl Layouter<F>,
+        public_inputs_and_outputs: &Vec<u64>,
+        input_index: Cell,
+    ) -> Result<(), Error> {
+        let digest_config = match &self.config.digest {
+            Some(digest_config) => digest_config,
+            None => return Ok(()),
+        };
+
+        let capacity = public_input_digest_capacity(self.max_available_rows);
+        let blocks = public_input_digest_blocks(self.max_available_rows);
+        assert!(public_inputs_and_outputs.len() <= capacity);
+
+        let value_cells = layouter.assign_region(
+            || "wasm input helper assign",
+            |mut region| {
+                let mut value_cells = vec![];
+
+                region.assign_advice(
+                    || "wasm input helper value",
+                    digest_config.values,
+                    0,
+                    || Ok(F::zero()),
+                )?;
+
+                // Rows are assigned up to the capacity to keep the copy constraints in place.
+                for offset in 0..capacity {
+                    let cell = region.assign_advice(
+                        || "wasm input helper value",
+                        digest_config.values,
+                        offset + 1, // The first public input index is 1.
+                        || {
+                            Ok(F::from(
+                                public_inputs_and_outputs.get(offset).cloned().unwrap_or(0),
+                            ))
+                        },
+                    )?;
+                    value_cells.push(cell.cell());
+                }
+
+                // Values read past the capacity are zero, as they are past the instances.
+                for offset in capacity + 1..foreign_table_enable_lines() {
+                    region.assign_fixed(
+                        || "wasm input helper padding sel",
+                        digest_config.padding_sel,
+                        offset,
+                        || Ok(F::one()),
+                    )?;
+                }
+
+                Ok(value_cells)
+            },
+        )?;
+
+        let poseidon = PoseidonChip::new(digest_config.poseidon.clone(), blocks);
+
+        let digest = layouter.assign_region(
+            || "public input digest",
+            |mut region| {
+                let mut inputs = vec![(
+                    F::from(public_inputs_and_outputs.len() as u64 + 1),
+                    Some(input_index),
+                )];
+
+                inputs.extend(value_cells.iter().enumerate().map(|(index, cell)| {
+                    (
+                        F::from(public_inputs_and_outputs.get(index).cloned().unwrap_or(0)),
+                        Some(*cell),
+                    )
+                }));
+
+                poseidon.assign(&mut region, 0, &inputs, public_inputs_and_outputs.len() + 1)
+            },
+        )?;
+
+        layouter.constrain_instance(digest.cell(), self.config.input, 0)
+    }
+}
@@ -5,10 +5,14 @@ use halo2_proofs::plonk::Expression;
 use halo2_proofs::plonk::Fixed;
 use halo2_proofs::plonk::VirtualCells;
 
+use crate::circuits::config::public_input_digest;
+use crate::circuits::poseidon::PoseidonConfig;
+use crate::curr;
 use crate::fixed_curr;
 use crate::foreign::ForeignTableConfig;
 use crate::instance_prev;
 
+use super::PublicInputDigestConfig;
 use super::WasmInputHelperTableConfig;
 
 impl<F: FieldExt> WasmInputHelperTableConfig<F> {
@@ -16,9 +20,28 @@ impl<F: FieldExt> WasmInputHelperTableConfig<F> {
         let input = meta.instance_column();
         meta.enable_equality(input);
 
+        let digest = if public_input_digest() {
+            let padding_sel = meta.fixed_column();
+            let values = meta.advice_column();
+            meta.enable_equality(values);
+
+            meta.create_gate("wasm input helper padding", |meta| {
+                vec![fixed_curr!(meta, padding_sel) * curr!(meta, values)]
+            });
+
+            Some(PublicInputDigestConfig {
+                padding_sel,
+                values,
+                poseidon: PoseidonConfig::configure(meta),
+            })
+        } else {
+            None
+        };
+
         WasmInputHelperTableConfig {
             from_zero_index,
             input,
+            digest,
             _mark: std::marker::PhantomData,
         }
     }
@@ -34,9 +57,14 @@ impl<F: FieldExt> ForeignTableConfig<F> for WasmInputHelperTableConfig<F> {
         meta.lookup_any(key, |meta| {
             let mut exprs = expr(meta);
 
+            let value = match &self.digest {
+                Some(digest) => curr!(meta, digest.values),
+                None => instance_prev!(meta, self.input),
+            };
+
             vec![
                 (exprs.remove(0), fixed_curr!(meta, self.from_zero_index)),
-                (exprs.remove(0), instance_prev!(meta, self.input)),
+                (exprs.remove(0), value),
             ]
         });
     }
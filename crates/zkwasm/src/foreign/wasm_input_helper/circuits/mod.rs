@@ -1,17 +1,65 @@
 use std::marker::PhantomData;
 
 use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::plonk::Advice;
 use halo2_proofs::plonk::Column;
 use halo2_proofs::plonk::Fixed;
 use halo2_proofs::plonk::Instance;
 
+use crate::circuits::poseidon::poseidon_capacity;
+use crate::circuits::poseidon::poseidon_hash_with_length;
+use crate::circuits::poseidon::PoseidonConfig;
+use crate::circuits::poseidon::POSEIDON_BLOCK_ROWS;
+
+pub mod assign;
 pub mod config;
 
 pub const WASM_INPUT_FOREIGN_TABLE_KEY: &'static str = "wasm-input-helper-table";
 
+/// Public inputs and outputs are witnessed and absorbed into a digest.
+#[derive(Clone)]
+pub struct PublicInputDigestConfig<F: FieldExt> {
+    /// Enabled on the rows past the capacity, where the values must be zero.
+    padding_sel: Column<Fixed>,
+    values: Column<Advice>,
+    poseidon: PoseidonConfig<F>,
+}
+
 #[derive(Clone)]
 pub struct WasmInputHelperTableConfig<F: FieldExt> {
     from_zero_index: Column<Fixed>,
+    /// Public inputs and outputs, or their digest if `digest` is enabled.
     input: Column<Instance>,
+    digest: Option<PublicInputDigestConfig<F>>,
     _mark: PhantomData<F>,
 }
+
+/// Number of blocks of the public input digest.
+pub(crate) fn public_input_digest_blocks(max_available_rows: usize) -> usize {
+    (max_available_rows - 2) / POSEIDON_BLOCK_ROWS
+}
+
+/// Number of public inputs and outputs a digest holds, the digest also absorbs their count.
+pub(crate) fn public_input_digest_capacity(max_available_rows: usize) -> usize {
+    poseidon_capacity(public_input_digest_blocks(max_available_rows)) - 1
+}
+
+/// Digest of the public inputs and outputs, as exposed by the circuit.
+pub fn public_inputs_digest<F: FieldExt>(public_inputs_and_outputs: &[u64]) -> F {
+    poseidon_hash_with_length(public_inputs_and_outputs)
+}
+
+/// Instances of the public input column, see `CircuitParams::public_input_digest`.
+pub fn public_instances<F: FieldExt>(
+    public_inputs_and_outputs: &[u64],
+    public_input_digest: bool,
+) -> Vec<F> {
+    if public_input_digest {
+        vec![public_inputs_digest(public_inputs_and_outputs)]
+    } else {
+        public_inputs_and_outputs
+            .iter()
+            .map(|v| F::from(*v))
+            .collect()
+    }
+}
@@ -1,5 +1,14 @@
 #[cfg(test)]
 mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    use crate::circuits::config::with_circuit_params;
+    use crate::circuits::config::zkwasm_k;
+    use crate::circuits::config::CircuitParams;
+    use crate::circuits::TestCircuit;
+    use crate::foreign::context::circuits::context_instances;
+    use crate::foreign::wasm_input_helper::circuits::public_inputs_digest;
     use crate::foreign::wasm_input_helper::runtime::register_wasm_input_foreign;
     use crate::runtime::host::host_env::HostEnv;
     use crate::test::test_circuit_with_env;
@@ -61,4 +70,71 @@ mod tests {
 
         test_circuit_with_env(env, wasm_runtime_io, wasm, "main").unwrap();
     }
+
+    #[test]
+    fn test_foreign_wasm_input_digest() {
+        let textual_repr = r#"
+                (module
+                    (import "env" "wasm_input" (func $wasm_input (param i32) (result i64)))
+                    (import "env" "wasm_output" (func $wasm_output (param i64)))
+                    (export "main" (func $main))
+                    (func $main (; 2 ;)
+                        (call $wasm_input (i32.const 1))
+                        (call $wasm_input (i32.const 1))
+                        (i64.add)
+                        (call $wasm_output)
+                    )
+                )
+            "#;
+
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        let wasm_runtime_io = register_wasm_input_foreign(&mut env, vec![3, 4], vec![]);
+        env.finalize();
+
+        let execution_result = test_circuit_with_env(env, wasm_runtime_io, wasm, "main").unwrap();
+        assert_eq!(execution_result.public_inputs_and_outputs, vec![3, 4, 7]);
+
+        let circuit: TestCircuit<Fr> = TestCircuit::new(
+            execution_result.tables,
+            execution_result.public_inputs_and_outputs,
+            CircuitParams {
+                public_input_digest: true,
+                ..CircuitParams::default()
+            },
+        );
+        let context_instance = context_instances(&[], &[]);
+
+        let mock = |public_inputs_and_outputs: &[u64]| {
+            with_circuit_params(&circuit.params, || {
+                MockProver::run(
+                    zkwasm_k(),
+                    &circuit,
+                    vec![
+                        vec![public_inputs_digest(public_inputs_and_outputs)],
+                        context_instance.clone(),
+                    ],
+                )
+                .unwrap()
+                .verify()
+            })
+        };
+
+        assert!(mock(&[3, 4, 8]).is_err());
+        assert_eq!(mock(&[3, 4, 7]), Ok(()));
+
+        // Out of `with_circuit_params` the default parameters are configured.
+        let unscoped = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            MockProver::run(
+                zkwasm_k(),
+                &circuit,
+                vec![
+                    vec![public_inputs_digest(&[3, 4, 7])],
+                    context_instance.clone(),
+                ],
+            )
+        }));
+        assert!(unscoped.is_err());
+    }
 }
@@ -7,10 +7,9 @@ pub mod checksum;
 pub mod circuits;
 pub mod foreign;
 pub mod loader;
+pub mod profile;
 pub mod runtime;
 
-mod profile;
-
 #[cfg(test)]
 pub mod test;
 
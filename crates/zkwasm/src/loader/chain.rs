@@ -0,0 +1,84 @@
+//! Sequential executions of an image where the context outputs written by an execution are
+//! the context inputs of the next one.
+
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use halo2_proofs::arithmetic::FieldExt;
+use halo2_proofs::arithmetic::MultiMillerLoop;
+
+use crate::circuits::TestCircuit;
+use crate::runtime::host::record::HostCallMode;
+
+use super::err::ChainErr;
+use super::err::Error;
+use super::ExecutionArg;
+use super::ZkWasmLoader;
+
+pub struct ChainStepArg {
+    /// Public inputs for `wasm_input(1)`
+    pub public_inputs: Vec<u64>,
+    /// Private inputs for `wasm_input(0)`
+    pub private_inputs: Vec<u64>,
+}
+
+pub struct ChainStep<F: FieldExt> {
+    pub circuit: TestCircuit<F>,
+    /// Public inputs and outputs followed by the context digests.
+    pub instances: Vec<Vec<F>>,
+    /// Context outputs written by the step, i.e. the context inputs of the next step.
+    pub context_outputs: Vec<u64>,
+}
+
+impl<E: MultiMillerLoop> ZkWasmLoader<E> {
+    /// Execute `steps` in order, the first step reads `context_inputs`.
+    pub fn circuits_with_context_chain(
+        &self,
+        steps: Vec<ChainStepArg>,
+        context_inputs: Vec<u64>,
+    ) -> Result<Vec<ChainStep<E::Scalar>>> {
+        let mut context_inputs = context_inputs;
+        let mut chain = Vec::with_capacity(steps.len());
+
+        for step in steps {
+            let context_outputs = Arc::new(Mutex::new(vec![]));
+
+            let (circuit, instances) = self.circuit_with_witness(ExecutionArg {
+                public_inputs: step.public_inputs,
+                private_inputs: step.private_inputs,
+                context_inputs,
+                context_outputs: context_outputs.clone(),
+                host_call_mode: HostCallMode::Native,
+            })?;
+
+            context_inputs = context_outputs.lock().unwrap().clone();
+
+            chain.push(ChainStep {
+                circuit,
+                instances,
+                context_outputs: context_inputs.clone(),
+            });
+        }
+
+        Ok(chain)
+    }
+}
+
+/// Check with the context digests that each step read exactly the context written by the
+/// previous one. A step reading only a part of its context inputs breaks the chain.
+pub fn check_context_chain<F: FieldExt>(instances: &[Vec<Vec<F>>]) -> Result<()> {
+    for (step, pair) in instances.windows(2).enumerate() {
+        let written = pair[0][1][1];
+        let read = pair[1][1][0];
+
+        if written != read {
+            return Err(anyhow!(Error::Chain(ChainErr::ContextMismatch {
+                step: step + 1
+            })));
+        }
+    }
+
+    Ok(())
+}
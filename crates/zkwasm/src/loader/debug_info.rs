@@ -0,0 +1,624 @@
+//! Source-level information of a wasm image.
+//!
+//! Function names come from the name section, falling back to exports. Source locations come
+//! from the DWARF sections of the image when it was built with debug info, read with gimli.
+//!
+//! Functions are indexed by the fid the tracer allocates them: every function of a copy of the
+//! module is exported under a name holding its index, see `DebugInfo::export_functions`, and
+//! the instance of the copy resolves each name to its fid.
+//!
+//! The interpreter compiles function bodies into its own instruction set. The code offset of
+//! each iid is found by replaying this translation over the instructions of the body, each
+//! instruction then takes the `.debug_line` row covering its offset. A function whose itable
+//! doesn't match the translation only has the location of the function.
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::io::Cursor;
+use std::io::Write;
+use std::path::PathBuf;
+
+use gimli::ColumnType;
+use gimli::Dwarf;
+use gimli::EndianSlice;
+use gimli::LittleEndian;
+use gimli::Reader;
+use parity_wasm::elements::Deserialize;
+use parity_wasm::elements::ExportEntry;
+use parity_wasm::elements::ExportSection;
+use parity_wasm::elements::ImportCountType;
+use parity_wasm::elements::Instruction;
+use parity_wasm::elements::Internal;
+use parity_wasm::elements::Local;
+use parity_wasm::elements::Module;
+use parity_wasm::elements::Section;
+use parity_wasm::elements::Type;
+use parity_wasm::elements::VarUint32;
+use serde::Serialize;
+use specs::itable::InstructionTable;
+use specs::itable::InstructionTableEntry;
+use specs::itable::OpcodeClass;
+use wasmi::tracer::Tracer;
+use wasmi::NotStartedModuleRef;
+
+use crate::runtime::CompiledImage;
+
+const CODE_SECTION_ID: u8 = 10;
+const CUSTOM_SECTION_ID: u8 = 0;
+
+/// Prefix of the names under which `DebugInfo::export_functions` exports each function.
+const FUNCTION_EXPORT_PREFIX: &str = "__zkwasm_debug_function_";
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SourceLocation {
+    pub file: String,
+    pub line: u64,
+    pub column: u64,
+}
+
+impl Display for SourceLocation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.column == 0 {
+            write!(f, "{}:{}", self.file, self.line)
+        } else {
+            write!(f, "{}:{}:{}", self.file, self.line, self.column)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct FunctionDebugInfo {
+    pub name: Option<String>,
+    pub location: Option<SourceLocation>,
+    /// Number of parameters and declared locals, absent for imported functions.
+    pub locals: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+struct InstructionLocation {
+    /// Offset of the wasm instruction, relative to the payload of the code section as the
+    /// addresses of `.debug_line`.
+    offset: u64,
+    location: Option<SourceLocation>,
+}
+
+/// An itable entry with the name of its function, its code offset and its source location.
+#[derive(Clone, Debug, Serialize)]
+pub struct InstructionDebugInfo {
+    pub fid: u32,
+    pub iid: u32,
+    pub opcode: String,
+    pub function_name: Option<String>,
+    pub offset: Option<u64>,
+    pub location: Option<SourceLocation>,
+}
+
+/// Debug information indexed by fid, and by `(fid, iid)` for instructions.
+#[derive(Clone, Debug, Default)]
+pub struct DebugInfo {
+    functions: BTreeMap<u32, FunctionDebugInfo>,
+    instructions: BTreeMap<(u32, u32), InstructionLocation>,
+}
+
+impl DebugInfo {
+    /// A copy of `module` exporting each defined function under a name holding its index.
+    /// Exports leave the functions untouched, the tracer allocates the same fids for both.
+    pub fn export_functions(module: &Module) -> Module {
+        let mut module = module.clone();
+
+        let imported_functions = module.import_count(ImportCountType::Function) as u32;
+        let defined_functions = module
+            .function_section()
+            .map_or(0, |section| section.entries().len()) as u32;
+
+        let entries = (imported_functions..imported_functions + defined_functions)
+            .map(|index| {
+                ExportEntry::new(
+                    format!("{}{}", FUNCTION_EXPORT_PREFIX, index),
+                    Internal::Function(index),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        match module.export_section_mut() {
+            Some(section) => section.entries_mut().extend(entries),
+            None => module
+                .insert_section(Section::Export(ExportSection::with_entries(entries)))
+                .expect("the module has no export section"),
+        }
+
+        module
+    }
+
+    /// `image` is the binary as provided by the user, `module` is the module being executed,
+    /// which may differ from `image` after the WASI shim is linked. Linking keeps the order of
+    /// the defined functions, so both are matched by the index of their body.
+    ///
+    /// `compiled` is the compilation of `export_functions(module)`.
+    pub fn new(
+        image: &[u8],
+        module: &Module,
+        compiled: &CompiledImage<NotStartedModuleRef<'_>, Tracer>,
+    ) -> Self {
+        let module = match module.clone().parse_names() {
+            Ok(module) => module,
+            Err((_, module)) => module,
+        };
+
+        let imported_functions = module.import_count(ImportCountType::Function) as u32;
+
+        // The fid of each defined function, from the function table of the tracer.
+        let fids: BTreeMap<u32, u32> = {
+            let defined_functions = module
+                .function_section()
+                .map_or(0, |section| section.entries().len())
+                as u32;
+
+            (imported_functions..imported_functions + defined_functions)
+                .map(|index| {
+                    let fid = compiled.instance.lookup_function_by_name(
+                        compiled.tracer.clone(),
+                        &format!("{}{}", FUNCTION_EXPORT_PREFIX, index),
+                    );
+
+                    (index, fid)
+                })
+                .collect()
+        };
+
+        let mut functions: BTreeMap<u32, FunctionDebugInfo> = BTreeMap::new();
+
+        if let Some(section) = module.export_section() {
+            for entry in section.entries() {
+                if let Internal::Function(index) = entry.internal() {
+                    if let Some(fid) = fids.get(index) {
+                        functions.entry(*fid).or_default().name = Some(entry.field().to_owned());
+                    }
+                }
+            }
+        }
+
+        if let Some(names) = module
+            .names_section()
+            .and_then(|section| section.functions())
+        {
+            for (index, name) in names.names().iter() {
+                if let Some(fid) = fids.get(&index) {
+                    functions.entry(*fid).or_default().name = Some(name.clone());
+                }
+            }
+        }
+
+        if let (Some(functions_section), Some(code)) =
+            (module.function_section(), module.code_section())
+        {
+            let types = module
+                .type_section()
+                .map_or(&[][..], |section| section.types());
+
+            for (body, (func, code)) in functions_section
+                .entries()
+                .iter()
+                .zip(code.bodies())
+                .enumerate()
+            {
+                let params = match types.get(func.type_ref() as usize) {
+                    Some(Type::Function(ty)) => ty.params().len() as u32,
+                    None => 0,
+                };
+                let declared = code.locals().iter().map(|local| local.count()).sum::<u32>();
+
+                functions
+                    .entry(fids[&(imported_functions + body as u32)])
+                    .or_default()
+                    .locals = Some(params + declared);
+            }
+        }
+
+        let mut instructions = BTreeMap::new();
+
+        if let Some(sections) = raw_sections(image) {
+            let rows = line_rows(&sections.custom).unwrap_or_default();
+
+            let mut itable: BTreeMap<u32, Vec<&InstructionTableEntry>> = BTreeMap::new();
+            for entry in compiled.tables.itable.entries() {
+                itable.entry(entry.fid).or_default().push(entry);
+            }
+
+            for (body_index, body) in sections.bodies.iter().enumerate() {
+                let fid = match fids.get(&(imported_functions + body_index as u32)) {
+                    Some(fid) => *fid,
+                    None => continue,
+                };
+
+                functions.entry(fid).or_default().location = rows
+                    .iter()
+                    .filter(|row| row.address >= body.start && row.address < body.end)
+                    .find_map(|row| row.location.clone());
+
+                let mut entries = itable.remove(&fid).unwrap_or_default();
+                entries.sort_by_key(|entry| entry.iid);
+
+                for (iid, offset) in
+                    instruction_offsets(&body.instructions, &entries).unwrap_or_default()
+                {
+                    instructions.insert(
+                        (fid, iid),
+                        InstructionLocation {
+                            offset,
+                            location: location_at(&rows, body.start, offset),
+                        },
+                    );
+                }
+            }
+        }
+
+        DebugInfo {
+            functions,
+            instructions,
+        }
+    }
+
+    pub fn function(&self, fid: u32) -> Option<&FunctionDebugInfo> {
+        self.functions.get(&fid)
+    }
+
+    pub fn fid_of_name(&self, name: &str) -> Option<u32> {
+        self.functions
+            .iter()
+            .find(|(_, function)| function.name.as_deref() == Some(name))
+            .map(|(fid, _)| *fid)
+    }
+
+    pub fn function_name(&self, fid: u32) -> Option<&str> {
+        self.function(fid)
+            .and_then(|function| function.name.as_deref())
+    }
+
+    /// The location of the first line row of the function.
+    pub fn function_location(&self, fid: u32) -> Option<&SourceLocation> {
+        self.function(fid)
+            .and_then(|function| function.location.as_ref())
+    }
+
+    /// The code offset of the wasm instruction translated to `iid`.
+    pub fn offset(&self, fid: u32, iid: u32) -> Option<u64> {
+        self.instructions
+            .get(&(fid, iid))
+            .map(|instruction| instruction.offset)
+    }
+
+    /// The location of the instruction, falling back to the location of its function.
+    pub fn location(&self, fid: u32, iid: u32) -> Option<&SourceLocation> {
+        self.instructions
+            .get(&(fid, iid))
+            .and_then(|instruction| instruction.location.as_ref())
+            .or_else(|| self.function_location(fid))
+    }
+
+    pub fn annotate(&self, itable: &InstructionTable) -> Vec<InstructionDebugInfo> {
+        itable
+            .entries()
+            .iter()
+            .map(|entry| InstructionDebugInfo {
+                fid: entry.fid,
+                iid: entry.iid,
+                opcode: format!("{:?}", entry.opcode),
+                function_name: self.function_name(entry.fid).map(|name| name.to_owned()),
+                offset: self.offset(entry.fid, entry.iid),
+                location: self.location(entry.fid, entry.iid).cloned(),
+            })
+            .collect()
+    }
+
+    /// Write the annotated itable to `itable.debug.json` next to the other table dumps.
+    pub fn write_json(&self, itable: &InstructionTable, dir: Option<PathBuf>) {
+        let mut path = dir.unwrap_or(std::env::current_dir().unwrap());
+        path.push("itable.debug.json");
+
+        let mut fd = std::fs::File::create(path.as_path()).unwrap();
+        fd.write_all(
+            serde_json::to_string_pretty(&self.annotate(itable))
+                .unwrap()
+                .as_bytes(),
+        )
+        .unwrap();
+    }
+}
+
+/// A function body, offsets are relative to the start of the code section payload.
+struct Body {
+    start: u64,
+    end: u64,
+    instructions: Vec<(u64, Instruction)>,
+}
+
+struct RawSections<'a> {
+    bodies: Vec<Body>,
+    custom: BTreeMap<String, &'a [u8]>,
+}
+
+fn raw_sections(image: &[u8]) -> Option<RawSections<'_>> {
+    let mut reader = EndianSlice::new(image.get(8..)?, LittleEndian);
+
+    let mut bodies = vec![];
+    let mut custom = BTreeMap::new();
+
+    while !reader.is_empty() {
+        let id = reader.read_u8().ok()?;
+        let len = reader.read_uleb128().ok()? as usize;
+        let mut payload = reader.split(len).ok()?;
+
+        match id {
+            CODE_SECTION_ID => bodies = function_bodies(payload)?,
+            CUSTOM_SECTION_ID => {
+                let name_len = payload.read_uleb128().ok()? as usize;
+                let name = payload.split(name_len).ok()?.to_string_lossy().into_owned();
+                custom.insert(name, payload.slice());
+            }
+            _ => (),
+        }
+    }
+
+    Some(RawSections { bodies, custom })
+}
+
+fn function_bodies(code: EndianSlice<LittleEndian>) -> Option<Vec<Body>> {
+    let mut reader = code;
+    let count = reader.read_uleb128().ok()?;
+
+    (0..count)
+        .map(|_| {
+            let start = reader.offset_from(&code) as u64;
+            let len = reader.read_uleb128().ok()? as usize;
+            let body = reader.split(len).ok()?;
+            let end = reader.offset_from(&code) as u64;
+
+            Some(Body {
+                start,
+                end,
+                instructions: body_instructions(body.slice(), end - len as u64)?,
+            })
+        })
+        .collect()
+}
+
+/// The instructions of a body with their offsets, `base` being the offset of the body.
+fn body_instructions(body: &[u8], base: u64) -> Option<Vec<(u64, Instruction)>> {
+    let mut cursor = Cursor::new(body);
+
+    let locals: u32 = VarUint32::deserialize(&mut cursor).ok()?.into();
+    for _ in 0..locals {
+        Local::deserialize(&mut cursor).ok()?;
+    }
+
+    let mut instructions = vec![];
+    while (cursor.position() as usize) < body.len() {
+        let offset = base + cursor.position();
+        instructions.push((offset, Instruction::deserialize(&mut cursor).ok()?));
+    }
+
+    Some(instructions)
+}
+
+const NUMERIC_CLASSES: &[OpcodeClass] = &[
+    OpcodeClass::Bin,
+    OpcodeClass::BinShift,
+    OpcodeClass::BinBit,
+    OpcodeClass::Unary,
+    OpcodeClass::Test,
+    OpcodeClass::Rel,
+    OpcodeClass::Conversion,
+];
+
+/// The classes of the instruction emitted by the interpreter for `instruction`, `None` if
+/// nothing is emitted. `Const` and `LocalGet` may be fused with the following `Bin`.
+fn translated_classes(instruction: &Instruction, is_function_end: bool) -> Option<&[OpcodeClass]> {
+    use parity_wasm::elements::Instruction::*;
+
+    let classes: &[OpcodeClass] = match instruction {
+        Nop | Block(_) | Loop(_) => return None,
+        End if !is_function_end => return None,
+        End | Return => &[OpcodeClass::Return],
+        Unreachable => &[OpcodeClass::Unreachable],
+        If(_) => &[OpcodeClass::BrIfEqz],
+        Else | Br(_) => &[OpcodeClass::Br],
+        BrIf(_) => &[OpcodeClass::BrIf],
+        BrTable(_) => &[OpcodeClass::BrTable],
+        Call(_) => &[
+            OpcodeClass::Call,
+            OpcodeClass::CallHost,
+            OpcodeClass::ForeignPluginStart,
+        ],
+        CallIndirect(..) => &[OpcodeClass::CallIndirect],
+        Drop => &[OpcodeClass::Drop],
+        Select => &[OpcodeClass::Select],
+        GetLocal(_) => &[OpcodeClass::LocalGet, OpcodeClass::BinLocal],
+        SetLocal(_) => &[OpcodeClass::LocalSet],
+        TeeLocal(_) => &[OpcodeClass::LocalTee],
+        GetGlobal(_) => &[OpcodeClass::GlobalGet],
+        SetGlobal(_) => &[OpcodeClass::GlobalSet],
+        I32Const(_) | I64Const(_) | F32Const(_) | F64Const(_) => {
+            &[OpcodeClass::Const, OpcodeClass::BinConst]
+        }
+        I32Load(..) | I64Load(..) | F32Load(..) | F64Load(..) | I32Load8S(..) | I32Load8U(..)
+        | I32Load16S(..) | I32Load16U(..) | I64Load8S(..) | I64Load8U(..) | I64Load16S(..)
+        | I64Load16U(..) | I64Load32S(..) | I64Load32U(..) => &[OpcodeClass::Load],
+        I32Store(..) | I64Store(..) | F32Store(..) | F64Store(..) | I32Store8(..)
+        | I32Store16(..) | I64Store8(..) | I64Store16(..) | I64Store32(..) => &[OpcodeClass::Store],
+        CurrentMemory(_) => &[OpcodeClass::MemorySize],
+        GrowMemory(_) => &[OpcodeClass::MemoryGrow],
+        _ => NUMERIC_CLASSES,
+    };
+
+    Some(classes)
+}
+
+/// Match the instructions of a body with the itable entries of its function, ordered by
+/// iid, as `(iid, offset)`. `None` if the entries don't follow the translation.
+fn instruction_offsets(
+    instructions: &[(u64, Instruction)],
+    entries: &[&InstructionTableEntry],
+) -> Option<Vec<(u32, u64)>> {
+    let mut entries = entries.iter();
+
+    let offsets = instructions
+        .iter()
+        .enumerate()
+        .filter_map(|(index, (offset, instruction))| {
+            translated_classes(instruction, index + 1 == instructions.len())
+                .map(|classes| (*offset, classes))
+        })
+        .map(|(offset, classes)| {
+            let entry = entries.next()?;
+            let class: OpcodeClass = entry.opcode.clone().into();
+
+            classes.contains(&class).then_some((entry.iid, offset))
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    entries.next().is_none().then_some(offsets)
+}
+
+#[derive(Clone, Debug)]
+struct LineRow {
+    address: u64,
+    /// `None` for rows without a source line, and at the end of a sequence.
+    location: Option<SourceLocation>,
+}
+
+/// Rows of the line programs of all units, ordered by address.
+fn line_rows(custom: &BTreeMap<String, &[u8]>) -> gimli::Result<Vec<LineRow>> {
+    let dwarf = Dwarf::load(|id| -> gimli::Result<_> {
+        Ok(EndianSlice::new(
+            custom.get(id.name()).cloned().unwrap_or_default(),
+            LittleEndian,
+        ))
+    })?;
+
+    let mut rows = vec![];
+
+    let mut units = dwarf.units();
+    while let Some(header) = units.next()? {
+        let mut unit = dwarf.unit(header)?;
+        let program = match unit.line_program.take() {
+            Some(program) => program,
+            None => continue,
+        };
+
+        let mut program_rows = program.rows();
+        while let Some((header, row)) = program_rows.next_row()? {
+            let location = match row.line() {
+                Some(line) if !row.end_sequence() => {
+                    let file = match row.file(header) {
+                        Some(file) => {
+                            let path = dwarf.attr_string(&unit, file.path_name())?;
+                            let path = path.to_string_lossy();
+
+                            match file.directory(header) {
+                                Some(directory) if !path.starts_with('/') => {
+                                    let directory = dwarf.attr_string(&unit, directory)?;
+
+                                    if directory.is_empty() {
+                                        path.into_owned()
+                                    } else {
+                                        format!("{}/{}", directory.to_string_lossy(), path)
+                                    }
+                                }
+                                _ => path.into_owned(),
+                            }
+                        }
+                        None => format!("<file {}>", row.file_index()),
+                    };
+
+                    Some(SourceLocation {
+                        file,
+                        line: line.get(),
+                        column: match row.column() {
+                            ColumnType::LeftEdge => 0,
+                            ColumnType::Column(column) => column.get(),
+                        },
+                    })
+                }
+                _ => None,
+            };
+
+            rows.push(LineRow {
+                address: row.address(),
+                location,
+            });
+        }
+    }
+
+    // A sequence may start where another ends.
+    rows.sort_by_key(|row| (row.address, row.location.is_some()));
+
+    Ok(rows)
+}
+
+/// The location of the last row at or before `offset` within the body starting at `start`.
+fn location_at(rows: &[LineRow], start: u64, offset: u64) -> Option<SourceLocation> {
+    let end = rows.partition_point(|row| row.address <= offset);
+
+    rows[..end]
+        .last()
+        .filter(|row| row.address >= start)
+        .and_then(|row| row.location.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use parity_wasm::elements::Instruction;
+
+    use super::raw_sections;
+
+    #[test]
+    fn test_instruction_offsets_of_bodies() {
+        let wasm = wabt::wat2wasm(
+            r#"
+            (module
+                (func
+                    i32.const 1
+                    drop)
+                (func (local i64)
+                    nop
+                    local.get 0
+                    drop))
+            "#,
+        )
+        .unwrap();
+
+        let sections = raw_sections(&wasm).unwrap();
+        let bodies = sections
+            .bodies
+            .iter()
+            .map(|body| (body.start, body.end, body.instructions.clone()))
+            .collect::<Vec<_>>();
+
+        // The payload starts with the number of bodies, each body with its size and locals.
+        assert_eq!(
+            bodies,
+            vec![
+                (
+                    1,
+                    7,
+                    vec![
+                        (3, Instruction::I32Const(1)),
+                        (5, Instruction::Drop),
+                        (6, Instruction::End),
+                    ]
+                ),
+                (
+                    7,
+                    16,
+                    vec![
+                        (11, Instruction::Nop),
+                        (12, Instruction::GetLocal(0)),
+                        (14, Instruction::Drop),
+                        (15, Instruction::End),
+                    ]
+                ),
+            ]
+        );
+    }
+}
@@ -4,15 +4,39 @@ use std::fmt::Display;
 pub enum PreCheckErr {
     ZkmainNotExists,
     ZkmainIsNotFunction,
+    PhantomFunctionNotExists(String),
+    UnsafePhantomFunction {
+        name: String,
+        reason: String,
+    },
+    /// The instruction, br and elem tables need more rows than the image table holds for K.
+    ImageTableOverflow {
+        k: u32,
+        required: usize,
+        available: usize,
+    },
+    /// The global init entries need more rows than the init memory column holds for K.
+    InitMemoryTableOverflow {
+        k: u32,
+        required: usize,
+        available: usize,
+    },
     // ZkmainTypeNotMatch,
 }
 
 #[derive(Debug)]
 pub enum RuntimeErr {}
 
+#[derive(Debug)]
+pub enum ChainErr {
+    /// The context inputs of `step` differ from the context outputs of the previous step.
+    ContextMismatch { step: usize },
+}
+
 #[derive(Debug)]
 pub enum Error {
     PreCheck(PreCheckErr),
+    Chain(ChainErr),
     // Runtime(RuntimeErr),
 }
 
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::marker::PhantomData;
 use std::sync::Arc;
 use std::sync::Mutex;
@@ -15,6 +16,12 @@ use halo2_proofs::poly::commitment::ParamsVerifier;
 use halo2aggregator_s::circuits::utils::load_or_create_proof;
 use halo2aggregator_s::circuits::utils::TranscriptHash;
 use halo2aggregator_s::transcript::poseidon::PoseidonRead;
+use log::info;
+use log::log_enabled;
+use log::Level;
+use specs::dump::TableDumpOption;
+use specs::dump::TableKind;
+use specs::CompilationTable;
 use specs::ExecutionTable;
 use specs::Tables;
 use wasmi::tracer::Tracer;
@@ -24,15 +31,31 @@ use wasmi::RuntimeValue;
 
 use crate::checksum::CompilationTableWithParams;
 use crate::checksum::ImageCheckSum;
+use crate::circuits::capacity::TraceCapacity;
 use crate::circuits::config::init_zkwasm_runtime;
+use crate::circuits::config::max_image_table_rows;
+use crate::circuits::config::max_init_memory_table_rows;
 use crate::circuits::config::set_zkwasm_k;
+use crate::circuits::config::with_circuit_params;
+use crate::circuits::config::CircuitParams;
+use crate::circuits::diagnose::DiagnosticReport;
+use crate::circuits::image_table::image_table_lookup_rows;
+use crate::circuits::image_table::init_memory_table_rows;
 use crate::circuits::image_table::IMAGE_COL_NAME;
+use crate::circuits::image_table::INIT_MEMORY_COL_NAME;
 use crate::circuits::TestCircuit;
 use crate::circuits::ZkWasmCircuitBuilder;
+use crate::foreign::context::circuits::assign::ExtractContextFromTrace;
+use crate::foreign::context::circuits::context_instances;
+use crate::foreign::wasi_helper::shim::link_wasi_shim;
+use crate::foreign::wasm_input_helper::circuits::public_instances;
+use crate::loader::debug_info::DebugInfo;
 use crate::loader::err::Error;
 use crate::loader::err::PreCheckErr;
+use crate::loader::phantom::resolve_phantom_functions;
 use crate::profile::Profiler;
 use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::host::record::HostCallMode;
 use crate::runtime::wasmi_interpreter::Execution;
 use crate::runtime::CompiledImage;
 use crate::runtime::ExecutionResult;
@@ -40,6 +63,10 @@ use crate::runtime::WasmInterpreter;
 use anyhow::anyhow;
 
 mod err;
+mod phantom;
+
+pub mod chain;
+pub mod debug_info;
 
 const ENTRY: &str = "zkmain";
 
@@ -52,6 +79,8 @@ pub struct ExecutionArg {
     pub context_inputs: Vec<u64>,
     /// Context outputs for `wasm_write_context()`
     pub context_outputs: Arc<Mutex<Vec<u64>>>,
+    /// Record or replay the invocations of external host functions
+    pub host_call_mode: HostCallMode,
 }
 
 pub struct ExecutionReturn {
@@ -61,7 +90,12 @@ pub struct ExecutionReturn {
 pub struct ZkWasmLoader<E: MultiMillerLoop> {
     k: u32,
     module: wasmi::Module,
+    debug_info: DebugInfo,
     phantom_functions: Vec<String>,
+    table_dump: Option<TableDumpOption>,
+    step_limit: Option<usize>,
+    circuit_params: CircuitParams,
+    imported_globals: BTreeMap<String, RuntimeValue>,
     _data: PhantomData<E>,
 }
 
@@ -86,21 +120,77 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
             }
         }
 
+        fn check_image_table_capacity(k: u32, table: &CompilationTable) -> Result<()> {
+            let required = image_table_lookup_rows(table);
+            let available = max_image_table_rows() as usize;
+
+            if required > available {
+                return Err(anyhow!(Error::PreCheck(PreCheckErr::ImageTableOverflow {
+                    k,
+                    required,
+                    available,
+                })));
+            }
+
+            let required = init_memory_table_rows(table);
+            let available = max_init_memory_table_rows() as usize;
+
+            if required > available {
+                return Err(anyhow!(Error::PreCheck(
+                    PreCheckErr::InitMemoryTableOverflow {
+                        k,
+                        required,
+                        available,
+                    }
+                )));
+            }
+
+            Ok(())
+        }
+
         check_zkmain_exists(&self.module)?;
         // TODO: check the signature of zkmain function.
         // TODO: check the relation between maximal pages and K.
-        // TODO: check the instructions of phantom functions.
-        // TODO: check phantom functions exists.
         // TODO: check if instructions are supported.
 
+        {
+            let (mut env, _) = HostEnv::new_with_full_foreign_plugins(
+                vec![],
+                vec![],
+                vec![],
+                Arc::new(Mutex::new(vec![])),
+            );
+
+            check_image_table_capacity(self.k, &self.compile(&mut env)?.tables)?;
+        }
+
+        // The context is only known at run time, its limit is reported upfront.
+        info!(
+            "K = {} holds at most {} context reads and as many context writes per execution",
+            self.k,
+            TraceCapacity::new().context
+        );
+
         Ok(())
     }
 
-    fn compile(&self, env: &HostEnv) -> Result<CompiledImage<NotStartedModuleRef<'_>, Tracer>> {
+    fn compile(&self, env: &mut HostEnv) -> Result<CompiledImage<NotStartedModuleRef<'_>, Tracer>> {
+        self.compile_module(&self.module, env)
+    }
+
+    fn compile_module<'a>(
+        &self,
+        module: &'a wasmi::Module,
+        env: &mut HostEnv,
+    ) -> Result<CompiledImage<NotStartedModuleRef<'a>, Tracer>> {
+        for (name, value) in &self.imported_globals {
+            env.register_global(name, *value);
+        }
+
         let imports = ImportsBuilder::new().with_resolver("env", env);
 
         WasmInterpreter::compile(
-            &self.module,
+            module,
             &imports,
             &env.function_description_table(),
             ENTRY,
@@ -109,14 +199,14 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
     }
 
     fn circuit_without_witness(&self) -> Result<TestCircuit<E::Scalar>> {
-        let (env, wasm_runtime_io) = HostEnv::new_with_full_foreign_plugins(
+        let (mut env, wasm_runtime_io) = HostEnv::new_with_full_foreign_plugins(
             vec![],
             vec![],
             vec![],
             Arc::new(Mutex::new(vec![])),
         );
 
-        let compiled_module = self.compile(&env)?;
+        let compiled_module = self.compile(&mut env)?;
 
         let builder = ZkWasmCircuitBuilder {
             tables: Tables {
@@ -124,43 +214,135 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
                 execution_tables: ExecutionTable::default(),
             },
             public_inputs_and_outputs: wasm_runtime_io.public_inputs_and_outputs.borrow().clone(),
+            params: self.circuit_params.clone(),
         };
 
         Ok(builder.build_circuit::<E::Scalar>())
     }
 
+    /// Compile a copy of the module exporting all its functions, to look up their fids.
+    fn load_debug_info(&self, image: &[u8]) -> Result<DebugInfo> {
+        let module = wasmi::Module::from_parity_wasm_module(DebugInfo::export_functions(
+            self.module.module(),
+        ))?;
+
+        let (mut env, _) = HostEnv::new_with_full_foreign_plugins(
+            vec![],
+            vec![],
+            vec![],
+            Arc::new(Mutex::new(vec![])),
+        );
+        let compiled = self.compile_module(&module, &mut env)?;
+
+        Ok(DebugInfo::new(image, self.module.module(), &compiled))
+    }
+
     pub fn new(k: u32, image: Vec<u8>, phantom_functions: Vec<String>) -> Result<Self> {
+        Self::new_with_imported_globals(k, image, phantom_functions, BTreeMap::new())
+    }
+
+    /// Provide the initial values of the globals imported from `env`, which are part of the
+    /// image, see `HostEnv::register_global`.
+    pub fn new_with_imported_globals(
+        k: u32,
+        image: Vec<u8>,
+        phantom_functions: Vec<String>,
+        imported_globals: BTreeMap<String, RuntimeValue>,
+    ) -> Result<Self> {
         set_zkwasm_k(k);
 
-        let module = wasmi::Module::from_buffer(&image)?;
+        let linked_image = link_wasi_shim(&image)?;
+        let module = wasmi::Module::from_buffer(&linked_image)?;
+        let phantom_functions = resolve_phantom_functions(module.module(), &phantom_functions)?;
 
-        let loader = Self {
+        let mut loader = Self {
             k,
             module,
+            debug_info: DebugInfo::default(),
             phantom_functions,
+            table_dump: None,
+            step_limit: None,
+            circuit_params: CircuitParams::default(),
+            imported_globals,
             _data: PhantomData,
         };
 
         loader.precheck()?;
         loader.init_env()?;
+        loader.debug_info = loader.load_debug_info(&image)?;
 
         Ok(loader)
     }
 
+    /// Dump tables after each execution, nothing is dumped by default.
+    pub fn with_table_dump(mut self, table_dump: Option<TableDumpOption>) -> Self {
+        self.table_dump = table_dump;
+        self
+    }
+
+    /// Abort `run` once more than `step_limit` instructions are traced,
+    /// the limit is derived from the capacity of the circuit by default.
+    pub fn with_step_limit(mut self, step_limit: Option<usize>) -> Self {
+        self.step_limit = step_limit;
+        self
+    }
+
+    /// Expose the digest of the public inputs and outputs instead of the values, see
+    /// `public_inputs_digest` to recompute it. The mode is carried by the circuits built by
+    /// the loader.
+    pub fn with_public_input_digest(mut self, enabled: bool) -> Self {
+        self.circuit_params.public_input_digest = enabled;
+        self
+    }
+
+    /// Configure only the opcode classes used by the image, the verifying key then rejects
+    /// proofs of images using other classes. The opcode set is carried by the circuits built
+    /// by the loader.
+    pub fn with_opcode_set_from_image(mut self, enabled: bool) -> Result<Self> {
+        self.circuit_params.opcode_set = if enabled {
+            let (mut env, _) = HostEnv::new_with_full_foreign_plugins(
+                vec![],
+                vec![],
+                vec![],
+                Arc::new(Mutex::new(vec![])),
+            );
+            let compiled = self.compile(&mut env)?;
+
+            Some(compiled.tables.itable.opcode_class().into_iter().collect())
+        } else {
+            None
+        };
+
+        Ok(self)
+    }
+
+    pub fn debug_info(&self) -> &DebugInfo {
+        &self.debug_info
+    }
+
+    /// Parameters of the circuits built by the loader, see `with_circuit_params`.
+    pub fn circuit_params(&self) -> &CircuitParams {
+        &self.circuit_params
+    }
+
     pub fn create_vkey(&self, params: &Params<E::G1Affine>) -> Result<VerifyingKey<E::G1Affine>> {
         let circuit = self.circuit_without_witness()?;
 
-        Ok(keygen_vk(&params, &circuit).unwrap())
+        Ok(with_circuit_params(&circuit.params, || {
+            keygen_vk(&params, &circuit).unwrap()
+        }))
     }
 
+    /// The commitments of the image column, which holds the root of the initial heap, and of
+    /// the init memory column of the globals.
     pub fn checksum(&self, params: &Params<E::G1Affine>) -> Result<Vec<E::G1Affine>> {
-        let (env, _) = HostEnv::new_with_full_foreign_plugins(
+        let (mut env, _) = HostEnv::new_with_full_foreign_plugins(
             vec![],
             vec![],
             vec![],
             Arc::new(Mutex::new(vec![])),
         );
-        let compiled = self.compile(&env)?;
+        let compiled = self.compile(&mut env)?;
 
         let table_with_params = CompilationTableWithParams {
             table: &compiled.tables,
@@ -179,8 +361,9 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
             arg.context_inputs,
             arg.context_outputs,
         );
+        env.set_host_call_mode(arg.host_call_mode);
 
-        let compiled_module = self.compile(&env)?;
+        let compiled_module = self.compile(&mut env)?;
 
         compiled_module.dry_run(&mut env)
     }
@@ -192,13 +375,29 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
             arg.context_inputs,
             arg.context_outputs,
         );
+        env.set_host_call_mode(arg.host_call_mode);
+
+        let compiled_module = self.compile(&mut env)?.with_step_limit(self.step_limit);
+
+        let mut result = compiled_module.run(&mut env, wasm_runtime_io)?;
 
-        let compiled_module = self.compile(&env)?;
+        result.time_profile.host_functions = env.time_profile();
 
-        let result = compiled_module.run(&mut env, wasm_runtime_io)?;
+        if log_enabled!(Level::Debug) {
+            result.tables.profile_tables();
+            env.display_time_profile();
+        }
+
+        if let Some(table_dump) = &self.table_dump {
+            result.tables.dump(table_dump)?;
 
-        result.tables.profile_tables();
-        result.tables.write_json(None);
+            if table_dump.tables.contains(&TableKind::Itable) {
+                self.debug_info.write_json(
+                    &result.tables.compilation_tables.itable,
+                    Some(table_dump.dir.clone()),
+                );
+            }
+        }
 
         Ok(result)
     }
@@ -206,36 +405,54 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
     pub fn circuit_with_witness(
         &self,
         arg: ExecutionArg,
-    ) -> Result<(TestCircuit<E::Scalar>, Vec<E::Scalar>)> {
+    ) -> Result<(TestCircuit<E::Scalar>, Vec<Vec<E::Scalar>>)> {
         let execution_result = self.run(arg)?;
 
-        let instance: Vec<E::Scalar> = execution_result
-            .public_inputs_and_outputs
-            .clone()
-            .iter()
-            .map(|v| (*v).into())
-            .collect();
+        Ok(self.circuit_with_execution_result(execution_result))
+    }
+
+    /// Build the circuit from the result of `run`, e.g. to keep its `time_profile`.
+    ///
+    /// The instances are the public inputs and outputs, or their digest if enabled, followed by
+    /// the context digests.
+    pub fn circuit_with_execution_result(
+        &self,
+        execution_result: ExecutionResult<RuntimeValue>,
+    ) -> (TestCircuit<E::Scalar>, Vec<Vec<E::Scalar>>) {
+        let instance: Vec<E::Scalar> = public_instances(
+            &execution_result.public_inputs_and_outputs,
+            self.circuit_params.public_input_digest,
+        );
+
+        let etable = &execution_result.tables.execution_tables.etable;
+        let context_instance =
+            context_instances(&etable.get_context_inputs(), &etable.get_context_outputs());
 
         let builder = ZkWasmCircuitBuilder {
             tables: execution_result.tables,
             public_inputs_and_outputs: execution_result.public_inputs_and_outputs,
+            params: self.circuit_params.clone(),
         };
 
         println!("output:");
         println!("{:?}", execution_result.outputs);
 
-        Ok((builder.build_circuit(), instance))
+        (builder.build_circuit(), vec![instance, context_instance])
     }
 
+    /// Failures are returned as a `DiagnosticReport` which locates them in the trace.
     pub fn mock_test(
         &self,
         circuit: &TestCircuit<E::Scalar>,
-        instances: &Vec<E::Scalar>,
+        instances: &Vec<Vec<E::Scalar>>,
     ) -> Result<()> {
-        let prover = MockProver::run(self.k, circuit, vec![instances.clone()])?;
-        assert_eq!(prover.verify(), Ok(()));
+        let prover = with_circuit_params(&circuit.params, || {
+            MockProver::run(self.k, circuit, instances.clone())
+        })?;
 
-        Ok(())
+        prover
+            .verify()
+            .map_err(|failures| anyhow!(DiagnosticReport::new(circuit, &self.debug_info, failures)))
     }
 
     pub fn create_proof(
@@ -243,17 +460,21 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
         params: &Params<E::G1Affine>,
         vkey: VerifyingKey<E::G1Affine>,
         circuit: TestCircuit<E::Scalar>,
-        instances: &Vec<E::Scalar>,
+        instances: &Vec<Vec<E::Scalar>>,
     ) -> Result<Vec<u8>> {
-        Ok(load_or_create_proof::<E, _>(
-            &params,
-            vkey,
-            circuit,
-            &[instances],
-            None,
-            TranscriptHash::Poseidon,
-            false,
-        ))
+        let circuit_params = circuit.params.clone();
+
+        Ok(with_circuit_params(&circuit_params, || {
+            load_or_create_proof::<E, _>(
+                &params,
+                vkey,
+                circuit,
+                &instances.iter().map(|x| &x[..]).collect::<Vec<_>>()[..],
+                None,
+                TranscriptHash::Poseidon,
+                false,
+            )
+        }))
     }
 
     pub fn init_env(&self) -> Result<()> {
@@ -266,30 +487,33 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
         &self,
         params: &Params<E::G1Affine>,
         vkey: VerifyingKey<E::G1Affine>,
-        instances: Vec<E::Scalar>,
+        instances: Vec<Vec<E::Scalar>>,
         proof: Vec<u8>,
     ) -> Result<()> {
-        let params_verifier: ParamsVerifier<E> = params.verifier(instances.len()).unwrap();
+        let params_verifier: ParamsVerifier<E> = params
+            .verifier(instances.iter().map(|x| x.len()).max().unwrap_or(0))
+            .unwrap();
         let strategy = SingleVerifier::new(&params_verifier);
 
         verify_proof(
             &params_verifier,
             &vkey,
             strategy,
-            &[&[&instances]],
+            &[&instances.iter().map(|x| &x[..]).collect::<Vec<_>>()[..]],
             &mut PoseidonRead::init(&proof[..]),
         )
         .unwrap();
 
         {
-            let img_col_idx = vkey
-                .cs
-                .named_advices
-                .iter()
-                .find(|(k, _)| k == IMAGE_COL_NAME)
-                .unwrap()
-                .1;
-            let img_col_commitment: Vec<E::G1Affine> =
+            let col_idx = |name: &str| {
+                vkey.cs
+                    .named_advices
+                    .iter()
+                    .find(|(k, _)| k == name)
+                    .unwrap()
+                    .1 as usize
+            };
+            let advice_commitments: Vec<E::G1Affine> =
                 get_advice_commitments_from_transcript::<E, _, _>(
                     &vkey,
                     &mut PoseidonRead::init(&proof[..]),
@@ -297,7 +521,12 @@ impl<E: MultiMillerLoop> ZkWasmLoader<E> {
                 .unwrap();
             let checksum = self.checksum(params)?;
 
-            assert!(vec![img_col_commitment[img_col_idx as usize]] == checksum)
+            assert!(
+                vec![
+                    advice_commitments[col_idx(IMAGE_COL_NAME)],
+                    advice_commitments[col_idx(INIT_MEMORY_COL_NAME)]
+                ] == checksum
+            )
         }
 
         Ok(())
@@ -322,7 +551,7 @@ mod tests {
     use super::ZkWasmLoader;
 
     impl ZkWasmLoader<Bn256> {
-        pub(crate) fn bench_test(&self, circuit: TestCircuit<Fr>, instances: Vec<Fr>) {
+        pub(crate) fn bench_test(&self, circuit: TestCircuit<Fr>, instances: Vec<Vec<Fr>>) {
             fn prepare_param(k: u32) -> Params<G1Affine> {
                 let path = PathBuf::from(format!("test_param.{}.data", k));
 
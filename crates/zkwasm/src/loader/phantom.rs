@@ -0,0 +1,345 @@
+//! Resolve and validate phantom functions.
+//!
+//! The instructions of a phantom function are skipped from the trace, only its call and its
+//! returned values are proven. This is sound as long as the function has no other observable
+//! effect, so a phantom function must not write memory or globals, nor call host functions or
+//! functions which are not phantom-safe themselves.
+//!
+//! A phantom function is specified by a pattern:
+//! * `re:<regex>` matches the whole name against a regular expression,
+//! * a pattern containing `*` or `?` is a glob,
+//! * any other pattern is an exact name.
+
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use parity_wasm::elements::ImportCountType;
+use parity_wasm::elements::Instruction;
+use parity_wasm::elements::Internal;
+use parity_wasm::elements::Module;
+use regex::Regex;
+
+use super::err::Error;
+use super::err::PreCheckErr;
+
+enum PhantomPattern {
+    Exact(String),
+    Glob(Vec<char>),
+    Regex(Regex),
+}
+
+impl PhantomPattern {
+    fn parse(pattern: &str) -> Result<Self> {
+        if let Some(re) = pattern.strip_prefix("re:") {
+            Ok(PhantomPattern::Regex(Regex::new(&format!("^(?:{})$", re))?))
+        } else if pattern.contains(['*', '?']) {
+            Ok(PhantomPattern::Glob(pattern.chars().collect()))
+        } else {
+            Ok(PhantomPattern::Exact(pattern.to_owned()))
+        }
+    }
+
+    fn matches(&self, name: &str) -> bool {
+        match self {
+            PhantomPattern::Exact(pattern) => pattern == name,
+            PhantomPattern::Glob(pattern) => {
+                glob_matches(pattern, &name.chars().collect::<Vec<_>>())
+            }
+            PhantomPattern::Regex(re) => re.is_match(name),
+        }
+    }
+}
+
+fn glob_matches(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| glob_matches(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && glob_matches(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && glob_matches(rest, &name[1..]),
+    }
+}
+
+/// Names of the defined functions indexed by function index, from the name section or the
+/// export section.
+fn function_names(module: &Module) -> BTreeMap<u32, String> {
+    let module = match module.clone().parse_names() {
+        Ok(module) => module,
+        Err((_, module)) => module,
+    };
+
+    let mut names = BTreeMap::new();
+
+    if let Some(section) = module.export_section() {
+        for entry in section.entries() {
+            if let Internal::Function(index) = entry.internal() {
+                names.insert(*index, entry.field().to_owned());
+            }
+        }
+    }
+
+    if let Some(section) = module
+        .names_section()
+        .and_then(|section| section.functions())
+    {
+        for (index, name) in section.names().iter() {
+            names.insert(index, name.clone());
+        }
+    }
+
+    let imported_functions = module.import_count(ImportCountType::Function) as u32;
+    names.retain(|index, _| *index >= imported_functions);
+
+    names
+}
+
+enum Visit {
+    /// On the call stack at this depth, assumed safe until the function is done.
+    InProgress(usize),
+    /// `None` if the function is safe, otherwise the reason why it is unsafe.
+    Done(Option<String>),
+}
+
+struct SafetyAnalysis<'a> {
+    module: &'a Module,
+    imported_functions: u32,
+    visited: BTreeMap<u32, Visit>,
+}
+
+impl<'a> SafetyAnalysis<'a> {
+    fn new(module: &'a Module) -> Self {
+        SafetyAnalysis {
+            module,
+            imported_functions: module.import_count(ImportCountType::Function) as u32,
+            visited: BTreeMap::new(),
+        }
+    }
+
+    fn check(&mut self, index: u32) -> Option<String> {
+        self.visit(index, 0).0
+    }
+
+    /// Also returns the lowest depth of the functions in progress the result relies on.
+    fn visit(&mut self, index: u32, depth: usize) -> (Option<String>, usize) {
+        match self.visited.get(&index) {
+            Some(Visit::Done(result)) => return (result.clone(), usize::MAX),
+            // Recursive calls are safe unless another instruction is not.
+            Some(Visit::InProgress(at)) => return (None, *at),
+            None => (),
+        }
+
+        self.visited.insert(index, Visit::InProgress(depth));
+
+        let (result, lowest) = self.check_body(index, depth);
+
+        // A safe result relying on a caller in progress is only known once the caller is done.
+        if result.is_some() || lowest >= depth {
+            self.visited.insert(index, Visit::Done(result.clone()));
+        } else {
+            self.visited.remove(&index);
+        }
+
+        (result, lowest)
+    }
+
+    fn check_body(&mut self, index: u32, depth: usize) -> (Option<String>, usize) {
+        let mut lowest = usize::MAX;
+
+        let body = match self.module.code_section().and_then(|section| {
+            section
+                .bodies()
+                .get((index - self.imported_functions) as usize)
+        }) {
+            Some(body) => body,
+            None => return (None, lowest),
+        };
+
+        for instruction in body.code().elements() {
+            let reason = match instruction {
+                Instruction::I32Store(..)
+                | Instruction::I64Store(..)
+                | Instruction::F32Store(..)
+                | Instruction::F64Store(..)
+                | Instruction::I32Store8(..)
+                | Instruction::I32Store16(..)
+                | Instruction::I64Store8(..)
+                | Instruction::I64Store16(..)
+                | Instruction::I64Store32(..) => Some("writes memory".to_owned()),
+                Instruction::GrowMemory(..) => Some("grows memory".to_owned()),
+                Instruction::SetGlobal(..) => Some("writes globals".to_owned()),
+                Instruction::CallIndirect(..) => Some("calls indirectly".to_owned()),
+                Instruction::Call(callee) if *callee < self.imported_functions => {
+                    Some(format!("calls host function {}", callee))
+                }
+                Instruction::Call(callee) => {
+                    let (reason, at) = self.visit(*callee, depth + 1);
+                    lowest = lowest.min(at);
+
+                    reason.map(|reason| format!("calls function {} which {}", callee, reason))
+                }
+                _ => None,
+            };
+
+            if reason.is_some() {
+                return (reason, lowest);
+            }
+        }
+
+        (None, lowest)
+    }
+}
+
+/// Expand phantom patterns to function names, every pattern must match at least one function
+/// and every matched function must be phantom-safe.
+pub(crate) fn resolve_phantom_functions(
+    module: &Module,
+    patterns: &[String],
+) -> Result<Vec<String>> {
+    let names = function_names(module);
+    let mut analysis = SafetyAnalysis::new(module);
+    let mut phantom_functions = BTreeSet::new();
+
+    for pattern in patterns {
+        let matcher = PhantomPattern::parse(pattern)?;
+        let mut matched = false;
+
+        for (index, name) in names.iter().filter(|(_, name)| matcher.matches(name)) {
+            matched = true;
+
+            if let Some(reason) = analysis.check(*index) {
+                return Err(anyhow!(Error::PreCheck(
+                    PreCheckErr::UnsafePhantomFunction {
+                        name: name.clone(),
+                        reason,
+                    }
+                )));
+            }
+
+            phantom_functions.insert(name.clone());
+        }
+
+        if !matched {
+            return Err(anyhow!(Error::PreCheck(
+                PreCheckErr::PhantomFunctionNotExists(pattern.clone())
+            )));
+        }
+    }
+
+    Ok(phantom_functions.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module(wat: &str) -> Module {
+        parity_wasm::deserialize_buffer(&wabt::wat2wasm(wat).unwrap()).unwrap()
+    }
+
+    const WAT: &str = r#"
+        (module
+            (import "env" "wasm_input" (func $wasm_input (param i32) (result i64)))
+            (memory 1)
+            (global $g (mut i32) (i32.const 0))
+            (func $pure_add (export "pure_add") (param i32 i32) (result i32)
+              local.get 0
+              local.get 1
+              i32.add
+            )
+            (func $pure_twice (export "pure_twice") (param i32) (result i32)
+              local.get 0
+              local.get 0
+              call $pure_add
+            )
+            (func $store (export "store")
+              i32.const 0
+              i32.const 1
+              i32.store
+            )
+            (func $set_global (export "set_global")
+              i32.const 1
+              global.set $g
+            )
+            (func $input (export "input") (result i64)
+              i32.const 0
+              call $wasm_input
+            )
+            (func $calls_store (export "calls_store")
+              call $store
+            )
+            (func $cycle_a (export "cycle_a")
+              call $cycle_b
+              call $store
+            )
+            (func $cycle_b (export "cycle_b")
+              call $cycle_a
+            )
+            (func $recursive (export "recursive") (param i32) (result i32)
+              local.get 0
+              i32.eqz
+              if (result i32)
+                i32.const 0
+              else
+                local.get 0
+                i32.const 1
+                i32.sub
+                call $recursive
+              end
+            )
+        )
+    "#;
+
+    #[test]
+    fn test_phantom_patterns() {
+        let module = module(WAT);
+
+        assert_eq!(
+            resolve_phantom_functions(&module, &["pure_add".to_owned()]).unwrap(),
+            vec!["pure_add"]
+        );
+        assert_eq!(
+            resolve_phantom_functions(&module, &["pure_*".to_owned()]).unwrap(),
+            vec!["pure_add", "pure_twice"]
+        );
+        assert_eq!(
+            resolve_phantom_functions(&module, &["re:pure_(add|tw.ce)".to_owned()]).unwrap(),
+            vec!["pure_add", "pure_twice"]
+        );
+        assert!(resolve_phantom_functions(&module, &["missing".to_owned()]).is_err());
+        assert!(resolve_phantom_functions(&module, &["missing_*".to_owned()]).is_err());
+    }
+
+    #[test]
+    fn test_unsafe_phantom_functions() {
+        let module = module(WAT);
+
+        for name in ["store", "set_global", "input", "calls_store"] {
+            assert!(
+                resolve_phantom_functions(&module, &[name.to_owned()]).is_err(),
+                "{} should be rejected",
+                name
+            );
+        }
+    }
+
+    #[test]
+    fn test_phantom_safety_of_cycles() {
+        let module = module(WAT);
+        let names = function_names(&module);
+        let index_of = |name: &str| {
+            *names
+                .iter()
+                .find(|(_, function)| function.as_str() == name)
+                .unwrap()
+                .0
+        };
+
+        // `cycle_b` is visited while `cycle_a` is in progress, it must not be cached as safe.
+        let mut analysis = SafetyAnalysis::new(&module);
+        assert!(analysis.check(index_of("cycle_a")).is_some());
+        assert!(analysis.check(index_of("cycle_b")).is_some());
+
+        assert!(resolve_phantom_functions(&module, &["recursive".to_owned()]).is_ok());
+    }
+}
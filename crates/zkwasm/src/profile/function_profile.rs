@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::collections::HashMap;
+use std::ops::AddAssign;
+
+use serde::Serialize;
+use specs::etable::EventTableEntry;
+use specs::itable::UnaryOp;
+use specs::mtable::AccessType;
+use specs::step::StepInfo;
+use specs::Tables;
+
+use crate::circuits::bit_table::STEP_SIZE as BIT_TABLE_STEP_ROWS;
+use crate::circuits::etable::EVENT_TABLE_ENTRY_ROWS;
+use crate::circuits::mtable::MEMORY_TABLE_ENTRY_ROWS;
+use crate::loader::debug_info::DebugInfo;
+use crate::runtime::memory_event_of_step;
+
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct RowCost {
+    pub etable_rows: usize,
+    pub mtable_rows: usize,
+    pub bit_table_rows: usize,
+}
+
+impl RowCost {
+    pub fn total(&self) -> usize {
+        self.etable_rows + self.mtable_rows + self.bit_table_rows
+    }
+
+    fn of_entry(entry: &EventTableEntry) -> Self {
+        let writes = memory_event_of_step(entry, &mut 1)
+            .iter()
+            .filter(|mentry| mentry.atype == AccessType::Write)
+            .count();
+
+        let uses_bit_table = matches!(
+            entry.step_info,
+            StepInfo::I32BinBitOp { .. }
+                | StepInfo::I64BinBitOp { .. }
+                | StepInfo::UnaryOp {
+                    class: UnaryOp::Popcnt,
+                    ..
+                }
+        );
+
+        RowCost {
+            etable_rows: EVENT_TABLE_ENTRY_ROWS as usize,
+            mtable_rows: writes * MEMORY_TABLE_ENTRY_ROWS as usize,
+            bit_table_rows: if uses_bit_table {
+                BIT_TABLE_STEP_ROWS
+            } else {
+                0
+            },
+        }
+    }
+}
+
+impl AddAssign for RowCost {
+    fn add_assign(&mut self, rhs: Self) {
+        self.etable_rows += rhs.etable_rows;
+        self.mtable_rows += rhs.mtable_rows;
+        self.bit_table_rows += rhs.bit_table_rows;
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct FunctionSummary {
+    pub fid: u32,
+    pub name: String,
+    pub location: Option<String>,
+    pub calls: usize,
+    /// Rows spent in the function itself.
+    pub self_cost: RowCost,
+    /// Rows spent in the function and its callees.
+    pub total_cost: RowCost,
+}
+
+/// Proving rows attributed to wasm functions and to the call stacks reconstructed from the jtable.
+#[derive(Clone, Debug, Serialize)]
+pub struct FunctionProfile {
+    pub total: RowCost,
+    /// Sorted by the total rows of `self_cost` in descending order.
+    pub functions: Vec<FunctionSummary>,
+    #[serde(skip)]
+    stacks: BTreeMap<Vec<String>, RowCost>,
+}
+
+impl FunctionProfile {
+    pub fn new(tables: &Tables, debug_info: &DebugInfo) -> Self {
+        let name_of = |fid: u32| {
+            debug_info
+                .function_name(fid)
+                .map_or(format!("fid_{}", fid), |name| name.to_owned())
+                .replace(';', ":")
+        };
+
+        let jtable = tables
+            .execution_tables
+            .jtable
+            .entries()
+            .iter()
+            .map(|entry| (entry.eid, entry))
+            .collect::<HashMap<_, _>>();
+
+        // Callers of each frame, outermost first. Frame 0 is the static frame of the entry.
+        let mut callers_of_frame: HashMap<u32, Vec<u32>> = HashMap::from([(0, vec![])]);
+        let mut callers = |last_jump_eid: u32| -> Vec<u32> {
+            let mut pending = vec![];
+            let mut frame_id = last_jump_eid;
+
+            while !callers_of_frame.contains_key(&frame_id) {
+                match jtable.get(&frame_id) {
+                    Some(entry) => {
+                        pending.push(*entry);
+                        frame_id = entry.last_jump_eid;
+                    }
+                    None => {
+                        callers_of_frame.insert(frame_id, vec![]);
+                    }
+                }
+            }
+
+            for entry in pending.into_iter().rev() {
+                let mut stack = callers_of_frame[&entry.last_jump_eid].clone();
+                stack.push(entry.inst.fid);
+                callers_of_frame.insert(entry.eid, stack);
+            }
+
+            callers_of_frame[&last_jump_eid].clone()
+        };
+
+        let mut total = RowCost::default();
+        let mut self_cost: BTreeMap<u32, RowCost> = BTreeMap::new();
+        let mut fid_stacks: BTreeMap<Vec<u32>, RowCost> = BTreeMap::new();
+
+        for entry in tables.execution_tables.etable.entries() {
+            let cost = RowCost::of_entry(entry);
+
+            let mut stack = callers(entry.last_jump_eid);
+            stack.push(entry.inst.fid);
+
+            total += cost;
+            *self_cost.entry(entry.inst.fid).or_default() += cost;
+            *fid_stacks.entry(stack).or_default() += cost;
+        }
+
+        let mut total_cost: BTreeMap<u32, RowCost> = BTreeMap::new();
+        for (stack, cost) in &fid_stacks {
+            // A recursive function is counted once per stack.
+            for fid in stack.iter().collect::<BTreeSet<_>>() {
+                *total_cost.entry(*fid).or_default() += *cost;
+            }
+        }
+
+        let mut calls: BTreeMap<u32, usize> = BTreeMap::new();
+        for entry in tables.execution_tables.jtable.entries() {
+            *calls.entry(entry.callee_fid).or_default() += 1;
+        }
+        for entry in &tables.compilation_tables.static_jtable {
+            if entry.enable {
+                *calls.entry(entry.callee_fid).or_default() += 1;
+            }
+        }
+
+        let mut functions = self_cost
+            .into_iter()
+            .map(|(fid, self_cost)| FunctionSummary {
+                fid,
+                name: name_of(fid),
+                location: debug_info
+                    .function_location(fid)
+                    .map(|location| location.to_string()),
+                calls: calls.get(&fid).cloned().unwrap_or_default(),
+                self_cost,
+                total_cost: total_cost[&fid],
+            })
+            .collect::<Vec<_>>();
+        functions.sort_by(|a, b| b.self_cost.total().cmp(&a.self_cost.total()));
+
+        let mut stacks: BTreeMap<Vec<String>, RowCost> = BTreeMap::new();
+        for (stack, cost) in fid_stacks {
+            *stacks
+                .entry(stack.into_iter().map(name_of).collect())
+                .or_default() += cost;
+        }
+
+        FunctionProfile {
+            total,
+            functions,
+            stacks,
+        }
+    }
+
+    /// Folded stacks weighted by the total rows, one `caller;callee rows` per line.
+    pub fn folded_stacks(&self) -> String {
+        self.stacks
+            .iter()
+            .map(|(stack, cost)| format!("{} {}\n", stack.join(";"), cost.total()))
+            .collect()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
@@ -2,7 +2,9 @@
 macro_rules! exec_with_profile {
     ($desc:expr, $statement: expr) => {{
         let timer = start_timer!($desc);
+        let start = std::time::Instant::now();
         let r = $statement;
+        $crate::profile::time_profile::record_assignment_time(($desc)(), start.elapsed());
         end_timer!(timer);
         r
     }};
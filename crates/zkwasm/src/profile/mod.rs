@@ -6,6 +6,9 @@ mod helper;
 mod instruction_merge;
 mod instruction_statistic;
 
+pub mod function_profile;
+pub mod time_profile;
+
 pub trait Profiler {
     fn profile_tables(&self);
 }
@@ -0,0 +1,58 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::Serialize;
+
+lazy_static! {
+    /// Timings of the latest circuit synthesis, recorded by `exec_with_profile!`.
+    static ref ASSIGNMENT_TIMINGS: Mutex<BTreeMap<String, u128>> = Mutex::new(BTreeMap::new());
+}
+
+pub fn record_assignment_time(desc: &str, duration: Duration) {
+    ASSIGNMENT_TIMINGS
+        .lock()
+        .unwrap()
+        .insert(desc.to_owned(), duration.as_millis());
+}
+
+/// Timings(ms) of each table assignment step of the latest circuit synthesis.
+pub fn assignment_timings() -> BTreeMap<String, u128> {
+    ASSIGNMENT_TIMINGS.lock().unwrap().clone()
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct HostFunctionTime {
+    pub calls: usize,
+    pub total_us: u128,
+    pub mean_us: u128,
+}
+
+impl HostFunctionTime {
+    pub(crate) fn record(&mut self, duration: Duration) {
+        self.calls += 1;
+        self.total_us += duration.as_micros();
+        self.mean_us = self.total_us / self.calls as u128;
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TimeProfileReport {
+    pub trace_generation_ms: u128,
+    pub host_functions: BTreeMap<String, HostFunctionTime>,
+    /// Filled from `assignment_timings()` once the circuit has been synthesized.
+    pub table_assignment_ms: BTreeMap<String, u128>,
+}
+
+impl TimeProfileReport {
+    pub fn with_assignment_timings(self) -> Self {
+        TimeProfileReport {
+            table_assignment_ms: assignment_timings(),
+            ..self
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+}
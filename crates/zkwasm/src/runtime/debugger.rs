@@ -0,0 +1,449 @@
+//! Step through an execution trace and inspect the machine state at any eid.
+//!
+//! The state before executing an etable entry is reconstructed from the memory events of the
+//! `MTable`: the latest write with a smaller eid, or the initial value from the `InitMemoryTable`.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use specs::etable::EventTableEntry;
+use specs::jtable::JumpTableEntry;
+use specs::mtable::AccessType;
+use specs::mtable::LocationType;
+use specs::mtable::MemoryTableEntry;
+use specs::Tables;
+
+use crate::loader::debug_info::DebugInfo;
+
+const WASM_PAGE_SIZE_IN_WORDS: u32 = 65536 / 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Breakpoint {
+    Function(u32),
+    Instruction { fid: u32, iid: u32 },
+}
+
+impl Breakpoint {
+    fn hits(&self, entry: &EventTableEntry) -> bool {
+        match self {
+            Breakpoint::Function(fid) => entry.inst.fid == *fid && entry.inst.iid == 0,
+            Breakpoint::Instruction { fid, iid } => {
+                entry.inst.fid == *fid && entry.inst.iid == *iid
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DebugCommand {
+    Step(usize),
+    Back(usize),
+    Continue,
+    ReverseContinue,
+    Goto(u32),
+    Break(String),
+    Delete(usize),
+    Breakpoints,
+    Info,
+    Backtrace,
+    Stack,
+    Locals,
+    Globals,
+    Heap { offset: u32, count: u32 },
+    Help,
+    Quit,
+}
+
+impl DebugCommand {
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("step");
+        let args = words.collect::<Vec<_>>();
+
+        fn number<T: std::str::FromStr>(
+            arg: Option<&&str>,
+            default: Option<T>,
+        ) -> Result<T, String> {
+            match arg {
+                Some(arg) => arg.parse().map_err(|_| format!("invalid number: {}", arg)),
+                None => default.ok_or_else(|| "missing argument".to_owned()),
+            }
+        }
+
+        Ok(match command {
+            "s" | "step" => DebugCommand::Step(number(args.first(), Some(1))?),
+            "b" | "back" => DebugCommand::Back(number(args.first(), Some(1))?),
+            "c" | "continue" => DebugCommand::Continue,
+            "rc" | "reverse-continue" => DebugCommand::ReverseContinue,
+            "g" | "goto" => DebugCommand::Goto(number(args.first(), None)?),
+            "break" => DebugCommand::Break(
+                args.first()
+                    .ok_or_else(|| "missing breakpoint".to_owned())?
+                    .to_string(),
+            ),
+            "delete" => DebugCommand::Delete(number(args.first(), None)?),
+            "breakpoints" => DebugCommand::Breakpoints,
+            "i" | "info" => DebugCommand::Info,
+            "bt" | "backtrace" => DebugCommand::Backtrace,
+            "stack" => DebugCommand::Stack,
+            "locals" => DebugCommand::Locals,
+            "globals" => DebugCommand::Globals,
+            "heap" => DebugCommand::Heap {
+                offset: number(args.first(), None)?,
+                count: number(args.get(1), Some(1))?,
+            },
+            "h" | "help" => DebugCommand::Help,
+            "q" | "quit" => DebugCommand::Quit,
+            _ => return Err(format!("unknown command: {}", command)),
+        })
+    }
+}
+
+const HELP: &str = "\
+step|s [n]              step forward n entries
+back|b [n]              step backward n entries
+continue|c              run forward to the next breakpoint
+reverse-continue|rc     run backward to the previous breakpoint
+goto|g <eid>            jump to the entry of eid
+break <function|fid|fid:iid>
+                        set a breakpoint on a function entry or an instruction
+delete <n>              delete the n-th breakpoint
+breakpoints             list breakpoints
+info|i                  show the current entry
+backtrace|bt            show the call stack
+stack                   show the operand stack of the current frame
+locals                  show the locals of the current frame
+globals                 show the globals
+heap <offset> [count]   show heap words of 8 bytes starting at offset
+quit|q                  exit";
+
+pub struct TraceDebugger<'a> {
+    tables: &'a Tables,
+    debug_info: &'a DebugInfo,
+    /// Writes and initializations of each location, sorted by `(eid, emid)`.
+    memory: BTreeMap<(LocationType, u32), Vec<&'a MemoryTableEntry>>,
+    jtable: HashMap<u32, &'a JumpTableEntry>,
+    /// Index of the first entry of each `(frame id, fid)`.
+    frame_start: HashMap<(u32, u32), usize>,
+    breakpoints: Vec<Breakpoint>,
+    cursor: usize,
+}
+
+impl<'a> TraceDebugger<'a> {
+    pub fn new(tables: &'a Tables, debug_info: &'a DebugInfo) -> Self {
+        let mut memory: BTreeMap<(LocationType, u32), Vec<&MemoryTableEntry>> = BTreeMap::new();
+        for entry in tables.execution_tables.mtable.entries() {
+            if entry.atype != AccessType::Read {
+                memory
+                    .entry((entry.ltype, entry.offset))
+                    .or_default()
+                    .push(entry);
+            }
+        }
+        for entries in memory.values_mut() {
+            entries.sort_by_key(|entry| (entry.eid, entry.emid));
+        }
+
+        let jtable = tables
+            .execution_tables
+            .jtable
+            .entries()
+            .iter()
+            .map(|entry| (entry.eid, entry))
+            .collect();
+
+        let mut frame_start = HashMap::new();
+        for (index, entry) in tables.execution_tables.etable.entries().iter().enumerate() {
+            frame_start
+                .entry((entry.last_jump_eid, entry.inst.fid))
+                .or_insert(index);
+        }
+
+        TraceDebugger {
+            tables,
+            debug_info,
+            memory,
+            jtable,
+            frame_start,
+            breakpoints: vec![],
+            cursor: 0,
+        }
+    }
+
+    fn entries(&self) -> &'a Vec<EventTableEntry> {
+        self.tables.execution_tables.etable.entries()
+    }
+
+    pub fn current(&self) -> Option<&'a EventTableEntry> {
+        self.entries().get(self.cursor)
+    }
+
+    fn function_name(&self, fid: u32) -> String {
+        self.debug_info
+            .function_name(fid)
+            .map_or(format!("fid {}", fid), |name| {
+                format!("{} (fid {})", name, fid)
+            })
+    }
+
+    /// The value of a location before executing the entry of `eid`.
+    pub fn value_at(&self, ltype: LocationType, offset: u32, eid: u32) -> Option<u64> {
+        let written = self.memory.get(&(ltype, offset)).and_then(|entries| {
+            let index = entries.partition_point(|entry| entry.eid < eid);
+            index.checked_sub(1).map(|index| entries[index].value)
+        });
+
+        written.or_else(|| match ltype {
+            LocationType::Stack => None,
+            _ => self
+                .tables
+                .compilation_tables
+                .imtable
+                .try_find(ltype, offset)
+                .map(|(_, _, value)| value),
+        })
+    }
+
+    /// Stack offsets of the locals and the operands of the current frame.
+    fn frame_layout(&self, entry: &EventTableEntry) -> (Option<Vec<u32>>, Vec<u32>) {
+        let start = self.frame_start[&(entry.last_jump_eid, entry.inst.fid)];
+        let frame_sp = self.entries()[start].sp;
+
+        // The top of the stack is at `sp + 1`, the stack grows downward.
+        let operands = (entry.sp + 1..=frame_sp).collect::<Vec<_>>();
+
+        // Locals are pushed before the first instruction, local 0 is the deepest.
+        let locals = self
+            .debug_info
+            .function(entry.inst.fid)
+            .and_then(|function| function.locals)
+            .map(|count| (0..count).map(|index| frame_sp + count - index).collect());
+
+        (locals, operands)
+    }
+
+    fn format_value(value: Option<u64>) -> String {
+        value.map_or("?".to_owned(), |value| {
+            format!("{:#x} ({})", value, value as i64)
+        })
+    }
+
+    fn info(&self) -> String {
+        match self.current() {
+            Some(entry) => format!(
+                "eid {} in {} iid {}{}: {:?}",
+                entry.eid,
+                self.function_name(entry.inst.fid),
+                entry.inst.iid,
+                self.debug_info
+                    .location(entry.inst.fid, entry.inst.iid)
+                    .map_or(String::new(), |location| format!(" at {}", location)),
+                entry.inst.opcode
+            ),
+            None => "end of trace".to_owned(),
+        }
+    }
+
+    fn backtrace(&self, entry: &EventTableEntry) -> String {
+        let mut frames = vec![format!(
+            "#0 {} iid {}",
+            self.function_name(entry.inst.fid),
+            entry.inst.iid
+        )];
+
+        let mut frame_id = entry.last_jump_eid;
+        while let Some(call) = self.jtable.get(&frame_id) {
+            frames.push(format!(
+                "#{} {} iid {} (eid {})",
+                frames.len(),
+                self.function_name(call.inst.fid),
+                call.inst.iid,
+                call.eid
+            ));
+            frame_id = call.last_jump_eid;
+        }
+
+        frames.join("\n")
+    }
+
+    fn run_to_breakpoint(&mut self, forward: bool) -> String {
+        loop {
+            let next = if forward {
+                self.cursor.checked_add(1)
+            } else {
+                self.cursor.checked_sub(1)
+            };
+
+            match next.filter(|next| *next < self.entries().len()) {
+                Some(next) => self.cursor = next,
+                None => return format!("no breakpoint hit\n{}", self.info()),
+            }
+
+            let entry = &self.entries()[self.cursor];
+            if let Some(index) = self
+                .breakpoints
+                .iter()
+                .position(|breakpoint| breakpoint.hits(entry))
+            {
+                return format!("breakpoint {} hit\n{}", index, self.info());
+            }
+        }
+    }
+
+    fn parse_breakpoint(&self, spec: &str) -> Result<Breakpoint, String> {
+        match spec.split_once(':') {
+            Some((fid, iid)) => Ok(Breakpoint::Instruction {
+                fid: fid.parse().map_err(|_| format!("invalid fid: {}", fid))?,
+                iid: iid.parse().map_err(|_| format!("invalid iid: {}", iid))?,
+            }),
+            None => spec
+                .parse()
+                .ok()
+                .or_else(|| self.debug_info.fid_of_name(spec))
+                .map(Breakpoint::Function)
+                .ok_or_else(|| format!("unknown function: {}", spec)),
+        }
+    }
+
+    /// Execute a command and render its output, returns `None` on `quit`.
+    pub fn execute(&mut self, command: DebugCommand) -> Option<String> {
+        let last = self.entries().len().saturating_sub(1);
+
+        let output = match command {
+            DebugCommand::Step(n) => {
+                self.cursor = self.cursor.saturating_add(n).min(last);
+                self.info()
+            }
+            DebugCommand::Back(n) => {
+                self.cursor = self.cursor.saturating_sub(n);
+                self.info()
+            }
+            DebugCommand::Continue => self.run_to_breakpoint(true),
+            DebugCommand::ReverseContinue => self.run_to_breakpoint(false),
+            DebugCommand::Goto(eid) => {
+                match self.entries().iter().position(|entry| entry.eid == eid) {
+                    Some(index) => {
+                        self.cursor = index;
+                        self.info()
+                    }
+                    None => format!("no entry of eid {}", eid),
+                }
+            }
+            DebugCommand::Break(spec) => match self.parse_breakpoint(&spec) {
+                Ok(breakpoint) => {
+                    self.breakpoints.push(breakpoint);
+                    format!("breakpoint {} set", self.breakpoints.len() - 1)
+                }
+                Err(err) => err,
+            },
+            DebugCommand::Delete(index) => {
+                if index < self.breakpoints.len() {
+                    self.breakpoints.remove(index);
+                    format!("breakpoint {} deleted", index)
+                } else {
+                    format!("no breakpoint {}", index)
+                }
+            }
+            DebugCommand::Breakpoints => self
+                .breakpoints
+                .iter()
+                .enumerate()
+                .map(|(index, breakpoint)| match breakpoint {
+                    Breakpoint::Function(fid) => {
+                        format!("{}: {}", index, self.function_name(*fid))
+                    }
+                    Breakpoint::Instruction { fid, iid } => {
+                        format!("{}: {} iid {}", index, self.function_name(*fid), iid)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            DebugCommand::Info => self.info(),
+            DebugCommand::Help => HELP.to_owned(),
+            DebugCommand::Quit => return None,
+            DebugCommand::Backtrace
+            | DebugCommand::Stack
+            | DebugCommand::Locals
+            | DebugCommand::Globals
+            | DebugCommand::Heap { .. } => match self.current() {
+                Some(entry) => self.inspect(command, entry),
+                None => "end of trace".to_owned(),
+            },
+        };
+
+        Some(output)
+    }
+
+    fn inspect(&self, command: DebugCommand, entry: &EventTableEntry) -> String {
+        let eid = entry.eid;
+
+        match command {
+            DebugCommand::Backtrace => self.backtrace(entry),
+            DebugCommand::Stack => {
+                let (_, operands) = self.frame_layout(entry);
+
+                operands
+                    .iter()
+                    .enumerate()
+                    .map(|(depth, offset)| {
+                        format!(
+                            "[{}] {}",
+                            depth,
+                            Self::format_value(self.value_at(LocationType::Stack, *offset, eid))
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            DebugCommand::Locals => match self.frame_layout(entry).0 {
+                Some(locals) => locals
+                    .iter()
+                    .enumerate()
+                    .map(|(index, offset)| {
+                        format!(
+                            "local {}: {}",
+                            index,
+                            Self::format_value(self.value_at(LocationType::Stack, *offset, eid))
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+                None => "locals are unknown for this function".to_owned(),
+            },
+            DebugCommand::Globals => self
+                .tables
+                .compilation_tables
+                .imtable
+                .filter(LocationType::Global)
+                .iter()
+                .map(|global| {
+                    format!(
+                        "global {}: {}",
+                        global.start_offset,
+                        Self::format_value(self.value_at(
+                            LocationType::Global,
+                            global.start_offset,
+                            eid
+                        ))
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            DebugCommand::Heap { offset, count } => {
+                let limit = entry.allocated_memory_pages * WASM_PAGE_SIZE_IN_WORDS;
+
+                (offset..offset.saturating_add(count).min(limit))
+                    .map(|offset| {
+                        format!(
+                            "{:#010x}: {}",
+                            offset as u64 * 8,
+                            Self::format_value(self.value_at(LocationType::Heap, offset, eid))
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            }
+            _ => unreachable!(),
+        }
+    }
+}
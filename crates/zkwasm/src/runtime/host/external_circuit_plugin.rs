@@ -39,6 +39,20 @@ impl ExternalCircuitEnv {
     ) -> Rc<ForeignPlugin> {
         Rc::new(ForeignPlugin {
             ctx: Rc::new(RefCell::new(ctx)),
+            persistent: false,
+        })
+    }
+
+    /// Register a plugin whose state outlives the execution, such as a storage. Its functions
+    /// are invoked in replay mode too, their results are checked against the record.
+    pub fn register_persistent_plugin(
+        &mut self,
+        _name: &str,
+        ctx: Box<dyn ForeignContext>,
+    ) -> Rc<ForeignPlugin> {
+        Rc::new(ForeignPlugin {
+            ctx: Rc::new(RefCell::new(ctx)),
+            persistent: true,
         })
     }
 
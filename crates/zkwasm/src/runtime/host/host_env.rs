@@ -7,15 +7,25 @@ use std::time::Instant;
 use log::debug;
 use specs::host_function::HostFunctionDesc;
 use wasmi::Externals;
+use wasmi::GlobalDescriptor;
+use wasmi::GlobalInstance;
+use wasmi::GlobalRef;
 use wasmi::ModuleImportResolver;
 use wasmi::RuntimeArgs;
 use wasmi::RuntimeValue;
 use wasmi::Trap;
+use wasmi::TrapKind;
 
+use crate::profile::time_profile::HostFunctionTime;
+use crate::runtime::host::ForeignContext;
+use crate::runtime::host::HostCallSequenceError;
 use crate::runtime::host::HostFunctionExecutionEnv;
 
 use super::external_circuit_plugin::ExternalCircuitEnv;
 use super::internal_circuit_plugin::InternalCircuitEnv;
+use super::record::HostCallMode;
+use super::record::HostCallRecord;
+use super::record::HostCallReplayError;
 use super::HostFunction;
 
 pub struct HostEnv {
@@ -25,8 +35,16 @@ pub struct HostEnv {
     finalized: Rc<RefCell<bool>>,
     cached_lookup: Option<HashMap<usize, HostFunction>>,
 
+    /// Initial values of the globals imported from `env`
+    globals: HashMap<String, RuntimeValue>,
+
     /// Profile foreign function time
-    time_profile: BTreeMap<String, u128>,
+    time_profile: BTreeMap<String, HostFunctionTime>,
+
+    host_call_mode: HostCallMode,
+
+    /// Context of the plugin whose call sequence is open, see `ForeignContext::in_sequence`.
+    open_sequence: Option<Rc<RefCell<Box<dyn ForeignContext>>>>,
 }
 
 impl HostEnv {
@@ -47,7 +65,10 @@ impl HostEnv {
             external_env: ExternalCircuitEnv::new(finalized.clone()),
             cached_lookup: None,
             finalized,
+            globals: HashMap::new(),
             time_profile: BTreeMap::new(),
+            host_call_mode: HostCallMode::Native,
+            open_sequence: None,
         }
     }
 
@@ -73,6 +94,7 @@ impl HostEnv {
                         execution_env: HostFunctionExecutionEnv {
                             ctx: op.plugin.ctx.clone(),
                             cb: op.cb.clone(),
+                            persistent: op.plugin.persistent,
                         },
                     },
                 )
@@ -101,6 +123,7 @@ impl HostEnv {
                             .ctx
                             .clone(),
                         cb: op.cb.clone(),
+                        persistent: false,
                     },
                 },
             );
@@ -114,6 +137,14 @@ impl HostEnv {
         *finalized = true;
     }
 
+    /// Provides the initial value of the global `name` imported from `env`. Globals hold no
+    /// op index, they can be registered after finalizing.
+    pub fn register_global(&mut self, name: &str, value: RuntimeValue) {
+        self.globals
+            .insert(name.to_owned(), value)
+            .map(|_| panic!("conflicting global {}", name));
+    }
+
     pub fn function_description_table(&self) -> HashMap<usize, HostFunctionDesc> {
         assert!(
             *self.finalized.borrow(),
@@ -128,10 +159,23 @@ impl HostEnv {
             .collect()
     }
 
+    /// Sets how external host functions are served, see `HostCallMode`.
+    pub fn set_host_call_mode(&mut self, mode: HostCallMode) {
+        self.host_call_mode = mode;
+    }
+
+    /// Call counts and execution time of each invoked host function.
+    pub fn time_profile(&self) -> BTreeMap<String, HostFunctionTime> {
+        self.time_profile.clone()
+    }
+
     pub fn display_time_profile(&self) {
-        debug!("Execution time(ms) of Foreign Functions:");
-        self.time_profile.iter().for_each(|(func, ms)| {
-            debug!("{}:\t{}", func, ms);
+        debug!("Execution time(us) of Foreign Functions:");
+        self.time_profile.iter().for_each(|(func, time)| {
+            debug!(
+                "{}:\t{} calls\t{} total\t{} mean",
+                func, time.calls, time.total_us, time.mean_us
+            );
         })
     }
 }
@@ -146,6 +190,28 @@ impl ModuleImportResolver for HostEnv {
             .resolve_func(function_name, signature)
             .or_else(|_| self.internal_env.resolve_func(function_name, signature))
     }
+
+    fn resolve_global(
+        &self,
+        global_name: &str,
+        descriptor: &GlobalDescriptor,
+    ) -> Result<GlobalRef, wasmi::Error> {
+        match self.globals.get(global_name) {
+            Some(value) if value.value_type() == descriptor.value_type() => {
+                Ok(GlobalInstance::alloc(*value, descriptor.is_mutable()))
+            }
+            Some(value) => Err(wasmi::Error::Instantiation(format!(
+                "Global `{}` of type {:?} doesnt match expected type {:?}",
+                global_name,
+                value.value_type(),
+                descriptor.value_type()
+            ))),
+            None => Err(wasmi::Error::Instantiation(format!(
+                "Global {} not found",
+                global_name
+            ))),
+        }
+    }
 }
 
 impl Externals for HostEnv {
@@ -157,8 +223,67 @@ impl Externals for HostEnv {
         match self.cached_lookup.as_ref().unwrap().get(&index).clone() {
             Some(HostFunction {
                 desc,
-                execution_env: HostFunctionExecutionEnv { ctx, cb },
+                execution_env:
+                    HostFunctionExecutionEnv {
+                        ctx,
+                        cb,
+                        persistent,
+                    },
             }) => {
+                let replay_error =
+                    |err: HostCallReplayError| Trap::new(TrapKind::Host(Box::new(err)));
+
+                if let HostFunctionDesc::External { op, .. } = desc {
+                    let sequence_error = |reason: String| {
+                        Trap::new(TrapKind::Host(Box::new(HostCallSequenceError {
+                            function: desc.name().to_owned(),
+                            reason,
+                        })))
+                    };
+
+                    if let Some(open) = &self.open_sequence {
+                        if !Rc::ptr_eq(open, ctx) {
+                            return Err(sequence_error(
+                                "the call sequence of another plugin is open".to_owned(),
+                            ));
+                        }
+                    }
+
+                    let mut sequence = (*ctx).borrow_mut();
+                    sequence
+                        .sequence_call(*op, args.nth_checked::<u64>(0).ok())
+                        .map_err(sequence_error)?;
+                    self.open_sequence = sequence.in_sequence().then(|| ctx.clone());
+                }
+
+                // Only invocations of external host functions are recorded.
+                let mut record = match (&self.host_call_mode, desc) {
+                    (HostCallMode::Native, _) | (_, HostFunctionDesc::Internal { .. }) => None,
+                    _ => Some(HostCallRecord::new(desc.name(), &args)),
+                };
+
+                if let (HostCallMode::Replay(records), Some(record)) =
+                    (&mut self.host_call_mode, record.as_mut())
+                {
+                    let recorded = records.pop_front().ok_or_else(|| {
+                        replay_error(HostCallReplayError::Exhausted {
+                            function: desc.name().to_owned(),
+                        })
+                    })?;
+
+                    record.ret = recorded.ret;
+                    if *record != recorded {
+                        return Err(replay_error(HostCallReplayError::Diverged {
+                            recorded,
+                            invoked: record.clone(),
+                        }));
+                    }
+
+                    if !persistent {
+                        return Ok(recorded.ret.map(|ret| ret.into()));
+                    }
+                }
+
                 let mut ctx = (*ctx).borrow_mut();
                 let ctx = ctx.as_mut();
 
@@ -166,10 +291,31 @@ impl Externals for HostEnv {
                 let r = cb(ctx, args);
                 let duration = start.elapsed();
 
+                if let Some(mut record) = record {
+                    let ret = r.as_ref().map(|ret| ret.into());
+
+                    match &self.host_call_mode {
+                        HostCallMode::Record(records) => {
+                            record.ret = ret;
+                            records.lock().unwrap().push(record);
+                        }
+                        HostCallMode::Replay(_) if record.ret != ret => {
+                            return Err(replay_error(HostCallReplayError::Diverged {
+                                invoked: HostCallRecord {
+                                    ret,
+                                    ..record.clone()
+                                },
+                                recorded: record,
+                            }));
+                        }
+                        _ => (),
+                    }
+                }
+
                 self.time_profile
                     .entry(desc.name().to_string())
-                    .and_modify(|d| *d += duration.as_millis())
-                    .or_insert(duration.as_millis());
+                    .or_default()
+                    .record(duration);
 
                 Ok(r)
             }
@@ -3,12 +3,15 @@ use downcast_rs::Downcast;
 use specs::external_host_call_table::ExternalHostCallSignature;
 use specs::host_function::HostFunctionDesc;
 use std::cell::RefCell;
+use std::fmt::Display;
 use std::rc::Rc;
+use wasmi::HostError;
 use wasmi::RuntimeArgs;
 use wasmi::RuntimeValue;
 use wasmi::Signature;
 
 pub mod host_env;
+pub mod record;
 
 mod external_circuit_plugin;
 mod internal_circuit_plugin;
@@ -49,17 +52,55 @@ impl MatchForeignOpSignature for ExternalHostCallSignature {
 /// impl ForeignContext for Context {
 /// }
 /// ```
-pub trait ForeignContext: Downcast {}
+pub trait ForeignContext: Downcast {
+    /// Checks the call to the external host function `op` with the argument `arg` before it
+    /// is served, in replay mode too. A plugin whose circuit requires its calls to follow a
+    /// sequence tracks the sequence here, the rejection aborts the execution with a
+    /// `HostCallSequenceError`.
+    fn sequence_call(&mut self, _op: usize, _arg: Option<u64>) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Whether the calls so far leave a sequence open. Until it is closed, the calls to the
+    /// external host functions of other plugins are rejected.
+    fn in_sequence(&self) -> bool {
+        false
+    }
+}
 impl_downcast!(ForeignContext);
 
+/// A call to an external host function rejected by the call sequence of a plugin.
+#[derive(Clone, Debug)]
+pub struct HostCallSequenceError {
+    pub function: String,
+    pub reason: String,
+}
+
+impl Display for HostCallSequenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "host call {} is out of sequence: {}",
+            self.function, self.reason
+        )
+    }
+}
+
+impl std::error::Error for HostCallSequenceError {}
+
+impl HostError for HostCallSequenceError {}
+
 pub struct ForeignPlugin {
     ctx: Rc<RefCell<Box<dyn ForeignContext>>>,
+    persistent: bool,
 }
 
 #[derive(Clone)]
 struct HostFunctionExecutionEnv {
     ctx: Rc<RefCell<Box<dyn ForeignContext>>>,
     cb: Rc<dyn Fn(&mut dyn ForeignContext, RuntimeArgs) -> Option<RuntimeValue>>,
+    /// Invoked in replay mode too, see `ExternalCircuitEnv::register_persistent_plugin`.
+    persistent: bool,
 }
 
 #[derive(Clone)]
@@ -0,0 +1,233 @@
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use std::fmt::Display;
+
+use serde::Deserialize;
+use serde::Serialize;
+use wasmi::HostError;
+use wasmi::RuntimeArgs;
+use wasmi::RuntimeValue;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum HostCallValue {
+    I32(i32),
+    I64(i64),
+}
+
+impl From<&RuntimeValue> for HostCallValue {
+    fn from(value: &RuntimeValue) -> Self {
+        match value {
+            RuntimeValue::I32(v) => HostCallValue::I32(*v),
+            RuntimeValue::I64(v) => HostCallValue::I64(*v),
+            _ => unreachable!("float is not supported"),
+        }
+    }
+}
+
+impl From<HostCallValue> for RuntimeValue {
+    fn from(value: HostCallValue) -> Self {
+        match value {
+            HostCallValue::I32(v) => RuntimeValue::I32(v),
+            HostCallValue::I64(v) => RuntimeValue::I64(v),
+        }
+    }
+}
+
+/// An invocation of an external host function.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HostCallRecord {
+    pub function: String,
+    pub args: Vec<HostCallValue>,
+    pub ret: Option<HostCallValue>,
+}
+
+impl HostCallRecord {
+    pub(super) fn new(function: &str, args: &RuntimeArgs) -> Self {
+        HostCallRecord {
+            function: function.to_owned(),
+            args: args.as_ref().iter().map(|arg| arg.into()).collect(),
+            ret: None,
+        }
+    }
+}
+
+/// How external host functions are served, internal ones always run natively.
+#[derive(Clone, Default)]
+pub enum HostCallMode {
+    /// Invoke the callbacks.
+    #[default]
+    Native,
+    /// Invoke the callbacks and append every invocation to the records.
+    Record(Arc<Mutex<Vec<HostCallRecord>>>),
+    /// Return the recorded values without invoking the callbacks. The states of the external
+    /// plugins are left untouched, except for the persistent ones whose callbacks still run,
+    /// see `ExternalCircuitEnv::register_persistent_plugin`. A mismatch between the invocation
+    /// and the record aborts the execution with a `HostCallReplayError`.
+    Replay(VecDeque<HostCallRecord>),
+}
+
+#[derive(Clone, Debug)]
+pub enum HostCallReplayError {
+    /// The guest invokes more external host functions than recorded.
+    Exhausted { function: String },
+    /// The invocation or its result differs from the record.
+    Diverged {
+        recorded: HostCallRecord,
+        invoked: HostCallRecord,
+    },
+}
+
+impl Display for HostCallReplayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostCallReplayError::Exhausted { function } => {
+                write!(f, "no recorded host call left for {}", function)
+            }
+            HostCallReplayError::Diverged { recorded, invoked } => write!(
+                f,
+                "host call diverges from the record: recorded {:?}, invoked {:?}",
+                recorded, invoked
+            ),
+        }
+    }
+}
+
+impl std::error::Error for HostCallReplayError {}
+
+impl HostError for HostCallReplayError {}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use specs::external_host_call_table::ExternalHostCallSignature;
+
+    use super::HostCallMode;
+    use super::HostCallRecord;
+    use super::HostCallValue;
+    use crate::foreign::require_helper::register_require_foreign;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::host::ForeignContext;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::test_circuit_with_env;
+
+    struct Counter(i64);
+    impl ForeignContext for Counter {}
+
+    fn env_with_counter(
+        cb: Rc<dyn Fn(&mut dyn ForeignContext, wasmi::RuntimeArgs) -> Option<wasmi::RuntimeValue>>,
+        mode: HostCallMode,
+    ) -> HostEnv {
+        let mut env = HostEnv::new();
+        let plugin = env
+            .external_env
+            .register_plugin("foreign_counter", Box::new(Counter(0)));
+        env.external_env.register_function(
+            "next",
+            0,
+            ExternalHostCallSignature::Return,
+            plugin,
+            cb,
+        );
+        register_require_foreign(&mut env);
+        env.finalize();
+        env.set_host_call_mode(mode);
+
+        env
+    }
+
+    #[test]
+    fn test_host_call_record_then_replay() {
+        let textual_repr = r#"
+                (module
+                    (import "env" "next" (func $next (result i64)))
+                    (import "env" "require" (func $require (param i32)))
+                    (export "main" (func $main))
+                    (func $main
+                        (call $require (i64.eq (call $next) (i64.const 1)))
+                        (call $require (i64.eq (call $next) (i64.const 2)))
+                    )
+                )
+            "#;
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+        let records = Arc::new(Mutex::new(vec![]));
+        let env = env_with_counter(
+            Rc::new(
+                |context: &mut dyn ForeignContext, _args: wasmi::RuntimeArgs| {
+                    let context = context.downcast_mut::<Counter>().unwrap();
+                    context.0 += 1;
+
+                    Some(wasmi::RuntimeValue::I64(context.0))
+                },
+            ),
+            HostCallMode::Record(records.clone()),
+        );
+        test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm.clone(), "main").unwrap();
+
+        let records = records.lock().unwrap().clone();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[1].function, "next");
+        assert_eq!(records[1].ret, Some(HostCallValue::I64(2)));
+
+        let env = env_with_counter(
+            Rc::new(
+                |_context: &mut dyn ForeignContext, _args: wasmi::RuntimeArgs| {
+                    unreachable!("external host functions are not invoked in replay mode")
+                },
+            ),
+            HostCallMode::Replay(records.into()),
+        );
+        test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "main").unwrap();
+    }
+
+    #[test]
+    fn test_host_call_replay_divergence_is_an_error() {
+        let textual_repr = r#"
+                (module
+                    (import "env" "next" (func $next (result i64)))
+                    (export "main" (func $main))
+                    (func $main
+                        (drop (call $next))
+                        (drop (call $next))
+                    )
+                )
+            "#;
+        let wasm = wabt::wat2wasm(&textual_repr).expect("failed to parse wat");
+
+        let replay_with = |records: Vec<HostCallRecord>| {
+            let env = env_with_counter(
+                Rc::new(
+                    |_context: &mut dyn ForeignContext, _args: wasmi::RuntimeArgs| {
+                        unreachable!("external host functions are not invoked in replay mode")
+                    },
+                ),
+                HostCallMode::Replay(records.into()),
+            );
+
+            test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm.clone(), "main")
+        };
+
+        let next = HostCallRecord {
+            function: "next".to_owned(),
+            args: vec![],
+            ret: Some(HostCallValue::I64(1)),
+        };
+
+        assert!(replay_with(vec![next.clone(), next.clone()]).is_ok());
+
+        let err = replay_with(vec![next.clone()]).unwrap_err();
+        assert!(format!("{:?}", err).contains("no recorded host call left for next"));
+
+        let other = HostCallRecord {
+            function: "other".to_owned(),
+            ..next.clone()
+        };
+        let err = replay_with(vec![next, other]).unwrap_err();
+        assert!(format!("{:?}", err).contains("host call diverges from the record"));
+    }
+}
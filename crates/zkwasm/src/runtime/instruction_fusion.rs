@@ -0,0 +1,172 @@
+//! Fuse an operand producing instruction with the `Bin` consuming it.
+//!
+//! `Const` and `LocalGet` directly followed by a `Bin` of the same type are replaced by
+//! `BinConst` and `BinLocal` in the itable. The `Bin` is kept so that a branch may still
+//! target it, a fused instruction continues at `iid + 2`.
+//!
+//! The trace is rewritten accordingly: each execution of a fused instruction merges the
+//! entry of the operand with the entry of the `Bin`, and eids are renumbered.
+
+use std::collections::HashMap;
+
+use specs::etable::EventTable;
+use specs::etable::EventTableEntry;
+use specs::itable::InstructionTable;
+use specs::itable::InstructionTableEntry;
+use specs::itable::Opcode;
+use specs::jtable::JumpTable;
+use specs::step::StepInfo;
+
+fn fuse_opcode(opcode: &Opcode, next: &Opcode) -> Option<Opcode> {
+    match (opcode, next) {
+        (
+            Opcode::Const { vtype, value },
+            Opcode::Bin {
+                class,
+                vtype: bin_vtype,
+            },
+        ) if vtype == bin_vtype => Some(Opcode::BinConst {
+            class: *class,
+            vtype: *vtype,
+            value: *value,
+        }),
+        (
+            Opcode::LocalGet { vtype, offset },
+            Opcode::Bin {
+                class,
+                vtype: bin_vtype,
+            },
+        ) if vtype == bin_vtype => Some(Opcode::BinLocal {
+            class: *class,
+            vtype: *vtype,
+            offset: *offset,
+        }),
+        _ => None,
+    }
+}
+
+pub fn fuse_itable(itable: &InstructionTable) -> InstructionTable {
+    let entries = itable.entries();
+
+    InstructionTable::new(
+        entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| {
+                entries
+                    .get(index + 1)
+                    .filter(|next| next.fid == entry.fid && next.iid == entry.iid + 1)
+                    .and_then(|next| fuse_opcode(&entry.opcode, &next.opcode))
+                    .map_or_else(
+                        || entry.clone(),
+                        |opcode| InstructionTableEntry {
+                            fid: entry.fid,
+                            iid: entry.iid,
+                            opcode,
+                        },
+                    )
+            })
+            .collect(),
+    )
+}
+
+fn bin_operands(step_info: &StepInfo) -> (u64, u64, u64) {
+    match step_info {
+        StepInfo::I32BinOp {
+            left, right, value, ..
+        } => (
+            *left as u32 as u64,
+            *right as u32 as u64,
+            *value as u32 as u64,
+        ),
+        StepInfo::I64BinOp {
+            left, right, value, ..
+        } => (*left as u64, *right as u64, *value as u64),
+        _ => unreachable!(),
+    }
+}
+
+fn fuse_step(opcode: &Opcode, operand: &StepInfo, bin: &StepInfo) -> StepInfo {
+    let (left, right, value) = bin_operands(bin);
+
+    match (opcode, operand) {
+        (Opcode::BinConst { class, vtype, .. }, StepInfo::I32Const { .. })
+        | (Opcode::BinConst { class, vtype, .. }, StepInfo::I64Const { .. }) => {
+            StepInfo::BinConst {
+                class: *class,
+                vtype: *vtype,
+                left,
+                right,
+                value,
+            }
+        }
+        (Opcode::BinLocal { class, vtype, .. }, StepInfo::GetLocal { depth, .. }) => {
+            StepInfo::BinLocal {
+                class: *class,
+                vtype: *vtype,
+                depth: *depth,
+                left,
+                right,
+                value,
+            }
+        }
+        _ => unreachable!(),
+    }
+}
+
+/// Rewrite the trace against the itable returned by `fuse_itable`.
+pub fn fuse_execution_trace(
+    itable: &InstructionTable,
+    etable: &EventTable,
+    jtable: &JumpTable,
+) -> (EventTable, JumpTable) {
+    let fused = itable
+        .entries()
+        .iter()
+        .filter(|entry| {
+            matches!(
+                entry.opcode,
+                Opcode::BinConst { .. } | Opcode::BinLocal { .. }
+            )
+        })
+        .map(|entry| ((entry.fid, entry.iid), entry))
+        .collect::<HashMap<_, _>>();
+
+    let mut entries: Vec<EventTableEntry> = vec![];
+    let mut eid_map = HashMap::from([(0, 0)]);
+
+    let mut iter = etable.entries().iter();
+    while let Some(entry) = iter.next() {
+        let eid = entries.len() as u32 + 1;
+        eid_map.insert(entry.eid, eid);
+
+        let entry = match fused.get(&(entry.inst.fid, entry.inst.iid)) {
+            Some(inst) => {
+                let bin = iter.next().unwrap();
+
+                EventTableEntry {
+                    inst: (*inst).clone(),
+                    step_info: fuse_step(&inst.opcode, &entry.step_info, &bin.step_info),
+                    ..entry.clone()
+                }
+            }
+            None => entry.clone(),
+        };
+
+        entries.push(EventTableEntry {
+            eid,
+            last_jump_eid: eid_map[&entry.last_jump_eid],
+            ..entry
+        });
+    }
+
+    let mut fused_jtable = JumpTable::default();
+    for entry in jtable.entries() {
+        let mut entry = entry.clone();
+        entry.eid = eid_map[&entry.eid];
+        entry.last_jump_eid = eid_map[&entry.last_jump_eid];
+        fused_jtable.push(entry);
+    }
+
+    (EventTable::new(entries), fused_jtable)
+}
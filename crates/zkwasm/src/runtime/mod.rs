@@ -1,4 +1,5 @@
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::rc::Rc;
 
 use specs::etable::EventTableEntry;
@@ -12,8 +13,11 @@ use specs::CompilationTable;
 use specs::Tables;
 
 use self::wasmi_interpreter::WasmiRuntime;
+use crate::profile::time_profile::TimeProfileReport;
 
+pub mod debugger;
 pub mod host;
+pub mod instruction_fusion;
 pub mod wasmi_interpreter;
 
 pub struct CompiledImage<I, T> {
@@ -21,6 +25,17 @@ pub struct CompiledImage<I, T> {
     pub tables: CompilationTable,
     pub instance: I,
     pub tracer: Rc<RefCell<T>>,
+    /// Maximal number of instructions to trace, derived from the table capacity by default.
+    pub step_limit: Option<usize>,
+    /// Names of the exported globals
+    pub exported_globals: Vec<String>,
+}
+
+impl<I, T> CompiledImage<I, T> {
+    pub fn with_step_limit(mut self, step_limit: Option<usize>) -> Self {
+        self.step_limit = step_limit;
+        self
+    }
 }
 
 #[derive(Clone)]
@@ -29,6 +44,9 @@ pub struct ExecutionResult<R> {
     pub result: Option<R>,
     pub public_inputs_and_outputs: Vec<u64>,
     pub outputs: Vec<u64>,
+    /// Values of the exported globals once the execution terminates
+    pub exported_globals: BTreeMap<String, R>,
+    pub time_profile: TimeProfileReport,
 }
 
 // TODO: use feature
@@ -1018,6 +1036,50 @@ pub fn memory_event_of_step(event: &EventTableEntry, emid: &mut u32) -> Vec<Memo
             &[*value as u64],
             &[*result as u64],
         ),
+
+        StepInfo::BinConst {
+            vtype, left, value, ..
+        } => mem_op_from_stack_only_step(
+            sp_before_execution,
+            eid,
+            emid,
+            *vtype,
+            *vtype,
+            &[*left],
+            &[*value],
+        ),
+        StepInfo::BinLocal {
+            vtype,
+            depth,
+            left,
+            right,
+            value,
+            ..
+        } => {
+            let read = MemoryTableEntry {
+                eid,
+                emid: *emid,
+                offset: sp_before_execution + depth,
+                ltype: LocationType::Stack,
+                atype: AccessType::Read,
+                vtype: *vtype,
+                is_mutable: true,
+                value: *right,
+            };
+            *emid = (*emid).checked_add(1).unwrap();
+
+            let mut ops = vec![read];
+            ops.append(&mut mem_op_from_stack_only_step(
+                sp_before_execution,
+                eid,
+                emid,
+                *vtype,
+                *vtype,
+                &[*left],
+                &[*value],
+            ));
+            ops
+        }
     }
 }
 
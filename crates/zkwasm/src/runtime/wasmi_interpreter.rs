@@ -1,16 +1,31 @@
 use std::cell::RefCell;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
+use std::panic::catch_unwind;
+use std::panic::resume_unwind;
+use std::panic::AssertUnwindSafe;
 use std::rc::Rc;
+use std::time::Instant;
 
+use crate::circuits::capacity::CapacityTable;
+use crate::circuits::capacity::TraceCapacity;
+use crate::circuits::capacity::TraceCapacityError;
 use crate::circuits::config::zkwasm_k;
+use crate::profile::time_profile::TimeProfileReport;
+use crate::runtime::instruction_fusion::fuse_execution_trace;
+use crate::runtime::instruction_fusion::fuse_itable;
 use crate::runtime::memory_event_of_step;
+use anyhow::anyhow;
 use anyhow::Result;
+use parity_wasm::elements::Internal;
+use specs::etable::EventTableLimitExceeded;
 use specs::host_function::HostFunctionDesc;
 use specs::jtable::StaticFrameEntry;
 use specs::mtable::MTable;
 use specs::CompilationTable;
 use specs::ExecutionTable;
 use specs::Tables;
+use wasmi::ExternVal;
 use wasmi::Externals;
 use wasmi::ImportResolver;
 use wasmi::ModuleInstance;
@@ -33,6 +48,29 @@ impl WasmRuntimeIO {
     }
 }
 
+/// Runs `f` with the event table of `tracer` bounded to `step_limit` entries, so that the
+/// interpreter is aborted within the step exceeding it, see `EventTableEntries::push`.
+fn with_step_limit<T>(
+    tracer: &Rc<RefCell<wasmi::tracer::Tracer>>,
+    capacity: &TraceCapacity,
+    step_limit: usize,
+    f: impl FnOnce() -> T,
+) -> Result<T, TraceCapacityError> {
+    tracer.borrow_mut().etable.set_limit(Some(step_limit));
+    let result = catch_unwind(AssertUnwindSafe(f));
+    tracer.borrow_mut().etable.set_limit(None);
+
+    result.map_err(
+        |payload| match payload.downcast::<EventTableLimitExceeded>() {
+            Ok(exceeded) => TraceCapacityError {
+                capacity: exceeded.limit,
+                ..capacity.error(CapacityTable::Etable, exceeded.limit + 1)
+            },
+            Err(payload) => resume_unwind(payload),
+        },
+    )
+}
+
 pub trait Execution<R> {
     fn dry_run<E: Externals>(self, externals: &mut E) -> Result<Option<R>>;
 
@@ -59,20 +97,40 @@ impl Execution<RuntimeValue>
         externals: &mut E,
         wasm_io: WasmRuntimeIO,
     ) -> Result<ExecutionResult<RuntimeValue>> {
-        let instance = self
-            .instance
-            .run_start_tracer(externals, self.tracer.clone())
-            .unwrap();
+        let start = Instant::now();
+
+        let capacity = TraceCapacity::new();
+        let step_limit = self.step_limit.unwrap_or(capacity.step_budget());
+
+        let (instance, result) = with_step_limit(&self.tracer, &capacity, step_limit, || {
+            let instance = self
+                .instance
+                .run_start_tracer(externals, self.tracer.clone())
+                .unwrap();
+            let result =
+                instance.invoke_export_trace(&self.entry, &[], externals, self.tracer.clone());
+
+            (instance, result)
+        })?;
+        let result = result?;
 
-        let result =
-            instance.invoke_export_trace(&self.entry, &[], externals, self.tracer.clone())?;
+        let exported_globals = self
+            .exported_globals
+            .iter()
+            .filter_map(|name| match instance.export_by_name(name) {
+                Some(ExternVal::Global(global)) => Some((name.clone(), global.get())),
+                _ => None,
+            })
+            .collect::<BTreeMap<_, _>>();
 
         let execution_tables = {
             let tracer = self.tracer.borrow();
 
+            let (etable, jtable) =
+                fuse_execution_trace(&self.tables.itable, &tracer.etable, &tracer.jtable);
+
             let mtable = {
-                let mentries = tracer
-                    .etable
+                let mentries = etable
                     .entries()
                     .iter()
                     .map(|eentry| memory_event_of_step(eentry, &mut 1))
@@ -83,12 +141,16 @@ impl Execution<RuntimeValue>
             };
 
             ExecutionTable {
-                etable: tracer.etable.clone(),
+                etable,
                 mtable,
-                jtable: tracer.jtable.clone(),
+                jtable,
             }
         };
 
+        capacity.check(&execution_tables)?;
+
+        let trace_generation_ms = start.elapsed().as_millis();
+
         Ok(ExecutionResult {
             tables: Tables {
                 compilation_tables: self.tables.clone(),
@@ -97,6 +159,11 @@ impl Execution<RuntimeValue>
             result,
             public_inputs_and_outputs: wasm_io.public_inputs_and_outputs.borrow().clone(),
             outputs: wasm_io.outputs.borrow().clone(),
+            exported_globals,
+            time_profile: TimeProfileReport {
+                trace_generation_ms,
+                ..TimeProfileReport::default()
+            },
         })
     }
 }
@@ -119,7 +186,7 @@ impl WasmiRuntime {
         let tracer = Rc::new(RefCell::new(tracer));
 
         let instance = ModuleInstance::new(&module, imports, Some(tracer.clone()))
-            .expect("failed to instantiate wasm module");
+            .map_err(|err| anyhow!("failed to instantiate wasm module: {:?}", err))?;
 
         let fid_of_entry = {
             let idx_of_entry = instance.lookup_function_by_name(tracer.clone(), entry);
@@ -159,11 +226,23 @@ impl WasmiRuntime {
             }
         };
 
-        let itable = tracer.borrow().itable.clone();
+        let itable = fuse_itable(&tracer.borrow().itable);
         let imtable = tracer.borrow().imtable.finalized(zkwasm_k());
         let elem_table = tracer.borrow().elem_table.clone();
         let configure_table = tracer.borrow().configure_table.clone();
         let static_jtable = tracer.borrow().static_jtable_entries.clone();
+        let exported_globals = module
+            .module()
+            .export_section()
+            .map(|section| {
+                section
+                    .entries()
+                    .iter()
+                    .filter(|entry| matches!(entry.internal(), Internal::Global(_)))
+                    .map(|entry| entry.field().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default();
 
         Ok(CompiledImage {
             entry: entry.to_owned(),
@@ -177,6 +256,8 @@ impl WasmiRuntime {
             },
             instance,
             tracer,
+            step_limit: None,
+            exported_globals,
         })
     }
 }
@@ -0,0 +1,111 @@
+//! Differential fuzzing between wasmi and the circuit.
+//!
+//! Random programs are executed by a plain wasmi instance and by the tracing runtime, the
+//! results must agree and the trace must pass the mock prover. A failing program is minimized
+//! before being reported.
+//!
+//! `ZKWASM_FUZZ_SEED` and `ZKWASM_FUZZ_ITERATIONS` override the seed and the number of programs.
+
+use std::env;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use halo2_proofs::pairing::bn256::Fr;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use wasmi::ImportsBuilder;
+use wasmi::ModuleInstance;
+use wasmi::NopExternals;
+
+use super::compile_then_execute_wasm;
+use super::test_circuit_mock;
+use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+
+use program::Program;
+use program::FUZZ_ENTRY;
+
+mod program;
+mod shrink;
+
+const DEFAULT_SEED: u64 = 0x7a6b7761736d;
+const DEFAULT_ITERATIONS: usize = 4;
+
+fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    panic
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+        .unwrap_or_default()
+}
+
+/// Run `program` with both runtimes and mock its trace.
+fn check_program(program: &Program) -> Result<()> {
+    let wat = program.to_wat();
+    let wasm = wabt::wat2wasm(&wat).expect("fuzzer generated an invalid module");
+
+    let expected = {
+        let module = wasmi::Module::from_buffer(&wasm).map_err(|err| anyhow!("{:?}", err))?;
+        let instance = ModuleInstance::new(&module, &ImportsBuilder::default(), None)
+            .map_err(|err| anyhow!("{:?}", err))?
+            .assert_no_start();
+
+        instance
+            .invoke_export(FUZZ_ENTRY, &[], &mut NopExternals)
+            .map_err(|err| anyhow!("wasmi: {:?}", err))?
+    };
+
+    catch_unwind(AssertUnwindSafe(|| {
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let execution_result =
+            compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, FUZZ_ENTRY)?;
+
+        if execution_result.result != expected {
+            return Err(anyhow!(
+                "result mismatch, wasmi: {:?}, traced: {:?}",
+                expected,
+                execution_result.result
+            ));
+        }
+
+        test_circuit_mock::<Fr>(execution_result)
+    }))
+    .unwrap_or_else(|panic| Err(anyhow!("panicked: {}", panic_message(panic))))
+}
+
+#[test]
+fn test_differential_fuzz() {
+    let seed = env_or("ZKWASM_FUZZ_SEED", DEFAULT_SEED);
+    let iterations = env_or("ZKWASM_FUZZ_ITERATIONS", DEFAULT_ITERATIONS);
+
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    for iteration in 0..iterations {
+        let program = Program::random(&mut rng);
+
+        if let Err(err) = check_program(&program) {
+            let minimized =
+                shrink::minimize(program, |candidate| check_program(candidate).is_err());
+
+            panic!(
+                "program {} of seed {} failed: {}\nminimized program fails with: {}\n{}",
+                iteration,
+                seed,
+                err,
+                check_program(&minimized).unwrap_err(),
+                minimized.to_wat()
+            );
+        }
+    }
+}
@@ -0,0 +1,380 @@
+//! Random programs within the supported instruction subset, rendered as wat.
+//!
+//! Programs never trap: divisors are forced odd, addresses are masked into the first page and
+//! loops are bounded by a counter.
+
+use std::fmt;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+const LOCALS: u32 = 4;
+const MAX_DEPTH: usize = 3;
+const MAX_LOOP_NESTING: u32 = 2;
+const MAX_ITERATIONS: u32 = 4;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum Ty {
+    I32,
+    I64,
+}
+
+impl Ty {
+    fn random(rng: &mut StdRng) -> Self {
+        if rng.gen() {
+            Ty::I32
+        } else {
+            Ty::I64
+        }
+    }
+}
+
+impl fmt::Display for Ty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Ty::I32 => write!(f, "i32"),
+            Ty::I64 => write!(f, "i64"),
+        }
+    }
+}
+
+const UNARY_OPS: [&str; 3] = ["clz", "ctz", "popcnt"];
+const BINARY_OPS: [&str; 10] = [
+    "add", "sub", "mul", "and", "or", "xor", "shl", "shr_s", "shr_u", "rotl",
+];
+const DIVISION_OPS: [&str; 3] = ["div_u", "rem_u", "rem_s"];
+const COMPARE_OPS: [&str; 6] = ["eq", "ne", "lt_s", "lt_u", "gt_u", "ge_s"];
+
+#[derive(Clone, Debug)]
+pub(super) enum Expr {
+    Const(Ty, u64),
+    Local(Ty, u32),
+    Unary(Ty, &'static str, Box<Expr>),
+    Binary(Ty, &'static str, Box<Expr>, Box<Expr>),
+    /// The divisor is forced odd.
+    Division(Ty, &'static str, Box<Expr>, Box<Expr>),
+    /// Compare two values of the type, the result is an i32.
+    Compare(Ty, &'static str, Box<Expr>, Box<Expr>),
+    Eqz(Ty, Box<Expr>),
+    Wrap(Box<Expr>),
+    Extend(bool, Box<Expr>),
+    Select(Ty, Box<Expr>, Box<Expr>, Box<Expr>),
+    If(Ty, Box<Expr>, Box<Expr>, Box<Expr>),
+    Load(Ty, Box<Expr>),
+    Call(Ty, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub(super) fn ty(&self) -> Ty {
+        match self {
+            Expr::Const(ty, _)
+            | Expr::Local(ty, _)
+            | Expr::Unary(ty, _, _)
+            | Expr::Binary(ty, _, _, _)
+            | Expr::Division(ty, _, _, _)
+            | Expr::Select(ty, _, _, _)
+            | Expr::If(ty, _, _, _)
+            | Expr::Load(ty, _)
+            | Expr::Call(ty, _, _) => *ty,
+            Expr::Compare(_, _, _, _) | Expr::Eqz(_, _) | Expr::Wrap(_) => Ty::I32,
+            Expr::Extend(_, _) => Ty::I64,
+        }
+    }
+
+    pub(super) fn children(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Const(_, _) | Expr::Local(_, _) => vec![],
+            Expr::Unary(_, _, a)
+            | Expr::Eqz(_, a)
+            | Expr::Wrap(a)
+            | Expr::Extend(_, a)
+            | Expr::Load(_, a) => vec![a],
+            Expr::Binary(_, _, a, b)
+            | Expr::Division(_, _, a, b)
+            | Expr::Compare(_, _, a, b)
+            | Expr::Call(_, a, b) => vec![a, b],
+            Expr::Select(_, a, b, c) | Expr::If(_, a, b, c) => vec![a, b, c],
+        }
+    }
+
+    /// The same expression with its children replaced, in the order of `children`.
+    pub(super) fn with_children(&self, children: Vec<Expr>) -> Expr {
+        let mut children = children.into_iter().map(Box::new);
+        let mut next = || children.next().unwrap();
+
+        match self {
+            Expr::Const(_, _) | Expr::Local(_, _) => self.clone(),
+            Expr::Unary(ty, op, _) => Expr::Unary(*ty, op, next()),
+            Expr::Eqz(ty, _) => Expr::Eqz(*ty, next()),
+            Expr::Wrap(_) => Expr::Wrap(next()),
+            Expr::Extend(signed, _) => Expr::Extend(*signed, next()),
+            Expr::Load(ty, _) => Expr::Load(*ty, next()),
+            Expr::Binary(ty, op, _, _) => Expr::Binary(*ty, op, next(), next()),
+            Expr::Division(ty, op, _, _) => Expr::Division(*ty, op, next(), next()),
+            Expr::Compare(ty, op, _, _) => Expr::Compare(*ty, op, next(), next()),
+            Expr::Call(ty, _, _) => Expr::Call(*ty, next(), next()),
+            Expr::Select(ty, _, _, _) => Expr::Select(*ty, next(), next(), next()),
+            Expr::If(ty, _, _, _) => Expr::If(*ty, next(), next(), next()),
+        }
+    }
+
+    fn random(rng: &mut StdRng, ty: Ty, depth: usize) -> Expr {
+        if depth >= MAX_DEPTH || rng.gen_ratio(1, 4) {
+            return if rng.gen() {
+                Expr::Const(ty, random_value(rng, ty))
+            } else {
+                Expr::Local(ty, rng.gen_range(0..LOCALS))
+            };
+        }
+
+        match (ty, rng.gen_range(0..10)) {
+            (_, 0) => Expr::Unary(
+                ty,
+                UNARY_OPS[rng.gen_range(0..UNARY_OPS.len())],
+                sub(rng, ty, depth),
+            ),
+            (_, 1) => Expr::Division(
+                ty,
+                DIVISION_OPS[rng.gen_range(0..DIVISION_OPS.len())],
+                sub(rng, ty, depth),
+                sub(rng, ty, depth),
+            ),
+            (Ty::I32, 2) => {
+                let operand = Ty::random(rng);
+                Expr::Compare(
+                    operand,
+                    COMPARE_OPS[rng.gen_range(0..COMPARE_OPS.len())],
+                    sub(rng, operand, depth),
+                    sub(rng, operand, depth),
+                )
+            }
+            (Ty::I32, 3) => {
+                let operand = Ty::random(rng);
+                Expr::Eqz(operand, sub(rng, operand, depth))
+            }
+            (Ty::I32, 4) => Expr::Wrap(sub(rng, Ty::I64, depth)),
+            (Ty::I64, 4) => Expr::Extend(rng.gen(), sub(rng, Ty::I32, depth)),
+            (_, 5) => Expr::Select(
+                ty,
+                sub(rng, ty, depth),
+                sub(rng, ty, depth),
+                sub(rng, Ty::I32, depth),
+            ),
+            (_, 6) => Expr::If(
+                ty,
+                sub(rng, Ty::I32, depth),
+                sub(rng, ty, depth),
+                sub(rng, ty, depth),
+            ),
+            (_, 7) => Expr::Load(ty, sub(rng, Ty::I32, depth)),
+            (_, 8) => Expr::Call(ty, sub(rng, ty, depth), sub(rng, ty, depth)),
+            _ => Expr::Binary(
+                ty,
+                BINARY_OPS[rng.gen_range(0..BINARY_OPS.len())],
+                sub(rng, ty, depth),
+                sub(rng, ty, depth),
+            ),
+        }
+    }
+}
+
+fn sub(rng: &mut StdRng, ty: Ty, depth: usize) -> Box<Expr> {
+    Box::new(Expr::random(rng, ty, depth + 1))
+}
+
+fn random_value(rng: &mut StdRng, ty: Ty) -> u64 {
+    let value = match rng.gen_range(0..4) {
+        0 => rng.gen_range(0..16),
+        1 => u64::MAX - rng.gen_range(0..16),
+        2 => 1u64 << rng.gen_range(0..64u32),
+        _ => rng.gen(),
+    };
+
+    match ty {
+        Ty::I32 => value as u32 as u64,
+        Ty::I64 => value,
+    }
+}
+
+/// Locals `0..LOCALS` of each type, followed by the loop counters.
+fn local_name(ty: Ty, index: u32) -> String {
+    format!("$l{}_{}", ty, index)
+}
+
+fn counter_name(nesting: u32) -> String {
+    format!("$c{}", nesting)
+}
+
+fn address(addr: &Expr) -> String {
+    format!("(i32.and {} (i32.const 0xfff8))", addr)
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Const(Ty::I32, value) => write!(f, "(i32.const {})", *value as u32 as i32),
+            Expr::Const(Ty::I64, value) => write!(f, "(i64.const {})", *value as i64),
+            Expr::Local(ty, index) => write!(f, "(local.get {})", local_name(*ty, *index)),
+            Expr::Unary(ty, op, a) => write!(f, "({}.{} {})", ty, op, a),
+            Expr::Binary(ty, op, a, b) => write!(f, "({}.{} {} {})", ty, op, a, b),
+            Expr::Division(ty, op, a, b) => write!(
+                f,
+                "({ty}.{} {} ({ty}.or {} ({ty}.const 1)))",
+                op,
+                a,
+                b,
+                ty = ty
+            ),
+            Expr::Compare(ty, op, a, b) => write!(f, "({}.{} {} {})", ty, op, a, b),
+            Expr::Eqz(ty, a) => write!(f, "({}.eqz {})", ty, a),
+            Expr::Wrap(a) => write!(f, "(i32.wrap_i64 {})", a),
+            Expr::Extend(true, a) => write!(f, "(i64.extend_i32_s {})", a),
+            Expr::Extend(false, a) => write!(f, "(i64.extend_i32_u {})", a),
+            Expr::Select(_, a, b, cond) => write!(f, "(select {} {} {})", a, b, cond),
+            Expr::If(ty, cond, a, b) => {
+                write!(f, "(if (result {}) {} (then {}) (else {}))", ty, cond, a, b)
+            }
+            Expr::Load(ty, addr) => write!(f, "({}.load {})", ty, address(addr)),
+            Expr::Call(ty, a, b) => write!(f, "(call $mix_{} {} {})", ty, a, b),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub(super) enum Stmt {
+    Set(Ty, u32, Expr),
+    Store(Expr, Expr),
+    Loop {
+        nesting: u32,
+        iterations: u32,
+        body: Vec<Stmt>,
+    },
+    /// Skip the body if the condition holds.
+    BrIf(Expr, Vec<Stmt>),
+}
+
+impl Stmt {
+    fn random(rng: &mut StdRng, nesting: u32) -> Stmt {
+        match rng.gen_range(0..8) {
+            0 if nesting < MAX_LOOP_NESTING => Stmt::Loop {
+                nesting,
+                iterations: rng.gen_range(1..=MAX_ITERATIONS),
+                body: random_stmts(rng, nesting + 1, 3),
+            },
+            1 => Stmt::BrIf(Expr::random(rng, Ty::I32, 1), random_stmts(rng, nesting, 2)),
+            2 | 3 => {
+                let ty = Ty::random(rng);
+                Stmt::Store(Expr::random(rng, Ty::I32, 1), Expr::random(rng, ty, 0))
+            }
+            _ => {
+                let ty = Ty::random(rng);
+                Stmt::Set(ty, rng.gen_range(0..LOCALS), Expr::random(rng, ty, 0))
+            }
+        }
+    }
+
+    fn render(&self, out: &mut String, indent: usize) {
+        let pad = " ".repeat(indent);
+
+        match self {
+            Stmt::Set(ty, index, value) => {
+                out.push_str(&format!(
+                    "{}(local.set {} {})\n",
+                    pad,
+                    local_name(*ty, *index),
+                    value
+                ));
+            }
+            Stmt::Store(addr, value) => {
+                out.push_str(&format!(
+                    "{}({}.store {} {})\n",
+                    pad,
+                    value.ty(),
+                    address(addr),
+                    value
+                ));
+            }
+            Stmt::Loop {
+                nesting,
+                iterations,
+                body,
+            } => {
+                let counter = counter_name(*nesting);
+
+                out.push_str(&format!("{}(local.set {} (i32.const 0))\n", pad, counter));
+                out.push_str(&format!("{}(block\n{}  (loop\n", pad, pad));
+                out.push_str(&format!(
+                    "{}    (br_if 1 (i32.ge_u (local.get {}) (i32.const {})))\n",
+                    pad, counter, iterations
+                ));
+                body.iter().for_each(|stmt| stmt.render(out, indent + 4));
+                out.push_str(&format!(
+                    "{}    (local.set {c} (i32.add (local.get {c}) (i32.const 1)))\n",
+                    pad,
+                    c = counter
+                ));
+                out.push_str(&format!("{}    (br 0)))\n", pad));
+            }
+            Stmt::BrIf(cond, body) => {
+                out.push_str(&format!("{}(block\n{}  (br_if 0 {})\n", pad, pad, cond));
+                body.iter().for_each(|stmt| stmt.render(out, indent + 2));
+                out.push_str(&format!("{})\n", pad));
+            }
+        }
+    }
+}
+
+fn random_stmts(rng: &mut StdRng, nesting: u32, max: usize) -> Vec<Stmt> {
+    (0..rng.gen_range(1..=max))
+        .map(|_| Stmt::random(rng, nesting))
+        .collect()
+}
+
+/// A module exporting `FUZZ_ENTRY`, which runs `body` then returns `result`.
+#[derive(Clone, Debug)]
+pub(super) struct Program {
+    pub(super) body: Vec<Stmt>,
+    pub(super) result: Expr,
+}
+
+pub(super) const FUZZ_ENTRY: &str = "fuzz";
+
+impl Program {
+    pub(super) fn random(rng: &mut StdRng) -> Self {
+        Program {
+            body: random_stmts(rng, 0, 6),
+            result: Expr::random(rng, Ty::I64, 0),
+        }
+    }
+
+    pub(super) fn to_wat(&self) -> String {
+        let mut out = String::new();
+
+        out.push_str("(module\n");
+        out.push_str("  (memory 1)\n");
+        out.push_str(
+            "  (func $mix_i32 (param i32 i32) (result i32)\n    (i32.rotl (i32.xor (local.get 0) (local.get 1)) (i32.const 7)))\n",
+        );
+        out.push_str(
+            "  (func $mix_i64 (param i64 i64) (result i64)\n    (i64.mul (i64.add (local.get 0) (local.get 1)) (local.get 1)))\n",
+        );
+        out.push_str(&format!(
+            "  (func (export \"{}\") (result i64)\n",
+            FUZZ_ENTRY
+        ));
+
+        for ty in [Ty::I32, Ty::I64] {
+            for index in 0..LOCALS {
+                out.push_str(&format!("    (local {} {})\n", local_name(ty, index), ty));
+            }
+        }
+        for nesting in 0..MAX_LOOP_NESTING {
+            out.push_str(&format!("    (local {} i32)\n", counter_name(nesting)));
+        }
+
+        self.body.iter().for_each(|stmt| stmt.render(&mut out, 4));
+        out.push_str(&format!("    {}))\n", self.result));
+
+        out
+    }
+}
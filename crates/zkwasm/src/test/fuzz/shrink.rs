@@ -0,0 +1,146 @@
+//! Greedy minimization of a failing program.
+
+use super::program::Expr;
+use super::program::Program;
+use super::program::Stmt;
+
+/// Smaller expressions of the same type: zero, a child of the same type, or the expression
+/// with a smaller child.
+fn shrink_expr(expr: &Expr) -> Vec<Expr> {
+    let mut candidates = vec![];
+
+    if !matches!(expr, Expr::Const(_, 0)) {
+        candidates.push(Expr::Const(expr.ty(), 0));
+    }
+
+    let children = expr.children();
+
+    for child in &children {
+        if child.ty() == expr.ty() {
+            candidates.push((*child).clone());
+        }
+    }
+
+    for (index, child) in children.iter().enumerate() {
+        for shrunk in shrink_expr(child) {
+            let mut replaced = children.iter().map(|c| (*c).clone()).collect::<Vec<_>>();
+            replaced[index] = shrunk;
+
+            candidates.push(expr.with_children(replaced));
+        }
+    }
+
+    candidates
+}
+
+fn shrink_stmt(stmt: &Stmt) -> Vec<Stmt> {
+    match stmt {
+        Stmt::Set(ty, index, value) => shrink_expr(value)
+            .into_iter()
+            .map(|value| Stmt::Set(*ty, *index, value))
+            .collect(),
+        Stmt::Store(addr, value) => shrink_expr(addr)
+            .into_iter()
+            .map(|addr| Stmt::Store(addr, value.clone()))
+            .chain(
+                shrink_expr(value)
+                    .into_iter()
+                    .map(|value| Stmt::Store(addr.clone(), value)),
+            )
+            .collect(),
+        Stmt::Loop {
+            nesting,
+            iterations,
+            body,
+        } => {
+            let mut candidates = vec![];
+
+            if *iterations > 1 {
+                candidates.push(Stmt::Loop {
+                    nesting: *nesting,
+                    iterations: 1,
+                    body: body.clone(),
+                });
+            }
+
+            candidates.extend(shrink_stmts(body).into_iter().map(|body| Stmt::Loop {
+                nesting: *nesting,
+                iterations: *iterations,
+                body,
+            }));
+
+            candidates
+        }
+        Stmt::BrIf(cond, body) => shrink_expr(cond)
+            .into_iter()
+            .map(|cond| Stmt::BrIf(cond, body.clone()))
+            .chain(
+                shrink_stmts(body)
+                    .into_iter()
+                    .map(|body| Stmt::BrIf(cond.clone(), body)),
+            )
+            .collect(),
+    }
+}
+
+/// Remove a statement, inline the body of a block, or shrink a statement.
+fn shrink_stmts(stmts: &[Stmt]) -> Vec<Vec<Stmt>> {
+    let mut candidates = vec![];
+
+    for index in 0..stmts.len() {
+        let mut removed = stmts.to_vec();
+        removed.remove(index);
+        candidates.push(removed);
+    }
+
+    for (index, stmt) in stmts.iter().enumerate() {
+        if let Stmt::Loop { body, .. } | Stmt::BrIf(_, body) = stmt {
+            let mut spliced = stmts[..index].to_vec();
+            spliced.extend(body.iter().cloned());
+            spliced.extend(stmts[index + 1..].iter().cloned());
+            candidates.push(spliced);
+        }
+    }
+
+    for (index, stmt) in stmts.iter().enumerate() {
+        for shrunk in shrink_stmt(stmt) {
+            let mut replaced = stmts.to_vec();
+            replaced[index] = shrunk;
+            candidates.push(replaced);
+        }
+    }
+
+    candidates
+}
+
+fn shrink_program(program: &Program) -> Vec<Program> {
+    shrink_stmts(&program.body)
+        .into_iter()
+        .map(|body| Program {
+            body,
+            result: program.result.clone(),
+        })
+        .chain(
+            shrink_expr(&program.result)
+                .into_iter()
+                .map(|result| Program {
+                    body: program.body.clone(),
+                    result,
+                }),
+        )
+        .collect()
+}
+
+/// Replace `program` by its first smaller candidate which still fails until none does.
+pub(super) fn minimize(program: Program, fails: impl Fn(&Program) -> bool) -> Program {
+    let mut program = program;
+
+    while let Some(smaller) = shrink_program(&program)
+        .into_iter()
+        .find(|candidate| fails(candidate))
+    {
+        program = smaller;
+    }
+
+    program
+}
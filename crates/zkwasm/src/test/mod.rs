@@ -1,6 +1,13 @@
+use crate::circuits::config::with_circuit_params;
 use crate::circuits::config::zkwasm_k;
+use crate::circuits::config::CircuitParams;
+use crate::circuits::diagnose::DiagnosticReport;
 use crate::circuits::utils::table_entry::MemoryWritingTable;
 use crate::circuits::TestCircuit;
+use crate::foreign::context::circuits::assign::ExtractContextFromTrace;
+use crate::foreign::context::circuits::context_instances;
+use crate::foreign::wasm_input_helper::circuits::public_instances;
+use crate::loader::debug_info::DebugInfo;
 use crate::profile::Profiler;
 use crate::runtime::host::host_env::HostEnv;
 use crate::runtime::wasmi_interpreter::Execution;
@@ -8,6 +15,7 @@ use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
 use crate::runtime::ExecutionResult;
 use crate::runtime::WasmInterpreter;
 
+use anyhow::anyhow;
 use anyhow::Result;
 use halo2_proofs::arithmetic::FieldExt;
 use halo2_proofs::dev::MockProver;
@@ -20,27 +28,40 @@ use wasmi::RuntimeValue;
 #[cfg(test)]
 mod test_wasm_instructions;
 
+mod fuzz;
 mod spec;
+mod test_context;
+mod test_debugger;
+mod test_diagnose;
+mod test_image_table;
+mod test_imported_globals;
+mod test_instruction_fusion;
+mod test_opcode_set;
+mod test_profile;
 mod test_rlp;
+mod test_soundness;
 mod test_start;
+mod test_table_dump;
+mod test_trace_capacity;
 mod test_uniform_verifier;
 
 /// Create circuit with trace and run mock test.
 fn test_circuit_mock<F: FieldExt>(
     execution_result: ExecutionResult<wasmi::RuntimeValue>,
 ) -> Result<()> {
-    let instance = {
-        let mut v: Vec<F> = vec![];
-
-        v.append(
-            &mut execution_result
-                .public_inputs_and_outputs
-                .iter()
-                .map(|v| (*v).into())
-                .collect(),
-        );
-
-        v
+    test_circuit_mock_with_params(execution_result, CircuitParams::default())
+}
+
+fn test_circuit_mock_with_params<F: FieldExt>(
+    execution_result: ExecutionResult<wasmi::RuntimeValue>,
+    params: CircuitParams,
+) -> Result<()> {
+    let instance: Vec<F> = public_instances(&execution_result.public_inputs_and_outputs, false);
+
+    let context_instance: Vec<F> = {
+        let etable = &execution_result.tables.execution_tables.etable;
+
+        context_instances(&etable.get_context_inputs(), &etable.get_context_outputs())
     };
 
     execution_result.tables.write_json(None);
@@ -54,11 +75,43 @@ fn test_circuit_mock<F: FieldExt>(
 
     execution_result.tables.profile_tables();
 
-    let circuit = TestCircuit::new(execution_result.tables);
-    let prover = MockProver::run(zkwasm_k(), &circuit, vec![instance])?;
-    assert_eq!(prover.verify(), Ok(()));
+    let circuit = TestCircuit::new(
+        execution_result.tables,
+        execution_result.public_inputs_and_outputs,
+        params,
+    );
+    let prover = with_circuit_params(&circuit.params, || {
+        MockProver::run(zkwasm_k(), &circuit, vec![instance, context_instance])
+    })?;
+
+    prover.verify().map_err(|failures| {
+        anyhow!(DiagnosticReport::new(
+            &circuit,
+            &DebugInfo::default(),
+            failures
+        ))
+    })
+}
+
+/// Debug info of `wasm` with the fids allocated by the tracer under the imports of `env`.
+fn debug_info_of(env: &HostEnv, wasm: &[u8], function_name: &str) -> DebugInfo {
+    let module = wasmi::Module::from_buffer(wasm).expect("failed to load wasm");
+    let exported =
+        wasmi::Module::from_parity_wasm_module(DebugInfo::export_functions(module.module()))
+            .unwrap();
 
-    Ok(())
+    let imports = ImportsBuilder::new().with_resolver("env", env);
+
+    let compiled = WasmInterpreter::compile(
+        &exported,
+        &imports,
+        &env.function_description_table(),
+        function_name,
+        &vec![],
+    )
+    .unwrap();
+
+    DebugInfo::new(wasm, module.module(), &compiled)
 }
 
 /// Run function and generate trace.
@@ -1,94 +1,495 @@
-/*
-    use wast::Error;
-
-    #[cfg(test)]
-    fn run_spec_test(_file_name: &str) -> Result<(), Error> {
-        todo!();
-
-        /*
-        let path = format!("src/test/spec/{}.wast", file_name);
-        let file = fs::read_to_string(&path).unwrap();
-
-        let mut lexer = Lexer::new(&file);
-        lexer.allow_confusing_unicode(true);
-        let parse_buffer = ParseBuffer::new_with_lexer(lexer)?;
-
-        let wast: Wast = wast::parser::parse(&parse_buffer)?;
-        let imports = ImportsBuilder::default();
-        let mut externals = NopExternals;
-
-        let compiler = WasmInterpreter::new();
-        let mut compile_outcome = None;
-
-        for directive in wast.directives {
-            match directive {
-                wast::WastDirective::Wat(wat) => match wat {
-                    wast::QuoteWat::Wat(wat) => match wat {
-                        wast::Wat::Module(module) => {
-                            let compiled = compiler
-                                .compile_from_wast(module, &imports, &HashMap::default())
-                                .unwrap();
-                            compile_outcome = Some(compiled);
-                        }
-                        wast::Wat::Component(_) => todo!(),
-                    },
-                    wast::QuoteWat::QuoteModule(_, _) => todo!(),
-                    wast::QuoteWat::QuoteComponent(_, _) => todo!(),
-                },
-                wast::WastDirective::AssertMalformed { .. } => todo!(),
-                wast::WastDirective::AssertInvalid { .. } => todo!(),
-                wast::WastDirective::Register { .. } => todo!(),
-                wast::WastDirective::Invoke(..) => todo!(),
-                wast::WastDirective::AssertTrap { .. } => todo!(),
-                wast::WastDirective::AssertReturn {
-                    span: _span,
-                    exec,
-                    results: _results,
-                } => {
-                    let from_wastarg = |arg: &WastArg| match arg {
-                        WastArg::Core(core) => match core {
-                            wast::core::WastArgCore::I32(v) => Value::I32(*v),
-                            wast::core::WastArgCore::I64(v) => Value::I64(*v),
-                            wast::core::WastArgCore::F32(_) => todo!(),
-                            wast::core::WastArgCore::F64(_) => todo!(),
-                            wast::core::WastArgCore::V128(_) => todo!(),
-                            wast::core::WastArgCore::RefNull(_) => todo!(),
-                            wast::core::WastArgCore::RefExtern(_) => todo!(),
-                        },
-                        WastArg::Component(_) => todo!(),
-                    };
-
-                    let _actual_results = match exec {
-                        wast::WastExecute::Invoke(invoke) => compiler.run(
-                            &mut externals,
-                            &compile_outcome.unwrap(),
-                            invoke.name,
-                            invoke.args.iter().map(|arg| from_wastarg(arg)).collect(),
-                        ),
-                        wast::WastExecute::Wat(_) => todo!(),
-                        wast::WastExecute::Get { .. } => todo!(),
+//! WebAssembly spec test harness.
+//!
+//! Every `<proposal>/<name>.wast` file in this directory is run against both the interpreter
+//! and the circuit:
+//! * each directive is first executed by a plain wasmi instance,
+//! * the `assert_return` and `invoke` directives of a module are then replayed in order by a
+//!   generated entry function which traps on a mismatched result, and its trace is checked by
+//!   the mock prover,
+//! * an `assert_trap` must also abort the traced execution, since a trapping execution can't
+//!   be proven.
+//!
+//! Directives relying on floats, references, imports or multiple modules are skipped.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::Path;
+
+use anyhow::anyhow;
+use anyhow::Result;
+use parity_wasm::builder;
+use parity_wasm::elements::BlockType;
+use parity_wasm::elements::ImportCountType;
+use parity_wasm::elements::Instruction;
+use parity_wasm::elements::Instructions;
+use parity_wasm::elements::Internal;
+use parity_wasm::elements::Module;
+use parity_wasm::elements::Type;
+use wasmi::ImportsBuilder;
+use wasmi::ModuleInstance;
+use wasmi::ModuleRef;
+use wasmi::NopExternals;
+use wasmi::RuntimeValue;
+use wast::core::WastArgCore;
+use wast::core::WastRetCore;
+use wast::parser::ParseBuffer;
+use wast::token::Span;
+use wast::QuoteWat;
+use wast::Wast;
+use wast::WastArg;
+use wast::WastDirective;
+use wast::WastExecute;
+use wast::WastInvoke;
+use wast::WastRet;
+
+use super::compile_then_execute_wasm;
+use super::test_circuit_with_env;
+use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+
+const SPEC_ENTRY: &str = "zkwasm_spec_entry";
+
+#[derive(Default)]
+struct ProposalReport {
+    passed: usize,
+    skipped: usize,
+    failures: Vec<String>,
+}
+
+#[derive(Default)]
+struct SpecReport {
+    proposals: BTreeMap<String, ProposalReport>,
+}
+
+impl SpecReport {
+    fn failed(&self) -> usize {
+        self.proposals
+            .values()
+            .map(|report| report.failures.len())
+            .sum()
+    }
+}
+
+impl fmt::Display for SpecReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (proposal, report) in &self.proposals {
+            writeln!(
+                f,
+                "{}: {} passed, {} failed, {} skipped",
+                proposal,
+                report.passed,
+                report.failures.len(),
+                report.skipped
+            )?;
+
+            for failure in &report.failures {
+                writeln!(f, "    {}", failure)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn runtime_value(arg: &WastArg) -> Option<RuntimeValue> {
+    match arg {
+        WastArg::Core(WastArgCore::I32(v)) => Some(RuntimeValue::I32(*v)),
+        WastArg::Core(WastArgCore::I64(v)) => Some(RuntimeValue::I64(*v)),
+        _ => None,
+    }
+}
+
+fn expected_value(ret: &WastRet) -> Option<RuntimeValue> {
+    match ret {
+        WastRet::Core(WastRetCore::I32(v)) => Some(RuntimeValue::I32(*v)),
+        WastRet::Core(WastRetCore::I64(v)) => Some(RuntimeValue::I64(*v)),
+        _ => None,
+    }
+}
+
+fn const_instruction(value: &RuntimeValue) -> Instruction {
+    match value {
+        RuntimeValue::I32(v) => Instruction::I32Const(*v),
+        RuntimeValue::I64(v) => Instruction::I64Const(*v),
+        _ => unreachable!(),
+    }
+}
+
+/// Function index and number of results of an exported function.
+fn exported_function(module: &Module, name: &str) -> Result<(u32, usize)> {
+    let index = module
+        .export_section()
+        .and_then(|section| section.entries().iter().find(|entry| entry.field() == name))
+        .and_then(|entry| match entry.internal() {
+            Internal::Function(index) => Some(*index),
+            _ => None,
+        })
+        .ok_or_else(|| anyhow!("function {} is not exported", name))?;
+
+    let imported_functions = module.import_count(ImportCountType::Function) as u32;
+    if index < imported_functions {
+        return Err(anyhow!("function {} is imported", name));
+    }
+
+    let type_ref = module.function_section().unwrap().entries()
+        [(index - imported_functions) as usize]
+        .type_ref();
+    let types = module.type_section().unwrap().types();
+    let Type::Function(function_type) = &types[type_ref as usize];
+
+    Ok((index, function_type.results().len()))
+}
+
+/// Append an exported function without parameters nor results running `body`.
+fn with_entry(module: &Module, mut body: Vec<Instruction>) -> Result<Vec<u8>> {
+    body.push(Instruction::End);
+
+    let index = module.import_count(ImportCountType::Function)
+        + module
+            .function_section()
+            .map_or(0, |section| section.entries().len());
+
+    let module = builder::from_module(module.clone())
+        .function()
+        .signature()
+        .build()
+        .body()
+        .with_instructions(Instructions::new(body))
+        .build()
+        .build()
+        .export()
+        .field(SPEC_ENTRY)
+        .internal()
+        .func(index as u32)
+        .build()
+        .build();
+
+    Ok(parity_wasm::serialize(module)?)
+}
+
+struct Assertion {
+    location: String,
+    name: String,
+    args: Vec<RuntimeValue>,
+    results: Vec<RuntimeValue>,
+}
+
+struct SpecModule {
+    module: Module,
+    instance: ModuleRef,
+    assertions: Vec<Assertion>,
+}
+
+impl SpecModule {
+    fn instantiate(wasm: &[u8]) -> Result<Self> {
+        let module = wasmi::Module::from_buffer(wasm).map_err(|err| anyhow!("{:?}", err))?;
+        let instance = ModuleInstance::new(&module, &ImportsBuilder::default(), None)
+            .map_err(|err| anyhow!("{:?}", err))?
+            .run_start(&mut NopExternals)
+            .map_err(|err| anyhow!("{:?}", err))?;
+
+        Ok(SpecModule {
+            module: parity_wasm::deserialize_buffer(wasm)?,
+            instance,
+            assertions: vec![],
+        })
+    }
+
+    fn invoke(&self, name: &str, args: &[RuntimeValue]) -> Result<Vec<RuntimeValue>> {
+        let result = self
+            .instance
+            .invoke_export(name, args, &mut NopExternals)
+            .map_err(|err| anyhow!("{:?}", err))?;
+
+        Ok(result.into_iter().collect())
+    }
+
+    fn call_instructions(&self, name: &str, args: &[RuntimeValue]) -> Result<Vec<Instruction>> {
+        let (index, _) = exported_function(&self.module, name)?;
+
+        let mut instructions: Vec<_> = args.iter().map(const_instruction).collect();
+        instructions.push(Instruction::Call(index));
+
+        Ok(instructions)
+    }
+
+    /// Replay the assertions in a single traced execution, checked by the mock prover.
+    fn prove_assertions(&self) -> Result<()> {
+        let mut body = vec![];
+
+        for assertion in &self.assertions {
+            body.append(&mut self.call_instructions(&assertion.name, &assertion.args)?);
+
+            for result in assertion.results.iter().rev() {
+                body.push(const_instruction(result));
+                body.push(match result {
+                    RuntimeValue::I32(_) => Instruction::I32Ne,
+                    _ => Instruction::I64Ne,
+                });
+                body.push(Instruction::If(BlockType::NoResult));
+                body.push(Instruction::Unreachable);
+                body.push(Instruction::End);
+            }
+        }
+
+        let wasm = with_entry(&self.module, body)?;
+
+        let mut env = HostEnv::new();
+        env.finalize();
+        test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, SPEC_ENTRY)?;
+
+        Ok(())
+    }
+
+    /// Run `name` in a fresh traced instance, which must trap.
+    fn trace_trap(&self, name: &str, args: &[RuntimeValue]) -> Result<()> {
+        let (_, results) = exported_function(&self.module, name)?;
+
+        let mut body = self.call_instructions(name, args)?;
+        body.extend(vec![Instruction::Drop; results]);
+
+        let wasm = with_entry(&self.module, body)?;
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        match compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, SPEC_ENTRY) {
+            Ok(_) => Err(anyhow!("traced execution of {} didn't trap", name)),
+            Err(_) => Ok(()),
+        }
+    }
+}
+
+struct SpecRunner<'a> {
+    path: &'a Path,
+    text: &'a str,
+    report: &'a mut ProposalReport,
+    current: Option<SpecModule>,
+}
+
+impl<'a> SpecRunner<'a> {
+    fn location(&self, span: Span) -> String {
+        let (line, _) = span.linecol_in(self.text);
+        format!("{}:{}", self.path.display(), line + 1)
+    }
+
+    fn record(&mut self, location: String, result: Result<()>) {
+        match result {
+            Ok(()) => self.report.passed += 1,
+            Err(err) => self.report.failures.push(format!("{}: {}", location, err)),
+        }
+    }
+
+    fn flush(&mut self) {
+        if let Some(module) = self.current.take() {
+            if module.assertions.is_empty() {
+                return;
+            }
+
+            match module.prove_assertions() {
+                Ok(()) => self.report.passed += module.assertions.len(),
+                Err(err) => {
+                    for assertion in &module.assertions {
+                        self.report
+                            .failures
+                            .push(format!("{}: circuit: {}", assertion.location, err));
                     }
-                    .unwrap();
+                }
+            }
+        }
+    }
+
+    /// Resolve an invocation of the current module with integer arguments.
+    fn invocation(&self, invoke: &WastInvoke) -> Option<Vec<RuntimeValue>> {
+        if self.current.is_none() || invoke.module.is_some() {
+            return None;
+        }
+
+        invoke.args.iter().map(runtime_value).collect()
+    }
+
+    fn run_invoke(&mut self, invoke: WastInvoke, expected: Option<Vec<RuntimeValue>>) {
+        let location = self.location(invoke.span);
+
+        let args = match self.invocation(&invoke) {
+            Some(args) => args,
+            None => {
+                self.report.skipped += 1;
+                return;
+            }
+        };
+
+        let module = self.current.as_mut().unwrap();
+
+        match module.invoke(invoke.name, &args) {
+            Ok(results) => match expected {
+                Some(expected) if results != expected => self.report.failures.push(format!(
+                    "{}: {} returned {:?}, expected {:?}",
+                    location, invoke.name, results, expected
+                )),
+                _ => module.assertions.push(Assertion {
+                    location,
+                    name: invoke.name.to_owned(),
+                    args,
+                    results,
+                }),
+            },
+            Err(err) => self
+                .report
+                .failures
+                .push(format!("{}: {} failed: {}", location, invoke.name, err)),
+        }
+    }
 
-                    todo!()
+    fn run_assert_trap(&mut self, invoke: WastInvoke) {
+        let location = self.location(invoke.span);
+
+        let args = match self.invocation(&invoke) {
+            Some(args) => args,
+            None => {
+                self.report.skipped += 1;
+                return;
+            }
+        };
+
+        let module = self.current.as_ref().unwrap();
+        let result = match module.invoke(invoke.name, &args) {
+            Ok(results) => Err(anyhow!(
+                "{} returned {:?}, expected a trap",
+                invoke.name,
+                results
+            )),
+            Err(_) => module.trace_trap(invoke.name, &args),
+        };
+
+        self.record(location, result);
+    }
+
+    fn run_assert_rejected(&mut self, span: Span, mut module: QuoteWat) {
+        let location = self.location(span);
+
+        let rejected = module
+            .encode()
+            .map_err(|err| anyhow!(err))
+            .and_then(|wasm| wasmi::Module::from_buffer(wasm).map_err(|err| anyhow!("{:?}", err)))
+            .is_err();
+
+        self.record(
+            location,
+            if rejected {
+                Ok(())
+            } else {
+                Err(anyhow!("module is not rejected"))
+            },
+        );
+    }
+
+    fn run(&mut self, directive: WastDirective) {
+        match directive {
+            WastDirective::Wat(mut module) => {
+                self.flush();
+
+                let location = self.location(directive_span(&module));
+                match module
+                    .encode()
+                    .map_err(|err| anyhow!(err))
+                    .and_then(|wasm| SpecModule::instantiate(&wasm))
+                {
+                    Ok(module) => self.current = Some(module),
+                    Err(err) => self
+                        .report
+                        .failures
+                        .push(format!("{}: failed to instantiate: {}", location, err)),
                 }
-                wast::WastDirective::AssertExhaustion { .. } => todo!(),
-                wast::WastDirective::AssertUnlinkable { .. } => todo!(),
-                wast::WastDirective::AssertException { .. } => todo!(),
             }
+            WastDirective::Invoke(invoke) => self.run_invoke(invoke, None),
+            WastDirective::AssertReturn {
+                exec: WastExecute::Invoke(invoke),
+                results,
+                ..
+            } => match results
+                .iter()
+                .map(expected_value)
+                .collect::<Option<Vec<_>>>()
+            {
+                Some(expected) => self.run_invoke(invoke, Some(expected)),
+                None => self.report.skipped += 1,
+            },
+            WastDirective::AssertTrap {
+                exec: WastExecute::Invoke(invoke),
+                ..
+            } => self.run_assert_trap(invoke),
+            WastDirective::AssertInvalid { span, module, .. }
+            | WastDirective::AssertMalformed { span, module, .. } => {
+                self.run_assert_rejected(span, module)
+            }
+            _ => self.report.skipped += 1,
         }
-        Ok(())
-        */
     }
+}
 
-    /*
-    mod tests {
-        use super::run_spec_test;
+fn directive_span(module: &QuoteWat) -> Span {
+    match module {
+        QuoteWat::Wat(wast::Wat::Module(module)) => module.span,
+        QuoteWat::Wat(wast::Wat::Component(component)) => component.span,
+        QuoteWat::QuoteModule(span, _) | QuoteWat::QuoteComponent(span, _) => *span,
+    }
+}
 
-        #[test]
-        fn test_spec_i32() {
-            run_spec_test("i32").unwrap()
+fn run_spec_file(path: &Path, report: &mut ProposalReport) -> Result<()> {
+    let text = std::fs::read_to_string(path)?;
+    let buffer = ParseBuffer::new(&text)?;
+    let wast: Wast = wast::parser::parse(&buffer)?;
+
+    let mut runner = SpecRunner {
+        path,
+        text: &text,
+        report,
+        current: None,
+    };
+
+    for directive in wast.directives {
+        runner.run(directive);
+    }
+    runner.flush();
+
+    Ok(())
+}
+
+/// Run every `<proposal>/<name>.wast` file in the spec directory.
+fn run_spec_tests() -> Result<SpecReport> {
+    let spec_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/test/spec");
+    let mut report = SpecReport::default();
+
+    for proposal in std::fs::read_dir(spec_dir)? {
+        let proposal = proposal?.path();
+        if !proposal.is_dir() {
+            continue;
+        }
+
+        let mut files = std::fs::read_dir(&proposal)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<std::io::Result<Vec<_>>>()?;
+        files.retain(|file| file.extension() == Some(OsStr::new("wast")));
+        files.sort();
+
+        let proposal_report = report
+            .proposals
+            .entry(proposal.file_name().unwrap().to_string_lossy().into_owned())
+            .or_default();
+
+        for file in files {
+            run_spec_file(&file, proposal_report)?;
         }
     }
-    */
-*/
+
+    Ok(report)
+}
+
+#[test]
+fn test_spec() {
+    let report = run_spec_tests().unwrap();
+
+    assert_eq!(report.failed(), 0, "spec tests failed:\n{}", report);
+}
@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use halo2_proofs::pairing::bn256::Bn256;
+    use halo2_proofs::pairing::bn256::Fr;
+
+    use crate::circuits::config::zkwasm_k;
+    use crate::foreign::context::circuits::context_instances;
+    use crate::loader::chain::check_context_chain;
+    use crate::loader::chain::ChainStepArg;
+    use crate::loader::ExecutionArg;
+    use crate::loader::ZkWasmLoader;
+    use crate::runtime::host::record::HostCallMode;
+
+    #[test]
+    fn test_context_digest_mock() {
+        let textual_repr = r#"
+        (module
+            (import "env" "wasm_read_context" (func $read_context (result i64)))
+            (import "env" "wasm_write_context" (func $write_context (param i64)))
+
+            (func (export "zkmain")
+              (call $read_context)
+              (call $read_context)
+              i64.add
+              (call $write_context)
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+        let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k(), wasm, vec![]).unwrap();
+
+        let context_outputs = Arc::new(Mutex::new(vec![]));
+        let (circuit, instances) = loader
+            .circuit_with_witness(ExecutionArg {
+                public_inputs: vec![],
+                private_inputs: vec![],
+                context_inputs: vec![3, 5],
+                context_outputs: context_outputs.clone(),
+                host_call_mode: HostCallMode::Native,
+            })
+            .unwrap();
+
+        assert_eq!(*context_outputs.lock().unwrap(), vec![8]);
+        assert_eq!(instances[1], context_instances::<Fr>(&[3, 5], &[8]));
+
+        loader.mock_test(&circuit, &instances).unwrap();
+
+        let mut tampered = instances.clone();
+        tampered[1] = context_instances::<Fr>(&[3, 5], &[9]);
+        assert!(loader.mock_test(&circuit, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_context_digest_packs_words_mock() {
+        let textual_repr = r#"
+        (module
+            (import "env" "wasm_read_context" (func $read_context (result i64)))
+            (import "env" "wasm_write_context" (func $write_context (param i64)))
+
+            (func (export "zkmain")
+              (local $i i32)
+              (loop
+                (call $read_context)
+                i64.const 1
+                i64.add
+                (call $write_context)
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br_if 0 (i32.lt_u (local.get $i) (i32.const 4)))
+              )
+              (call $write_context (i64.const -1))
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+        let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k(), wasm, vec![]).unwrap();
+
+        let context_outputs = Arc::new(Mutex::new(vec![]));
+        let (circuit, instances) = loader
+            .circuit_with_witness(ExecutionArg {
+                public_inputs: vec![],
+                private_inputs: vec![],
+                context_inputs: vec![1, 2, 3, 4],
+                context_outputs: context_outputs.clone(),
+                host_call_mode: HostCallMode::Native,
+            })
+            .unwrap();
+
+        let outputs = vec![2, 3, 4, 5, u64::MAX];
+        assert_eq!(*context_outputs.lock().unwrap(), outputs);
+        assert_eq!(
+            instances[1],
+            context_instances::<Fr>(&[1, 2, 3, 4], &outputs)
+        );
+
+        loader.mock_test(&circuit, &instances).unwrap();
+
+        // The second element packs the last two outputs.
+        let mut tampered = instances.clone();
+        tampered[1] = context_instances::<Fr>(&[1, 2, 3, 4], &[2, 3, 4, 5, 6]);
+        assert!(loader.mock_test(&circuit, &tampered).is_err());
+    }
+
+    #[test]
+    fn test_context_chain_mock() {
+        let textual_repr = r#"
+        (module
+            (import "env" "wasm_read_context" (func $read_context (result i64)))
+            (import "env" "wasm_write_context" (func $write_context (param i64)))
+
+            (func (export "zkmain")
+              (call $read_context)
+              i64.const 1
+              i64.add
+              (call $write_context)
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+        let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k(), wasm, vec![]).unwrap();
+
+        let steps = (0..3)
+            .map(|_| ChainStepArg {
+                public_inputs: vec![],
+                private_inputs: vec![],
+            })
+            .collect();
+        let chain = loader.circuits_with_context_chain(steps, vec![1]).unwrap();
+
+        assert_eq!(
+            chain
+                .iter()
+                .map(|step| step.context_outputs.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![2], vec![3], vec![4]]
+        );
+
+        for step in &chain {
+            loader.mock_test(&step.circuit, &step.instances).unwrap();
+        }
+
+        let mut instances = chain
+            .into_iter()
+            .map(|step| step.instances)
+            .collect::<Vec<_>>();
+        check_context_chain(&instances).unwrap();
+
+        instances.swap(1, 2);
+        assert!(check_context_chain(&instances).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+#[cfg(test)]
+mod tests {
+    use gimli::write::Address;
+    use gimli::write::DwarfUnit;
+    use gimli::write::EndianVec;
+    use gimli::write::LineProgram;
+    use gimli::write::LineString;
+    use gimli::write::Sections;
+    use gimli::Encoding;
+    use gimli::Format;
+    use gimli::LineEncoding;
+    use gimli::LittleEndian;
+    use specs::itable::Opcode;
+
+    use crate::foreign::require_helper::register_require_foreign;
+    use crate::foreign::wasm_input_helper::runtime::register_wasm_input_foreign;
+    use crate::runtime::debugger::DebugCommand;
+    use crate::runtime::debugger::TraceDebugger;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::compile_then_execute_wasm;
+    use crate::test::debug_info_of;
+
+    #[test]
+    fn test_trace_debugger() {
+        let textual_repr = r#"
+        (module
+            (memory 1)
+            (global $g (mut i32) (i32.const 7))
+
+            (func $add (param $a i32) (param $b i32) (result i32) (local $c i32)
+              local.get $a
+              local.get $b
+              i32.add
+              local.set $c
+              global.get $g
+              local.get $c
+              i32.add
+              global.set $g
+              i32.const 8
+              local.get $c
+              i32.store
+              local.get $c
+            )
+
+            (func (export "test")
+              i32.const 3
+              i32.const 4
+              call $add
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let debug_info = debug_info_of(&env, &wasm, "test");
+        let trace = compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        let callee_fid = trace.tables.execution_tables.jtable.entries()[0].callee_fid;
+        let mut debugger = TraceDebugger::new(&trace.tables, &debug_info);
+        let mut execute = |command: &str| {
+            debugger
+                .execute(DebugCommand::parse(command).unwrap())
+                .unwrap()
+        };
+
+        assert_eq!(
+            execute(&format!("break {}", callee_fid)),
+            "breakpoint 0 set"
+        );
+        assert!(execute("continue").starts_with("breakpoint 0 hit"));
+        assert!(execute("bt").contains("#1 test"));
+        assert_eq!(
+            execute("locals"),
+            "local 0: 0x3 (3)\nlocal 1: 0x4 (4)\nlocal 2: 0x0 (0)"
+        );
+
+        execute("step 2");
+        assert_eq!(execute("stack"), "[0] 0x4 (4)\n[1] 0x3 (3)");
+
+        execute("back 2");
+        assert_eq!(execute("stack"), "");
+        assert_eq!(execute("globals"), "global 0: 0x7 (7)");
+
+        execute(&format!("step {}", usize::MAX));
+        assert_eq!(execute("globals"), "global 0: 0xe (14)");
+        assert_eq!(execute("heap 1"), "0x00000008: 0x7 (7)");
+
+        assert!(execute("reverse-continue").starts_with("breakpoint 0 hit"));
+        assert_eq!(execute("heap 1"), "0x00000008: 0x0 (0)");
+        assert!(debugger.execute(DebugCommand::Quit).is_none());
+    }
+
+    #[test]
+    fn test_debug_info_fids_match_itable() {
+        let textual_repr = r#"
+        (module
+            (import "env" "wasm_input" (func $wasm_input (param i32) (result i64)))
+            (import "env" "require" (func $require (param i32)))
+
+            (func (export "f2")
+              i32.const 102
+              drop
+            )
+
+            (func $start (export "f3")
+              i32.const 103
+              drop
+            )
+
+            (func (export "f4")
+              i32.const 104
+              drop
+            )
+
+            (func (export "f5")
+              i32.const 105
+              drop
+            )
+
+            (start $start)
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        let wasm_runtime_io = register_wasm_input_foreign(&mut env, vec![], vec![]);
+        register_require_foreign(&mut env);
+        env.finalize();
+
+        let debug_info = debug_info_of(&env, &wasm, "f5");
+        let trace = compile_then_execute_wasm(env, wasm_runtime_io, wasm, "f5").unwrap();
+
+        for index in 2..=5 {
+            let fid = trace
+                .tables
+                .compilation_tables
+                .itable
+                .entries()
+                .iter()
+                .find(|entry| {
+                    matches!(entry.opcode, Opcode::Const { value, .. } if value == 100 + index)
+                })
+                .unwrap()
+                .fid;
+
+            assert_eq!(
+                debug_info.function_name(fid),
+                Some(format!("f{}", index).as_str())
+            );
+        }
+    }
+
+    /// DWARF sections of a single unit whose line program has a row at each `(address, line)`
+    /// of `src/main.c`, as `(name, data)`.
+    fn debug_sections(rows: &[(u64, u64)], end: u64) -> Vec<(&'static str, Vec<u8>)> {
+        let encoding = Encoding {
+            format: Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+
+        let mut program = LineProgram::new(
+            encoding,
+            LineEncoding::default(),
+            LineString::String(b"/build".to_vec()),
+            LineString::String(b"main.c".to_vec()),
+            None,
+        );
+        let directory = program.add_directory(LineString::String(b"src".to_vec()));
+        let file = program.add_file(LineString::String(b"main.c".to_vec()), directory, None);
+
+        program.begin_sequence(Some(Address::Constant(0)));
+        for (address, line) in rows {
+            program.row().address_offset = *address;
+            program.row().file = file;
+            program.row().line = *line;
+            program.generate_row();
+        }
+        program.end_sequence(end);
+
+        let mut dwarf = DwarfUnit::new(encoding);
+        dwarf.unit.line_program = program;
+
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        dwarf.write(&mut sections).unwrap();
+
+        let mut custom = vec![];
+        sections
+            .for_each(|id, data| -> gimli::write::Result<()> {
+                if !data.slice().is_empty() {
+                    custom.push((id.name(), data.slice().to_vec()));
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        custom
+    }
+
+    #[test]
+    fn test_debug_info_instruction_locations() {
+        let textual_repr = r#"
+        (module
+            (func (export "test")
+              i32.const 1
+              drop
+              i32.const 2
+              drop
+            )
+           )
+        "#;
+
+        let mut wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        // The code section payload is `count, size, locals` followed by the instructions at
+        // offsets 3, 5, 6, 8 and the `end` at 9.
+        for (name, data) in debug_sections(&[(3, 10), (6, 11)], 10) {
+            let mut payload = vec![name.len() as u8];
+            payload.extend(name.as_bytes());
+            payload.extend(data);
+
+            wasm.push(0);
+            let mut len = payload.len();
+            loop {
+                let byte = (len & 0x7f) as u8;
+                len >>= 7;
+                if len == 0 {
+                    wasm.push(byte);
+                    break;
+                }
+                wasm.push(byte | 0x80);
+            }
+            wasm.extend(payload);
+        }
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let debug_info = debug_info_of(&env, &wasm, "test");
+        let fid = debug_info.fid_of_name("test").unwrap();
+
+        let locations = (0..5)
+            .map(|iid| {
+                (
+                    debug_info.offset(fid, iid),
+                    debug_info
+                        .location(fid, iid)
+                        .map(|location| location.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            locations,
+            [(3, 10), (5, 10), (6, 11), (8, 11), (9, 11)]
+                .into_iter()
+                .map(|(offset, line)| (Some(offset), Some(format!("src/main.c:{}", line))))
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            debug_info
+                .function_location(fid)
+                .map(|location| location.line),
+            Some(10)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::pairing::bn256::Fr;
+    use specs::mtable::AccessType;
+
+    use crate::circuits::diagnose::DiagnosticReport;
+    use crate::circuits::diagnose::FailureTable;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::compile_then_execute_wasm;
+    use crate::test::test_circuit_mock;
+
+    #[test]
+    fn test_diagnose_tampered_etable() {
+        let textual_repr = r#"
+        (module
+            (func (export "test")
+              i32.const 1
+              i32.const 2
+              i32.add
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let mut trace =
+            compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        let tampered = {
+            let entry = &mut trace.tables.execution_tables.etable.entries_mut()[1];
+            entry.allocated_memory_pages += 1;
+            entry.eid
+        };
+
+        let report = test_circuit_mock::<Fr>(trace)
+            .unwrap_err()
+            .downcast::<DiagnosticReport>()
+            .unwrap();
+
+        assert!(report.failures.iter().any(|failure| {
+            failure.table == FailureTable::Etable
+                && failure.gate.as_deref() == Some("c5f. mpages change")
+                && failure.etable_entry.as_ref().map_or(false, |entry| {
+                    entry.eid + 1 == tampered || entry.eid == tampered
+                })
+        }));
+    }
+
+    #[test]
+    fn test_diagnose_tampered_mtable() {
+        let textual_repr = r#"
+        (module
+            (func (export "test")
+              i32.const 1
+              i32.const 2
+              i32.add
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let mut trace =
+            compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        // Move the second write of a stack slot before the first one.
+        {
+            let entries = trace.tables.execution_tables.mtable.entries_mut();
+            let writes = entries
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.atype != AccessType::Read)
+                .map(|(index, _)| index)
+                .collect::<Vec<_>>();
+            let (first, second) = writes
+                .windows(2)
+                .map(|pair| (pair[0], pair[1]))
+                .find(|(first, second)| {
+                    entries[*first].ltype == entries[*second].ltype
+                        && entries[*first].offset == entries[*second].offset
+                })
+                .unwrap();
+
+            entries[second].eid = entries[first].eid;
+        }
+
+        let report = test_circuit_mock::<Fr>(trace)
+            .unwrap_err()
+            .downcast::<DiagnosticReport>()
+            .unwrap();
+
+        assert!(
+            report.failures.iter().any(|failure| {
+                failure.table == FailureTable::Mtable
+                    && failure.etable_entry.is_none()
+                    && failure.mtable_entry.is_some()
+            }),
+            "{}",
+            report
+        );
+    }
+}
@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod tests {
+    use halo2_proofs::dev::MockProver;
+    use halo2_proofs::pairing::bn256::Bn256;
+    use halo2_proofs::pairing::bn256::Fr;
+    use specs::imtable::InitMemoryTable;
+    use specs::mtable::LocationType;
+
+    use crate::circuits::config::max_image_table_rows;
+    use crate::circuits::config::max_init_memory_table_rows;
+    use crate::circuits::config::zkwasm_k;
+    use crate::circuits::config::CircuitParams;
+    use crate::circuits::image_table::image_table_lookup_rows;
+    use crate::circuits::image_table::init_memory_table_rows;
+    use crate::circuits::TestCircuit;
+    use crate::foreign::context::circuits::context_instances;
+    use crate::foreign::wasm_input_helper::circuits::public_instances;
+    use crate::loader::err::Error;
+    use crate::loader::err::PreCheckErr;
+    use crate::loader::ZkWasmLoader;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::test_circuit_with_env;
+
+    /// A zkmain of `pairs` pairs of `i32.const 0` and `drop` followed by the return.
+    fn zkmain_of_instructions(pairs: usize) -> Vec<u8> {
+        let textual_repr = format!(
+            r#"
+        (module
+            (func (export "zkmain")
+              {}
+            )
+           )
+        "#,
+            "i32.const 0\ndrop\n".repeat(pairs)
+        );
+
+        wabt::wat2wasm(textual_repr).expect("failed to parse wat")
+    }
+
+    #[test]
+    fn test_image_table_overflow() {
+        let available = max_image_table_rows() as usize;
+
+        let pairs = available / 2;
+
+        let err = ZkWasmLoader::<Bn256>::new(zkwasm_k(), zkmain_of_instructions(pairs), vec![])
+            .err()
+            .unwrap();
+
+        match err.downcast_ref::<Error>() {
+            Some(Error::PreCheck(PreCheckErr::ImageTableOverflow {
+                k,
+                required,
+                available: reported,
+            })) => {
+                assert_eq!(*k, zkwasm_k());
+                assert_eq!(*reported, available);
+                // A leading zero row of each table, the instructions and the return.
+                assert_eq!(*required, 2 + 2 * pairs + 1);
+            }
+            _ => panic!("unexpected error: {:?}", err),
+        }
+    }
+
+    #[test]
+    fn test_image_table_fits() {
+        let pairs = (max_image_table_rows() as usize - 3) / 2;
+
+        assert!(
+            ZkWasmLoader::<Bn256>::new(zkwasm_k(), zkmain_of_instructions(pairs), vec![]).is_ok()
+        );
+    }
+
+    /// Escape `bytes` for a data segment of the text format.
+    fn data_string(bytes: impl Iterator<Item = u8>) -> String {
+        bytes.map(|byte| format!("\\{:02x}", byte)).collect()
+    }
+
+    #[test]
+    fn test_sparse_init_memory() {
+        let textual_repr = format!(
+            r#"
+        (module
+            (memory 16)
+            (data (i32.const 0) "{}")
+            (data (i32.const 1000000) "{}")
+
+            (func (export "test")
+              i32.const 0
+              i64.load
+              i32.const 500000
+              i64.load
+              i64.add
+              i32.const 1000000
+              i64.load
+              i64.add
+              drop
+            )
+           )
+        "#,
+            data_string([1, 2, 3].into_iter()),
+            data_string([4, 5, 6].into_iter())
+        );
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let trace = test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        // Only the two non-zero words are kept, the heap takes no row of the init memory column.
+        let tables = &trace.tables.compilation_tables;
+        assert_eq!(
+            tables.imtable.heap_words().keys().collect::<Vec<_>>(),
+            vec![&0, &125000]
+        );
+        assert_eq!(init_memory_table_rows(tables), 1);
+    }
+
+    #[test]
+    fn test_large_init_memory() {
+        // Twice the words the init memory column holds, 2 MB at K = 18.
+        let words = 2 * max_init_memory_table_rows() as u64;
+
+        let textual_repr = format!(
+            r#"
+        (module
+            (memory 64)
+            (data (i32.const 0) "{}")
+
+            (func (export "test")
+              i32.const 8
+              i64.load
+              i32.const {}
+              i64.load
+              i64.add
+              drop
+            )
+           )
+        "#,
+            data_string((1..=words).flat_map(u64::to_le_bytes)),
+            (words - 1) * 8
+        );
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let trace = test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        let tables = &trace.tables.compilation_tables;
+        assert_eq!(tables.imtable.heap_words().len(), words as usize);
+        assert!(init_memory_table_rows(tables) < max_init_memory_table_rows() as usize);
+        assert!(image_table_lookup_rows(tables) < max_image_table_rows() as usize);
+    }
+
+    #[test]
+    fn test_heap_init_bound_to_memory_root() {
+        let textual_repr = r#"
+        (module
+            (memory 1)
+            (data (i32.const 8) "\01")
+
+            (func (export "test")
+              i32.const 8
+              i64.load
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let trace = test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        // The image holds another initial word than the one read by the trace.
+        let mut tables = trace.tables.clone();
+        let entries = tables
+            .compilation_tables
+            .imtable
+            .entries()
+            .iter()
+            .cloned()
+            .map(|mut entry| {
+                if entry.ltype == LocationType::Heap && entry.start_offset == 1 {
+                    entry.value = 2;
+                }
+                entry
+            })
+            .collect();
+        tables.compilation_tables.imtable = InitMemoryTable::new(entries, zkwasm_k());
+
+        let circuit = TestCircuit::<Fr>::new(
+            tables,
+            trace.public_inputs_and_outputs.clone(),
+            CircuitParams::default(),
+        );
+        let instances = vec![
+            public_instances(&trace.public_inputs_and_outputs, false),
+            context_instances(&[], &[]),
+        ];
+
+        assert!(MockProver::run(zkwasm_k(), &circuit, instances)
+            .unwrap()
+            .verify()
+            .is_err());
+    }
+}
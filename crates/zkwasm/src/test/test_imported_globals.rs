@@ -0,0 +1,116 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+
+use halo2_proofs::pairing::bn256::Bn256;
+use wasmi::ImportsBuilder;
+use wasmi::RuntimeValue;
+
+use crate::circuits::config::zkwasm_k;
+use crate::loader::ExecutionArg;
+use crate::loader::ZkWasmLoader;
+use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::host::record::HostCallMode;
+use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+use crate::runtime::WasmInterpreter;
+use crate::test::test_circuit_with_env;
+
+const TEXTUAL_REPR: &str = r#"
+        (module
+            (import "env" "__memory_base" (global $memory_base i32))
+            (import "env" "seed" (global $seed i64))
+            (memory 1)
+
+            (global $offset (export "offset") i32 (global.get $memory_base))
+            (export "memory_base" (global $memory_base))
+
+            (func (export "test")
+              global.get $offset
+              global.get $seed
+              i64.const 1
+              i64.add
+              i64.store
+            )
+           )
+        "#;
+
+#[test]
+fn test_imported_globals() {
+    let wasm = wabt::wat2wasm(TEXTUAL_REPR).expect("failed to parse wat");
+
+    let mut env = HostEnv::new();
+    env.register_global("__memory_base", RuntimeValue::I32(1024));
+    env.register_global("seed", RuntimeValue::I64(41));
+    env.finalize();
+
+    let trace = test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+    assert_eq!(
+        trace.exported_globals,
+        BTreeMap::from([
+            ("memory_base".to_owned(), RuntimeValue::I32(1024)),
+            ("offset".to_owned(), RuntimeValue::I32(1024)),
+        ])
+    );
+}
+
+#[test]
+fn test_unresolved_imported_global() {
+    let wasm = wabt::wat2wasm(TEXTUAL_REPR).expect("failed to parse wat");
+    let module = wasmi::Module::from_buffer(&wasm).expect("failed to load wasm");
+
+    // `seed` is missing and `__memory_base` is of the wrong type.
+    let mut env = HostEnv::new();
+    env.register_global("__memory_base", RuntimeValue::I64(1024));
+    env.finalize();
+
+    let imports = ImportsBuilder::new().with_resolver("env", &env);
+
+    assert!(WasmInterpreter::compile(
+        &module,
+        &imports,
+        &env.function_description_table(),
+        "test",
+        &vec![],
+    )
+    .is_err());
+}
+
+#[test]
+fn test_loader_imported_globals() {
+    let textual_repr = r#"
+        (module
+            (import "env" "__stack_pointer" (global $stack_pointer i32))
+            (memory 1)
+
+            (func (export "zkmain")
+              global.get $stack_pointer
+              i32.const 8
+              i32.sub
+              i64.const 7
+              i64.store
+            )
+           )
+        "#;
+
+    let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+    let loader = ZkWasmLoader::<Bn256>::new_with_imported_globals(
+        zkwasm_k(),
+        wasm,
+        vec![],
+        BTreeMap::from([("__stack_pointer".to_owned(), RuntimeValue::I32(4096))]),
+    )
+    .unwrap();
+
+    let (circuit, instances) = loader
+        .circuit_with_witness(ExecutionArg {
+            public_inputs: vec![],
+            private_inputs: vec![],
+            context_inputs: vec![],
+            context_outputs: Arc::new(Mutex::new(vec![])),
+            host_call_mode: HostCallMode::Native,
+        })
+        .unwrap();
+
+    loader.mock_test(&circuit, &instances).unwrap();
+}
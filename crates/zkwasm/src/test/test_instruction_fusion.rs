@@ -0,0 +1,70 @@
+#[cfg(test)]
+mod tests {
+    use specs::itable::Opcode;
+
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::test_circuit_with_env;
+
+    #[test]
+    fn test_instruction_fusion() {
+        let textual_repr = r#"
+        (module
+            (func (export "test")
+              (local i32 i64)
+              i32.const 3
+              i32.const 4
+              i32.mul
+              local.set 0
+
+              i64.const 10
+              local.get 1
+              i64.sub
+              drop
+
+              ;; The branch targets the i32.add fused with the preceding i32.const.
+              local.get 0
+              (block (result i32)
+                i32.const 1
+                i32.const 1
+                br_if 0
+                drop
+                i32.const 2
+              )
+              i32.add
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let trace = test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        let itable = trace.tables.compilation_tables.itable.entries();
+        assert!(itable
+            .iter()
+            .any(|entry| matches!(entry.opcode, Opcode::BinConst { .. })));
+        assert!(itable
+            .iter()
+            .any(|entry| matches!(entry.opcode, Opcode::BinLocal { .. })));
+
+        let etable = trace.tables.execution_tables.etable.entries();
+        assert!(etable
+            .iter()
+            .any(|entry| matches!(entry.inst.opcode, Opcode::BinConst { .. })));
+        assert!(etable
+            .iter()
+            .any(|entry| matches!(entry.inst.opcode, Opcode::BinLocal { .. })));
+        assert!(etable
+            .iter()
+            .any(|entry| matches!(entry.inst.opcode, Opcode::Bin { .. })));
+        assert!(etable
+            .iter()
+            .enumerate()
+            .all(|(index, entry)| entry.eid == index as u32 + 1));
+    }
+}
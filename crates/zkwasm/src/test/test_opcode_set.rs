@@ -0,0 +1,64 @@
+use halo2_proofs::pairing::bn256::Fr;
+use halo2_proofs::plonk::Circuit;
+use halo2_proofs::plonk::ConstraintSystem;
+
+use super::compile_then_execute_wasm;
+use super::test_circuit_mock_with_params;
+use crate::circuits::config::with_circuit_params;
+use crate::circuits::config::CircuitParams;
+use crate::circuits::TestCircuit;
+use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+
+/// Advice columns of the event table configured with `params`.
+fn etable_columns(params: &CircuitParams) -> usize {
+    with_circuit_params(params, || {
+        TestCircuit::<Fr>::configure(&mut ConstraintSystem::default())
+    })
+    .etable
+    .cell_profiler
+    .utilization()
+    .iter()
+    .map(|utilization| utilization.columns)
+    .sum()
+}
+
+#[test]
+fn test_opcode_set_from_image() {
+    let textual_repr = r#"
+        (module
+            (func (export "test")
+              (local i32)
+              i32.const 1
+              local.set 0
+            )
+           )
+        "#;
+
+    let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+    let mut env = HostEnv::new();
+    env.finalize();
+
+    let execution_result =
+        compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+    let params = CircuitParams {
+        opcode_set: Some(
+            execution_result
+                .tables
+                .compilation_tables
+                .itable
+                .opcode_class()
+                .into_iter()
+                .collect(),
+        ),
+        ..CircuitParams::default()
+    };
+
+    let full_columns = etable_columns(&CircuitParams::default());
+    let columns = etable_columns(&params);
+
+    test_circuit_mock_with_params::<Fr>(execution_result, params).unwrap();
+    assert!(columns < full_columns);
+}
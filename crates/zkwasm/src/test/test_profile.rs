@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use crate::loader::debug_info::DebugInfo;
+    use crate::profile::function_profile::FunctionProfile;
+    use crate::profile::time_profile::HostFunctionTime;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::compile_then_execute_wasm;
+    use crate::test::test_circuit_with_env;
+
+    #[test]
+    fn test_function_profile() {
+        let textual_repr = r#"
+        (module
+            (func $inner (result i32)
+              i32.const 1
+              i32.const 3
+              i32.and
+            )
+
+            (func (export "test")
+              call $inner
+              drop
+              call $inner
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let trace = compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        let profile = FunctionProfile::new(&trace.tables, &DebugInfo::default());
+
+        assert_eq!(profile.functions.len(), 2);
+        assert_eq!(
+            profile.total.etable_rows,
+            profile
+                .functions
+                .iter()
+                .map(|function| function.self_cost.etable_rows)
+                .sum::<usize>()
+        );
+
+        let entry_fid = trace.tables.compilation_tables.fid_of_entry;
+        let entry = profile
+            .functions
+            .iter()
+            .find(|function| function.fid == entry_fid)
+            .unwrap();
+        let inner = profile
+            .functions
+            .iter()
+            .find(|function| function.fid != entry_fid)
+            .unwrap();
+
+        assert_eq!(entry.calls, 1);
+        assert_eq!(inner.calls, 2);
+        assert!(inner.self_cost.bit_table_rows > 0);
+        assert_eq!(entry.total_cost.total(), profile.total.total());
+
+        let folded = profile.folded_stacks();
+        assert!(folded.contains(&format!("{};{} ", entry.name, inner.name)));
+    }
+
+    #[test]
+    fn test_time_profile() {
+        let textual_repr = r#"
+        (module
+            (func (export "test")
+              i32.const 1
+              i32.const 3
+              i32.and
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let trace = test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        let report = trace.time_profile.with_assignment_timings();
+        assert!(report.host_functions.is_empty());
+        assert!(report.table_assignment_ms.contains_key("Assign etable"));
+        assert!(report.table_assignment_ms.contains_key("Assign mtable"));
+
+        let mut time = HostFunctionTime::default();
+        time.record(std::time::Duration::from_micros(10));
+        time.record(std::time::Duration::from_micros(20));
+        assert_eq!(time.calls, 2);
+        assert_eq!(time.total_us, 30);
+        assert_eq!(time.mean_us, 15);
+    }
+}
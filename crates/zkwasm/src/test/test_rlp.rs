@@ -4,6 +4,7 @@ use std::sync::Mutex;
 use crate::circuits::TestCircuit;
 use crate::loader::ExecutionArg;
 use crate::loader::ZkWasmLoader;
+use crate::runtime::host::record::HostCallMode;
 
 use anyhow::Result;
 use halo2_proofs::pairing::bn256::Bn256;
@@ -156,6 +157,7 @@ fn build_circuit() -> Result<(ZkWasmLoader<Bn256>, TestCircuit<Fr>, Vec<Fr>)> {
         private_inputs,
         context_inputs: vec![],
         context_outputs: Arc::new(Mutex::new(vec![])),
+        host_call_mode: HostCallMode::Native,
     })?;
 
     Ok((loader, circuit, instances))
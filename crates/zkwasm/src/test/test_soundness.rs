@@ -0,0 +1,392 @@
+//! Soundness checks: targeted mutations of an honest trace, applied without the tracer,
+//! must be rejected by the mock prover.
+//!
+//! Each mutation keeps the trace assignable, so that it is rejected by a constraint rather
+//! than by a panic of the assignment.
+
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::panic::catch_unwind;
+use std::panic::AssertUnwindSafe;
+use std::rc::Rc;
+
+use halo2_proofs::dev::MockProver;
+use halo2_proofs::pairing::bn256::Fr;
+use serde_json::Value;
+use specs::etable::EventTableEntry;
+use specs::external_host_call_table::ExternalHostCallSignature;
+use specs::step::StepInfo;
+use specs::Tables;
+
+use crate::circuits::config::with_circuit_params;
+use crate::circuits::config::zkwasm_k;
+use crate::circuits::config::CircuitParams;
+use crate::circuits::diagnose::DiagnosticReport;
+use crate::circuits::TestCircuit;
+use crate::foreign::context::circuits::assign::ExtractContextFromTrace;
+use crate::foreign::context::circuits::context_instances;
+use crate::foreign::wasm_input_helper::circuits::public_instances;
+use crate::loader::debug_info::DebugInfo;
+use crate::runtime::host::host_env::HostEnv;
+use crate::runtime::host::ForeignContext;
+use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+use crate::runtime::ExecutionResult;
+use crate::test::test_circuit_with_env;
+
+/// A targeted change of an honest trace.
+struct Mutation {
+    kind: &'static str,
+    name: String,
+    apply: Box<dyn Fn(&mut Tables)>,
+}
+
+impl Mutation {
+    fn new(kind: &'static str, name: String, apply: impl Fn(&mut Tables) + 'static) -> Self {
+        Mutation {
+            kind,
+            name,
+            apply: Box::new(apply),
+        }
+    }
+}
+
+enum Verdict {
+    /// The mock prover rejected the mutation, with the failed constraints.
+    Rejected(BTreeSet<String>),
+    /// The mutation went unnoticed.
+    Accepted,
+    /// Assigning the mutated trace panicked, the mutation must be rewritten to be assignable.
+    Inconclusive(String),
+}
+
+struct MutationReport {
+    kind: &'static str,
+    name: String,
+    verdict: Verdict,
+}
+
+impl Display for MutationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.verdict {
+            Verdict::Rejected(constraints) => write!(
+                f,
+                "rejected  {}: {}",
+                self.name,
+                constraints.iter().cloned().collect::<Vec<_>>().join(", ")
+            ),
+            Verdict::Accepted => write!(f, "ACCEPTED  {}", self.name),
+            Verdict::Inconclusive(reason) => write!(f, "panicked  {}: {}", self.name, reason),
+        }
+    }
+}
+
+/// Mock the circuit of each mutated trace against the instances of the honest one.
+fn check_mutations(
+    honest: &ExecutionResult<wasmi::RuntimeValue>,
+    mutations: Vec<Mutation>,
+) -> Vec<MutationReport> {
+    let instances: Vec<Vec<Fr>> = {
+        let etable = &honest.tables.execution_tables.etable;
+
+        vec![
+            public_instances(&honest.public_inputs_and_outputs, false),
+            context_instances(&etable.get_context_inputs(), &etable.get_context_outputs()),
+        ]
+    };
+
+    mutations
+        .into_iter()
+        .map(|mutation| {
+            let mut tables = honest.tables.clone();
+            (mutation.apply)(&mut tables);
+
+            let circuit: TestCircuit<Fr> = TestCircuit::new(
+                tables,
+                honest.public_inputs_and_outputs.clone(),
+                CircuitParams::default(),
+            );
+
+            let result = catch_unwind(AssertUnwindSafe(|| {
+                with_circuit_params(&circuit.params, || {
+                    MockProver::run(zkwasm_k(), &circuit, instances.clone())
+                })
+                .unwrap()
+                .verify()
+            }));
+
+            let verdict = match result {
+                Ok(Ok(())) => Verdict::Accepted,
+                Ok(Err(failures)) => Verdict::Rejected(
+                    DiagnosticReport::new(&circuit, &DebugInfo::default(), failures)
+                        .failures
+                        .into_iter()
+                        .map(|failure| failure.gate.unwrap_or(failure.description))
+                        .collect(),
+                ),
+                Err(panic) => Verdict::Inconclusive(
+                    panic
+                        .downcast_ref::<String>()
+                        .cloned()
+                        .or_else(|| panic.downcast_ref::<&str>().map(|s| s.to_string()))
+                        .unwrap_or_default(),
+                ),
+            };
+
+            MutationReport {
+                kind: mutation.kind,
+                name: mutation.name,
+                verdict,
+            }
+        })
+        .collect()
+}
+
+/// Collect the paths of the numbers in `value`.
+fn number_paths(value: &Value, path: &mut Vec<String>, paths: &mut Vec<Vec<String>>) {
+    match value {
+        Value::Number(_) => paths.push(path.clone()),
+        Value::Array(values) => values.iter().enumerate().for_each(|(index, value)| {
+            path.push(index.to_string());
+            number_paths(value, path, paths);
+            path.pop();
+        }),
+        Value::Object(fields) => fields.iter().for_each(|(key, value)| {
+            path.push(key.clone());
+            number_paths(value, path, paths);
+            path.pop();
+        }),
+        _ => (),
+    }
+}
+
+fn flip_number(value: &mut Value, path: &[String]) {
+    let number = path.iter().fold(value, |value, key| match value {
+        Value::Array(values) => &mut values[key.parse::<usize>().unwrap()],
+        _ => &mut value[key.as_str()],
+    });
+
+    *number = match number.as_u64() {
+        Some(n) => Value::from(n ^ 1),
+        None => Value::from(number.as_i64().unwrap() ^ 1),
+    };
+}
+
+/// Flip the lowest bit of the value or result of the step of each etable entry.
+///
+/// Steps without one are skipped: their other fields (indexes, depths, addresses) select the
+/// memory and jump entries of the step, changing them breaks the assignment instead.
+fn etable_value_mutations(tables: &Tables) -> Vec<Mutation> {
+    tables
+        .execution_tables
+        .etable
+        .entries()
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| {
+            let step = serde_json::to_value(&entry.step_info).unwrap();
+
+            let mut paths = vec![];
+            number_paths(&step, &mut vec![], &mut paths);
+
+            let path = paths.into_iter().find(|path| {
+                path.last()
+                    .map_or(false, |key| key == "value" || key == "result")
+            })?;
+
+            Some(Mutation::new(
+                "etable value",
+                format!(
+                    "etable entry {} ({:?}): flip {}",
+                    index,
+                    entry.inst.opcode,
+                    path.join(".")
+                ),
+                move |tables| {
+                    let entry = &mut tables.execution_tables.etable.entries_mut()[index];
+
+                    let mut step = serde_json::to_value(&entry.step_info).unwrap();
+                    flip_number(&mut step, &path);
+                    entry.step_info = serde_json::from_value(step).unwrap();
+                },
+            ))
+        })
+        .collect()
+}
+
+/// Swap adjacent mtable entries of different locations.
+///
+/// The entries of a location keep their order, as the etable looks them up by their eids.
+fn mtable_reorder_mutations(tables: &Tables) -> Vec<Mutation> {
+    let entries = tables.execution_tables.mtable.entries();
+
+    (1..entries.len())
+        .filter(|index| {
+            let (prev, curr) = (&entries[index - 1], &entries[*index]);
+
+            !prev.is_same_location(curr) && prev.value != curr.value
+        })
+        .map(|index| {
+            Mutation::new(
+                "mtable reorder",
+                format!("mtable: swap entries {} and {}", index - 1, index),
+                move |tables| {
+                    tables
+                        .execution_tables
+                        .mtable
+                        .entries_mut()
+                        .swap(index - 1, index)
+                },
+            )
+        })
+        .collect()
+}
+
+/// Return to the instruction following the one after the call.
+fn jtable_return_mutations(tables: &Tables) -> Vec<Mutation> {
+    (0..tables.execution_tables.jtable.entries().len())
+        .map(|index| {
+            Mutation::new(
+                "jtable return",
+                format!("jtable entry {}: shift return address", index),
+                move |tables| {
+                    tables.execution_tables.jtable.entries_mut()[index].inst.iid += 1;
+                },
+            )
+        })
+        .collect()
+}
+
+/// Redirect each external host call to another operation.
+fn external_host_call_mutations(tables: &Tables) -> Vec<Mutation> {
+    fn is_external_host_call(entry: &EventTableEntry) -> bool {
+        matches!(entry.step_info, StepInfo::ExternalHostCall { .. })
+    }
+
+    tables
+        .execution_tables
+        .etable
+        .entries()
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| is_external_host_call(entry))
+        .map(|(index, _)| {
+            Mutation::new(
+                "external host call",
+                format!("external host call at etable entry {}: change op", index),
+                move |tables| {
+                    let entry = &mut tables.execution_tables.etable.entries_mut()[index];
+
+                    if let StepInfo::ExternalHostCall { op, .. } = &mut entry.step_info {
+                        *op += 1;
+                    }
+                },
+            )
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Context {
+    acc: u64,
+}
+impl ForeignContext for Context {}
+
+fn honest_execution() -> ExecutionResult<wasmi::RuntimeValue> {
+    let textual_repr = r#"
+        (module
+            (import "env" "foreign_push" (func $push (param i64)))
+            (import "env" "foreign_pop" (func $pop (result i64)))
+            (memory 1)
+
+            (func $double (param i64) (result i64)
+              local.get 0
+              local.get 0
+              i64.add)
+
+            (func (export "test")
+              i32.const 8
+              i64.const 21
+              call $double
+              i64.store
+              i32.const 8
+              i64.load
+              call $push
+              call $pop
+              drop))
+        "#;
+
+    let mut env = HostEnv::new();
+
+    let plugin = env
+        .external_env
+        .register_plugin("foreign_soundness", Box::new(Context::default()));
+    env.external_env.register_function(
+        "foreign_push",
+        0,
+        ExternalHostCallSignature::Argument,
+        plugin.clone(),
+        Rc::new(
+            |context: &mut dyn ForeignContext, args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+                context.acc += args.nth::<u64>(0);
+
+                None
+            },
+        ),
+    );
+    env.external_env.register_function(
+        "foreign_pop",
+        1,
+        ExternalHostCallSignature::Return,
+        plugin,
+        Rc::new(
+            |context: &mut dyn ForeignContext, _args: wasmi::RuntimeArgs| {
+                let context = context.downcast_mut::<Context>().unwrap();
+
+                Some(wasmi::RuntimeValue::I64(context.acc as i64))
+            },
+        ),
+    );
+    env.finalize();
+
+    let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+    test_circuit_with_env(env, WasmRuntimeIO::empty(), wasm, "test").unwrap()
+}
+
+#[test]
+fn test_tampered_witness_rejected() {
+    let honest = honest_execution();
+
+    let families = [
+        etable_value_mutations(&honest.tables),
+        mtable_reorder_mutations(&honest.tables),
+        jtable_return_mutations(&honest.tables),
+        external_host_call_mutations(&honest.tables),
+    ];
+
+    assert!(families.iter().all(|mutations| !mutations.is_empty()));
+
+    let mutations = families.into_iter().flatten().collect::<Vec<_>>();
+
+    let reports = check_mutations(&honest, mutations);
+    let summary = reports
+        .iter()
+        .map(|report| report.to_string())
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    // A panic says nothing about the constraints, each mutation must be rejected by one.
+    let unchecked = reports
+        .iter()
+        .filter(|report| !matches!(report.verdict, Verdict::Rejected(_)))
+        .map(|report| format!("{}: {}", report.kind, report.name))
+        .collect::<Vec<_>>();
+
+    assert!(
+        unchecked.is_empty(),
+        "mutations not rejected by a constraint: {:?}\n{}",
+        unchecked,
+        summary
+    );
+}
@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+    use specs::dump::TableDumpFormat;
+    use specs::dump::TableDumpOption;
+    use specs::dump::TableKind;
+
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::test::compile_then_execute_wasm;
+
+    #[test]
+    fn test_table_dump() {
+        let textual_repr = r#"
+        (module
+            (func (export "test")
+              i32.const 1
+              i32.const 2
+              i32.add
+              drop
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let trace = compile_then_execute_wasm(env, WasmRuntimeIO::empty(), wasm, "test").unwrap();
+
+        let dir = std::env::temp_dir().join("zkwasm_test_table_dump");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        trace
+            .tables
+            .dump(&TableDumpOption {
+                dir: dir.clone(),
+                tables: vec![TableKind::Etable, TableKind::Mtable],
+                format: TableDumpFormat::JsonLines,
+            })
+            .unwrap();
+
+        let etable = std::fs::read_to_string(dir.join("etable.jsonl")).unwrap();
+        assert_eq!(
+            etable.lines().count(),
+            trace.tables.execution_tables.etable.entries().len()
+        );
+        assert!(dir.join("mtable.jsonl").exists());
+        assert!(!dir.join("itable.jsonl").exists());
+
+        trace
+            .tables
+            .dump(&TableDumpOption {
+                format: TableDumpFormat::Binary,
+                ..TableDumpOption::new(dir.clone())
+            })
+            .unwrap();
+        assert!(dir.join("external_host_table.bin").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
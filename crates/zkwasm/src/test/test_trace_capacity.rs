@@ -0,0 +1,164 @@
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use halo2_proofs::pairing::bn256::Bn256;
+    use wasmi::ImportsBuilder;
+
+    use crate::circuits::capacity::CapacityTable;
+    use crate::circuits::capacity::TraceCapacity;
+    use crate::circuits::capacity::TraceCapacityError;
+    use crate::circuits::config::zkwasm_k;
+    use crate::loader::ExecutionArg;
+    use crate::loader::ZkWasmLoader;
+    use crate::runtime::host::host_env::HostEnv;
+    use crate::runtime::host::record::HostCallMode;
+    use crate::runtime::wasmi_interpreter::Execution;
+    use crate::runtime::wasmi_interpreter::WasmRuntimeIO;
+    use crate::runtime::WasmInterpreter;
+
+    #[test]
+    fn test_step_limit() {
+        let textual_repr = r#"
+        (module
+            (func (export "test")
+              (local i32)
+              (loop
+                local.get 0
+                i32.const 1
+                i32.add
+                local.tee 0
+                i32.const 100
+                i32.ne
+                br_if 0
+              )
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+        let module = wasmi::Module::from_buffer(&wasm).expect("failed to load wasm");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let imports = ImportsBuilder::new().with_resolver("env", &env);
+        let compiled_module = WasmInterpreter::compile(
+            &module,
+            &imports,
+            &env.function_description_table(),
+            "test",
+            &vec![],
+        )
+        .unwrap()
+        .with_step_limit(Some(100));
+
+        let err = compiled_module
+            .run(&mut env, WasmRuntimeIO::empty())
+            .err()
+            .unwrap();
+        let err = err.downcast_ref::<TraceCapacityError>().unwrap();
+
+        assert_eq!(err.table, CapacityTable::Etable);
+        assert_eq!(err.capacity, 100);
+        assert!(err.entries > 100);
+    }
+
+    #[test]
+    fn test_step_limit_without_host_calls() {
+        let textual_repr = r#"
+        (module
+            (func (export "zkmain")
+              (loop
+                br 0
+              )
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+        let loader = ZkWasmLoader::<Bn256>::new(zkwasm_k(), wasm, vec![])
+            .unwrap()
+            .with_step_limit(Some(1000));
+
+        let err = loader
+            .run(ExecutionArg {
+                public_inputs: vec![],
+                private_inputs: vec![],
+                context_inputs: vec![],
+                context_outputs: Arc::new(Mutex::new(vec![])),
+                host_call_mode: HostCallMode::Native,
+            })
+            .err()
+            .unwrap();
+        let err = err.downcast_ref::<TraceCapacityError>().unwrap();
+
+        assert_eq!(err.table, CapacityTable::Etable);
+        assert_eq!(err.capacity, 1000);
+        assert!(err.entries > 1000);
+    }
+
+    #[test]
+    fn test_memory_tree_capacity() {
+        // Reads a word every 4096 bytes of 4 MB, the paths of the leaves only merge near the
+        // root of the memory tree.
+        let textual_repr = r#"
+        (module
+            (memory 64)
+
+            (func (export "test")
+              (local i32)
+              (loop
+                local.get 0
+                i64.load
+                drop
+                local.get 0
+                i32.const 4096
+                i32.add
+                local.tee 0
+                i32.const 4194304
+                i32.ne
+                br_if 0
+              )
+            )
+           )
+        "#;
+
+        let wasm = wabt::wat2wasm(textual_repr).expect("failed to parse wat");
+        let module = wasmi::Module::from_buffer(&wasm).expect("failed to load wasm");
+
+        let mut env = HostEnv::new();
+        env.finalize();
+
+        let imports = ImportsBuilder::new().with_resolver("env", &env);
+        let compiled_module = WasmInterpreter::compile(
+            &module,
+            &imports,
+            &env.function_description_table(),
+            "test",
+            &vec![],
+        )
+        .unwrap();
+
+        let err = compiled_module
+            .run(&mut env, WasmRuntimeIO::empty())
+            .err()
+            .unwrap();
+        let err = err.downcast_ref::<TraceCapacityError>().unwrap();
+
+        assert_eq!(err.table, CapacityTable::MemoryTree);
+        assert_eq!(err.capacity, TraceCapacity::new().memory_tree);
+    }
+
+    #[test]
+    fn test_trace_capacity() {
+        let capacity = TraceCapacity::new();
+
+        assert!(capacity.etable > 0);
+        assert!(capacity.mtable > 0);
+        assert!(capacity.jtable > 0);
+        assert!(capacity.memory_tree > 0);
+        assert_eq!(capacity.step_budget(), capacity.etable * 2);
+    }
+}
@@ -1,3 +1,5 @@
+use crate::circuits::config::with_circuit_params;
+use crate::circuits::config::CircuitParams;
 use crate::circuits::TestCircuit;
 use crate::circuits::ZkWasmCircuitBuilder;
 use crate::runtime::host::host_env::HostEnv;
@@ -34,13 +36,16 @@ fn setup_uniform_verifier() -> Result<(Params<G1Affine>, ProvingKey<G1Affine>)>
     let builder = ZkWasmCircuitBuilder {
         tables: execution_result.tables,
         public_inputs_and_outputs: execution_result.public_inputs_and_outputs,
+        params: CircuitParams::default(),
     };
 
     let circuit: TestCircuit<Fr> = builder.build_circuit();
 
     let params = Params::<G1Affine>::unsafe_setup::<Bn256>(K);
-    let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
-    let pk = keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail");
+    let pk = with_circuit_params(&circuit.params, || {
+        let vk = keygen_vk(&params, &circuit).expect("keygen_vk should not fail");
+        keygen_pk(&params, vk, &circuit).expect("keygen_pk should not fail")
+    });
 
     Ok((params, pk))
 }
@@ -119,6 +124,8 @@ mod tests {
 
     use super::*;
     use crate::circuits::ZkWasmCircuitBuilder;
+    use crate::foreign::context::circuits::assign::ExtractContextFromTrace;
+    use crate::foreign::context::circuits::context_instances;
 
     #[test]
     fn test_uniform_verifier() {
@@ -132,30 +139,38 @@ mod tests {
         );
 
         let instances = vec![];
+        let context_instance: Vec<Fr> = {
+            let etable = &execution_result.tables.execution_tables.etable;
+
+            context_instances(&etable.get_context_inputs(), &etable.get_context_outputs())
+        };
 
         let builder = ZkWasmCircuitBuilder {
             tables: execution_result.tables,
             public_inputs_and_outputs: execution_result.public_inputs_and_outputs,
+            params: CircuitParams::default(),
         };
 
         let proof = {
             let mut transcript = Blake2bWrite::<_, _, Challenge255<_>>::init(vec![]);
 
-            create_proof(
-                &params,
-                &uniform_verifier_pk,
-                &[builder.build_circuit()],
-                &[&[&instances]],
-                OsRng,
-                &mut transcript,
-            )
+            with_circuit_params(&builder.params, || {
+                create_proof(
+                    &params,
+                    &uniform_verifier_pk,
+                    &[builder.build_circuit()],
+                    &[&[&instances, &context_instance]],
+                    OsRng,
+                    &mut transcript,
+                )
+            })
             .expect("proof generation should not fail");
 
             transcript.finalize()
         };
 
         {
-            let public_inputs_size = 1;
+            let public_inputs_size = context_instance.len();
 
             let params_verifier: ParamsVerifier<Bn256> =
                 params.verifier(public_inputs_size).unwrap();
@@ -167,7 +182,7 @@ mod tests {
                 &params_verifier,
                 uniform_verifier_pk.get_vk(),
                 strategy,
-                &[&[&instances]],
+                &[&[&instances, &context_instance]],
                 &mut transcript,
             )
             .unwrap();